@@ -0,0 +1,129 @@
+//! Benchmarks of the stages of a ceremony on synthetic circuits of
+//! `2^exp` constraints, run with
+//!
+//! ```text
+//! cargo bench --features test-utils
+//! ```
+//!
+//! The radix files are derived from a known tau, so the benchmarks
+//! don't need the Powers of Tau on disk. Set `PHASE2_BENCH_EXPS` to a
+//! comma-separated list of exponents to measure other sizes.
+
+use bls12_381::Bls12;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+use phase2::phase1::RadixSource;
+use phase2::testing::{insecure_radix, SyntheticCircuit};
+use phase2::{verify_contribution, MPCParameters};
+
+const DEFAULT_EXPS: &[u32] = &[8, 10, 12];
+
+fn exps() -> Vec<u32> {
+    match std::env::var("PHASE2_BENCH_EXPS") {
+        Ok(exps) => exps
+            .split(',')
+            .map(|exp| {
+                exp.trim()
+                    .parse()
+                    .expect("PHASE2_BENCH_EXPS lists exponents")
+            })
+            .collect(),
+        Err(_) => DEFAULT_EXPS.to_vec(),
+    }
+}
+
+/// The serialized radix file for `2^exp`.
+fn radix(exp: u32) -> Vec<u8> {
+    let mut bytes = vec![];
+    insecure_radix::<Bls12>(exp, [exp as u8; 32])
+        .write(&mut bytes)
+        .unwrap();
+    bytes
+}
+
+fn params(exp: u32, radix: &[u8]) -> MPCParameters {
+    MPCParameters::new_from_source(SyntheticCircuit::new(exp), RadixSource::Bytes(radix)).unwrap()
+}
+
+fn bench_new(c: &mut Criterion) {
+    let mut group = c.benchmark_group("new");
+    group.sample_size(10);
+
+    for exp in exps() {
+        let radix = radix(exp);
+        group.bench_with_input(BenchmarkId::from_parameter(exp), &radix, |b, radix| {
+            b.iter(|| params(exp, radix))
+        });
+    }
+}
+
+fn bench_contribute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contribute");
+    group.sample_size(10);
+    let mut rng = ChaChaRng::from_seed([0; 32]);
+
+    for exp in exps() {
+        let params = params(exp, &radix(exp));
+        group.bench_with_input(BenchmarkId::from_parameter(exp), &params, |b, params| {
+            b.iter_batched(
+                || params.clone(),
+                |mut params| params.contribute(&mut rng),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn bench_verify_contribution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_contribution");
+    group.sample_size(10);
+
+    for exp in exps() {
+        let before = params(exp, &radix(exp));
+        let mut after = before.clone();
+        after.contribute_with_seed([1; 32]);
+
+        group.bench_with_input(BenchmarkId::from_parameter(exp), &after, |b, after| {
+            b.iter(|| verify_contribution(&before, after).unwrap())
+        });
+    }
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+    group.sample_size(10);
+
+    for exp in exps() {
+        let mut params = params(exp, &radix(exp));
+        params.contribute_with_seed([1; 32]);
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("write", exp), &params, |b, params| {
+            b.iter(|| {
+                let mut out = Vec::with_capacity(bytes.len());
+                params.write(&mut out).unwrap();
+                out
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("read", exp), &bytes, |b, bytes| {
+            b.iter(|| MPCParameters::<Bls12>::read(&bytes[..], true).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("read_unchecked", exp),
+            &bytes,
+            |b, bytes| b.iter(|| MPCParameters::<Bls12>::read(&bytes[..], false).unwrap()),
+        );
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_new,
+    bench_contribute,
+    bench_verify_contribution,
+    bench_serialization
+);
+criterion_main!(benches);
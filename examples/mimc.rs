@@ -42,9 +42,9 @@ const MIMC_ROUNDS: usize = 322;
 fn mimc<E: Engine>(mut xl: E::Fr, mut xr: E::Fr, constants: &[E::Fr]) -> E::Fr {
     assert_eq!(constants.len(), MIMC_ROUNDS);
 
-    for i in 0..MIMC_ROUNDS {
+    for constant in constants {
         let mut tmp1 = xl;
-        tmp1.add_assign(&constants[i]);
+        tmp1.add_assign(constant);
         let mut tmp2 = tmp1;
         tmp2 = tmp2.square();
         tmp2.mul_assign(&tmp1);
@@ -171,7 +171,7 @@ fn main() {
             constants: &constants,
         };
 
-        phase2::MPCParameters::new(c).unwrap()
+        phase2::MPCParameters::<Bls12>::new(c).unwrap()
     };
 
     let old_params = params.clone();
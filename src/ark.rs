@@ -0,0 +1,236 @@
+//! Conversion of parameters to arkworks' Groth16 keys, enabled by
+//! the `ark` feature, so that provers built on `ark-groth16` can use
+//! the result of a ceremony.
+//!
+//! arkworks lays out the QAP the same way bellman does, with the
+//! constraints on the inputs after the circuit's own, so the keys
+//! only differ in encoding, and in arkworks keeping the points of
+//! the A and B queries that bellman filters away.
+
+use ark_bls12_381::{Bls12_381, Fq, Fq2};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_groth16::{ProvingKey, VerifyingKey};
+use bellman::Circuit;
+use bls12_381::Bls12;
+use std::io;
+
+use crate::{expand, KeypairAssembly, MPCParameters};
+
+/// The size of a big-endian base field element.
+const FQ_SIZE: usize = 48;
+
+fn fq(be: &[u8]) -> Fq {
+    Fq::from_be_bytes_mod_order(be)
+}
+
+fn g1(p: &bls12_381::G1Affine) -> ark_bls12_381::G1Affine {
+    if p.is_identity().into() {
+        return ark_bls12_381::G1Affine::zero();
+    }
+
+    // The uncompressed encoding is x | y, big-endian, with flags in
+    // the top bits of the first byte. The point was checked when it
+    // was read, so it's on the curve and in the subgroup.
+    let mut repr = p.to_uncompressed();
+    repr[0] &= 0x1f;
+
+    ark_bls12_381::G1Affine::new_unchecked(fq(&repr[..FQ_SIZE]), fq(&repr[FQ_SIZE..]))
+}
+
+fn g2(p: &bls12_381::G2Affine) -> ark_bls12_381::G2Affine {
+    if p.is_identity().into() {
+        return ark_bls12_381::G2Affine::zero();
+    }
+
+    // The uncompressed encoding is x.c1 | x.c0 | y.c1 | y.c0
+    let mut repr = p.to_uncompressed();
+    repr[0] &= 0x1f;
+    let c = |i: usize| fq(&repr[i * FQ_SIZE..(i + 1) * FQ_SIZE]);
+
+    ark_bls12_381::G2Affine::new_unchecked(Fq2::new(c(1), c(0)), Fq2::new(c(3), c(2)))
+}
+
+impl MPCParameters<Bls12> {
+    /// Convert the verifying key of these parameters to arkworks'.
+    pub fn to_ark_verifying_key(&self) -> VerifyingKey<Bls12_381> {
+        let vk = &self.params.vk;
+
+        VerifyingKey {
+            alpha_g1: g1(&vk.alpha_g1),
+            beta_g2: g2(&vk.beta_g2),
+            gamma_g2: g2(&vk.gamma_g2),
+            delta_g2: g2(&vk.delta_g2),
+            gamma_abc_g1: vk.ic.iter().map(g1).collect(),
+        }
+    }
+
+    /// Convert these parameters to an arkworks proving key, which
+    /// can be serialized with `ark-serialize` for arkworks-based
+    /// provers. The circuit the parameters were created for must be
+    /// supplied, as arkworks expects A and B queries with a point for
+    /// every variable, including those bellman filters away. The
+    /// prover must synthesize the same constraints, allocating the
+    /// same variables in the same order.
+    pub fn to_ark_proving_key<C>(&self, circuit: C) -> io::Result<ProvingKey<Bls12_381>>
+    where
+        C: Circuit<bls12_381::Scalar>,
+    {
+        let assembly = KeypairAssembly::synthesize(circuit)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let params = &self.params;
+        if params.vk.ic.len() != assembly.num_inputs || params.l.len() != assembly.num_aux {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "parameters don't match the circuit",
+            ));
+        }
+
+        let at = assembly.at_inputs.iter().chain(assembly.at_aux.iter());
+        let bt = assembly.bt_inputs.iter().chain(assembly.bt_aux.iter());
+        let a_density: Vec<bool> = at.map(|t| !t.is_empty()).collect();
+        let b_density: Vec<bool> = bt.map(|t| !t.is_empty()).collect();
+
+        let a = expand(&params.a, &a_density)?;
+        let b_g1 = expand(&params.b_g1, &b_density)?;
+        let b_g2 = expand(&params.b_g2, &b_density)?;
+
+        Ok(ProvingKey {
+            vk: self.to_ark_verifying_key(),
+            beta_g1: g1(&params.vk.beta_g1),
+            delta_g1: g1(&params.vk.delta_g1),
+            a_query: a.iter().map(g1).collect(),
+            b_g1_query: b_g1.iter().map(g1).collect(),
+            b_g2_query: b_g2.iter().map(g2).collect(),
+            h_query: params.h.iter().map(g1).collect(),
+            l_query: params.l.iter().map(g1).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::insecure_radix;
+    use ark_ff::BigInteger;
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Variable};
+    use bellman::{ConstraintSystem, SynthesisError};
+    use bls12_381::Scalar;
+
+    /// `x^3 + x + 5 = out`, with `out` public.
+    struct Cubic;
+
+    impl Circuit<Scalar> for Cubic {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let out = cs.alloc_input(|| "out", || Err(SynthesisError::AssignmentMissing))?;
+            let x = cs.alloc(|| "x", || Err(SynthesisError::AssignmentMissing))?;
+            let t = cs.alloc(|| "t", || Err(SynthesisError::AssignmentMissing))?;
+            let u = cs.alloc(|| "u", || Err(SynthesisError::AssignmentMissing))?;
+
+            cs.enforce(|| "x * x", |lc| lc + x, |lc| lc + x, |lc| lc + t);
+            cs.enforce(|| "t * x", |lc| lc + t, |lc| lc + x, |lc| lc + u);
+            cs.enforce(
+                || "u + x + 5",
+                |lc| lc + u + x + (Scalar::from(5), CS::one()),
+                |lc| lc + CS::one(),
+                |lc| lc + out,
+            );
+
+            Ok(())
+        }
+    }
+
+    /// `Cubic` for arkworks, with `x = 3`.
+    struct ArkCubic;
+
+    impl ConstraintSynthesizer<ark_bls12_381::Fr> for ArkCubic {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<ark_bls12_381::Fr>,
+        ) -> ark_relations::r1cs::Result<()> {
+            let fr = ark_bls12_381::Fr::from;
+            let out = cs.new_input_variable(|| Ok(fr(35)))?;
+            let x = cs.new_witness_variable(|| Ok(fr(3)))?;
+            let t = cs.new_witness_variable(|| Ok(fr(9)))?;
+            let u = cs.new_witness_variable(|| Ok(fr(27)))?;
+
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + t)?;
+            cs.enforce_constraint(lc!() + t, lc!() + x, lc!() + u)?;
+            cs.enforce_constraint(
+                lc!() + u + x + (fr(5), Variable::One),
+                lc!() + Variable::One,
+                lc!() + out,
+            )?;
+
+            Ok(())
+        }
+    }
+
+    fn params() -> MPCParameters<Bls12> {
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(3, [3; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params = MPCParameters::new_from_source(Cubic, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+        params
+    }
+
+    /// The big-endian coordinates of `p`, as its uncompressed
+    /// encoding has them.
+    fn coords(p: &impl AffineRepr<BaseField = Fq>) -> Vec<u8> {
+        let (x, y) = p.xy().unwrap();
+        [x.into_bigint().to_bytes_be(), y.into_bigint().to_bytes_be()].concat()
+    }
+
+    fn coords2(p: &impl AffineRepr<BaseField = Fq2>) -> Vec<u8> {
+        let (x, y) = p.xy().unwrap();
+        [x.c1, x.c0, y.c1, y.c0]
+            .iter()
+            .flat_map(|c| c.into_bigint().to_bytes_be())
+            .collect()
+    }
+
+    fn unflagged<const N: usize>(mut repr: [u8; N]) -> Vec<u8> {
+        repr[0] &= 0x1f;
+        repr.to_vec()
+    }
+
+    #[test]
+    fn verifying_key_matches_bellman() {
+        let params = params();
+        let vk = &params.params.vk;
+        let ark = params.to_ark_verifying_key();
+
+        let g1 = |p: &bls12_381::G1Affine| unflagged(p.to_uncompressed());
+        let g2 = |p: &bls12_381::G2Affine| unflagged(p.to_uncompressed());
+        assert_eq!(coords(&ark.alpha_g1), g1(&vk.alpha_g1));
+        assert_eq!(coords2(&ark.beta_g2), g2(&vk.beta_g2));
+        assert_eq!(coords2(&ark.gamma_g2), g2(&vk.gamma_g2));
+        assert_eq!(coords2(&ark.delta_g2), g2(&vk.delta_g2));
+        assert_eq!(ark.gamma_abc_g1.len(), vk.ic.len());
+        for (ark, ic) in ark.gamma_abc_g1.iter().zip(&vk.ic) {
+            assert_eq!(coords(ark), g1(ic));
+        }
+    }
+
+    #[test]
+    fn arkworks_proves_with_converted_parameters() {
+        let params = params();
+        let pk = params.to_ark_proving_key(Cubic).unwrap();
+        let pvk = prepare_verifying_key(&params.to_ark_verifying_key());
+
+        let fr = ark_bls12_381::Fr::from;
+        let proof = Groth16::<Bls12_381>::create_proof_with_reduction(ArkCubic, &pk, fr(7), fr(11))
+            .unwrap();
+        assert!(Groth16::<Bls12_381>::verify_proof(&pvk, &proof, &[fr(35)]).unwrap());
+        assert!(!Groth16::<Bls12_381>::verify_proof(&pvk, &proof, &[fr(36)]).unwrap());
+    }
+}
@@ -0,0 +1,148 @@
+//! Reading, writing and verifying parameters with tokio's async I/O,
+//! enabled by the `async` feature.
+//!
+//! Checking points and pairings is CPU-bound, so it's done on
+//! tokio's blocking thread pool, leaving the runtime free to serve
+//! other tasks in the meantime. These functions must be called from
+//! within a tokio runtime.
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::task;
+
+use crate::{
+    check_contribution, header, ContributionHash, FormatVersion, MPCEngine, MPCParameters,
+    VerificationError,
+};
+
+/// Runs `f` on tokio's blocking thread pool.
+async fn blocking<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Like `read`, from an `AsyncRead`. The file is read into memory
+    /// before its points are parsed (and, if `checked`, checked).
+    pub async fn read_async<R>(mut reader: R, checked: bool) -> io::Result<MPCParameters<E>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        blocking(move || MPCParameters::read(&bytes[..], checked)).await
+    }
+
+    /// Like `write`, to an `AsyncWrite`. The queries are serialized a
+    /// piece at a time, so no second copy of the parameters is held
+    /// in memory. The writer is flushed at the end.
+    pub async fn write_async<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.write_async_with_version(writer, FormatVersion::V1)
+            .await
+    }
+
+    /// Like `write_with_version`, to an `AsyncWrite`.
+    pub async fn write_async_with_version<W>(
+        &self,
+        mut writer: W,
+        version: FormatVersion,
+    ) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        // Fails up front if the version needs the circuit fingerprint
+        let pieces = self.body_pieces(version)?;
+
+        let mut header = vec![];
+        header::write_header::<E, _>(
+            &mut header,
+            version,
+            &self.cs_hash,
+            self.transcript_scheme.id(),
+        )?;
+        writer.write_all(&header).await?;
+        for piece in pieces {
+            writer.write_all(&piece).await?;
+        }
+
+        writer.flush().await
+    }
+}
+
+/// Like `verify_contribution`, for parameters that are read from
+/// `AsyncRead`s, with all curve validity and group order checks.
+pub async fn verify_contribution_async<E, R1, R2>(
+    before: R1,
+    after: R2,
+) -> Result<ContributionHash, VerificationError>
+where
+    E: MPCEngine,
+    R1: AsyncRead + Unpin,
+    R2: AsyncRead + Unpin,
+{
+    let before = MPCParameters::<E>::read_async(before, true).await?;
+    let after = MPCParameters::<E>::read_async(after, true).await?;
+
+    blocking(move || check_contribution(&before, &after)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::{insecure_radix, RandomCircuit};
+    use bls12_381::Bls12;
+
+    fn params() -> MPCParameters<Bls12> {
+        let circuit = RandomCircuit {
+            constraints: 6,
+            inputs: 1,
+            aux: 4,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(3, [3; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+        params
+    }
+
+    #[tokio::test]
+    async fn async_writes_match_sync_writes() {
+        let params = params();
+
+        for version in [FormatVersion::V1, FormatVersion::V2, FormatVersion::V3] {
+            let mut sync = vec![];
+            params.write_with_version(&mut sync, version).unwrap();
+            let mut written = vec![];
+            params
+                .write_async_with_version(&mut written, version)
+                .await
+                .unwrap();
+            assert_eq!(written, sync);
+        }
+
+        let mut sync = vec![];
+        params.write(&mut sync).unwrap();
+        let mut written = vec![];
+        params.write_async(&mut written).await.unwrap();
+        assert_eq!(written, sync);
+
+        let read = MPCParameters::<Bls12>::read_async(&written[..], true)
+            .await
+            .unwrap();
+        assert!(read == params);
+    }
+}
@@ -0,0 +1,382 @@
+//! Signed attestations of contributions, enabled by the `attest`
+//! feature.
+//!
+//! The parameters themselves only record anonymous public keys, and
+//! their layout is fixed by what bellman reads, so attestations are
+//! kept next to them (e.g. in the ceremony's transcript) and checked
+//! with `MPCParameters::verify_attested`.
+//!
+//! Independent verifiers can also sign that they verified a
+//! contribution, with a `TranscriptAttestation`. The parameters carry
+//! those, and `write_with_attestations` appends them to the file, so
+//! that the evidence that a pool of verifiers checked the ceremony
+//! is published with it.
+
+use bellman::Circuit;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::Rng;
+use std::io::{self, Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{ContributionHash, MPCEngine, MPCParameters, VerificationError};
+
+/// Separates attestations from anything else the key may sign.
+const ATTESTATION_DOMAIN: &[u8] = b"phase2 contribution attestation";
+
+/// Separates verifiers' attestations from participants'.
+const VERIFIER_DOMAIN: &[u8] = b"phase2 verifier attestation";
+
+/// Starts the verifiers' attestations after the parameters.
+const VERIFIER_MAGIC: &[u8; 4] = b"PH2V";
+
+/// A participant's Ed25519 signature over the hash of their
+/// contribution and a statement about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    /// The hash of the contribution, as `contribute` returns.
+    pub contribution: [u8; 64],
+    /// Who contributed.
+    pub name: String,
+    /// Anything else the participant wants to say, e.g. how they
+    /// generated their randomness.
+    pub statement: String,
+    /// When the attestation was made, in seconds since the Unix
+    /// epoch.
+    pub timestamp: u64,
+    /// The participant's key.
+    pub public_key: VerifyingKey,
+    /// The signature over all of the above.
+    pub signature: Signature,
+}
+
+/// The hash of a contribution, with its attestation if it has one,
+/// as returned by `MPCParameters::verify_attested`.
+pub type AttestedContribution<'a> = ([u8; 64], Option<&'a Attestation>);
+
+fn write_string<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(s.len() as u32)?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = reader.read_u32::<BigEndian>()?;
+
+    let mut bytes = vec![];
+    reader.take(u64::from(len)).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The message an attestation signs.
+fn message(contribution: &[u8; 64], name: &str, statement: &str, timestamp: u64) -> Vec<u8> {
+    let mut message = ATTESTATION_DOMAIN.to_vec();
+    message.extend_from_slice(contribution);
+    write_string(&mut message, name).unwrap();
+    write_string(&mut message, statement).unwrap();
+    message.write_u64::<BigEndian>(timestamp).unwrap();
+    message
+}
+
+impl Attestation {
+    /// Sign an attestation of `contribution`, timestamped now.
+    pub fn sign(
+        contribution: [u8; 64],
+        signing_key: &SigningKey,
+        name: &str,
+        statement: &str,
+    ) -> Attestation {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let signature = signing_key.sign(&message(&contribution, name, statement, timestamp));
+
+        Attestation {
+            contribution,
+            name: name.to_string(),
+            statement: statement.to_string(),
+            timestamp,
+            public_key: signing_key.verifying_key(),
+            signature,
+        }
+    }
+
+    /// Whether the signature is valid. This doesn't check that the
+    /// contribution is in any parameters.
+    pub fn is_valid(&self) -> bool {
+        let message = message(
+            &self.contribution,
+            &self.name,
+            &self.statement,
+            self.timestamp,
+        );
+
+        self.public_key.verify(&message, &self.signature).is_ok()
+    }
+
+    /// Serialize this attestation.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.contribution)?;
+        write_string(&mut writer, &self.name)?;
+        write_string(&mut writer, &self.statement)?;
+        writer.write_u64::<BigEndian>(self.timestamp)?;
+        writer.write_all(self.public_key.as_bytes())?;
+        writer.write_all(&self.signature.to_bytes())?;
+
+        Ok(())
+    }
+
+    /// Deserialize an attestation. The signature isn't checked.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Attestation> {
+        let mut contribution = [0u8; 64];
+        reader.read_exact(&mut contribution)?;
+        let name = read_string(&mut reader)?;
+        let statement = read_string(&mut reader)?;
+        let timestamp = reader.read_u64::<BigEndian>()?;
+
+        let mut public_key = [0u8; 32];
+        reader.read_exact(&mut public_key)?;
+        let public_key = VerifyingKey::from_bytes(&public_key)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut signature = [0u8; 64];
+        reader.read_exact(&mut signature)?;
+
+        Ok(Attestation {
+            contribution,
+            name,
+            statement,
+            timestamp,
+            public_key,
+            signature: Signature::from_bytes(&signature),
+        })
+    }
+}
+
+/// A verifier's Ed25519 signature of the statement "I verified
+/// contribution `index`, whose hash is `contribution`".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptAttestation {
+    /// The position of the contribution in the transcript.
+    pub index: u64,
+    /// The hash of the contribution.
+    pub contribution: [u8; 64],
+    /// The verifier's key.
+    pub public_key: VerifyingKey,
+    /// The signature over the index and the hash.
+    pub signature: Signature,
+}
+
+/// The message a verifier signs.
+fn verifier_message(index: u64, contribution: &[u8; 64]) -> Vec<u8> {
+    let mut message = VERIFIER_DOMAIN.to_vec();
+    message.write_u64::<BigEndian>(index).unwrap();
+    message.extend_from_slice(contribution);
+    message
+}
+
+impl TranscriptAttestation {
+    /// Sign that contribution `index`, whose hash is `contribution`,
+    /// was verified. Only sign once it was, e.g. with `verify`.
+    pub fn sign(
+        index: u64,
+        contribution: ContributionHash,
+        signing_key: &SigningKey,
+    ) -> TranscriptAttestation {
+        let contribution = contribution.into();
+        let signature = signing_key.sign(&verifier_message(index, &contribution));
+
+        TranscriptAttestation {
+            index,
+            contribution,
+            public_key: signing_key.verifying_key(),
+            signature,
+        }
+    }
+
+    /// Whether the signature is valid, and contribution `index` of
+    /// `params` has the hash that was signed.
+    pub fn verify<E: MPCEngine>(&self, params: &MPCParameters<E>) -> bool {
+        let message = verifier_message(self.index, &self.contribution);
+
+        usize::try_from(self.index)
+            .ok()
+            .and_then(|index| params.contributions.get(index))
+            .is_some_and(|pubkey| pubkey.hash().as_bytes() == &self.contribution)
+            && self.public_key.verify(&message, &self.signature).is_ok()
+    }
+
+    /// Serialize this attestation.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u64::<BigEndian>(self.index)?;
+        writer.write_all(&self.contribution)?;
+        writer.write_all(self.public_key.as_bytes())?;
+        writer.write_all(&self.signature.to_bytes())?;
+
+        Ok(())
+    }
+
+    /// Deserialize an attestation. The signature isn't checked.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<TranscriptAttestation> {
+        let index = reader.read_u64::<BigEndian>()?;
+        let mut contribution = [0u8; 64];
+        reader.read_exact(&mut contribution)?;
+
+        let mut public_key = [0u8; 32];
+        reader.read_exact(&mut public_key)?;
+        let public_key = VerifyingKey::from_bytes(&public_key)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut signature = [0u8; 64];
+        reader.read_exact(&mut signature)?;
+
+        Ok(TranscriptAttestation {
+            index,
+            contribution,
+            public_key,
+            signature: Signature::from_bytes(&signature),
+        })
+    }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// The verifiers' attestations these parameters carry.
+    pub fn verifier_attestations(&self) -> &[TranscriptAttestation] {
+        &self.verifier_attestations
+    }
+
+    /// Carry `attestation` along with these parameters. Fails with
+    /// `InvalidAttestation` unless it `verify`s against them.
+    pub fn add_verifier_attestation(
+        &mut self,
+        attestation: TranscriptAttestation,
+    ) -> Result<(), VerificationError> {
+        if !attestation.verify(self) {
+            return Err(VerificationError::InvalidAttestation {
+                index: self.verifier_attestations.len(),
+            });
+        }
+
+        self.verifier_attestations.push(attestation);
+        Ok(())
+    }
+
+    /// Check that every contribution is attested by at least
+    /// `threshold` distinct verifiers from `pool`, e.g. a majority
+    /// of them. Attestations by anyone else are ignored.
+    pub fn check_verifier_quorum(
+        &self,
+        pool: &[VerifyingKey],
+        threshold: usize,
+    ) -> Result<(), VerificationError> {
+        for (index, pubkey) in self.contributions.iter().enumerate() {
+            let hash = pubkey.hash();
+
+            let mut verifiers: Vec<&VerifyingKey> = self
+                .verifier_attestations
+                .iter()
+                .filter(|a| a.index == index as u64 && a.contribution == *hash.as_bytes())
+                .filter(|a| pool.contains(&a.public_key) && a.verify(self))
+                .map(|a| &a.public_key)
+                .collect();
+            verifiers.sort_by_key(|key| key.as_bytes());
+            verifiers.dedup();
+
+            if verifiers.len() < threshold {
+                return Err(VerificationError::NotEnoughVerifiers { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like `write`, followed by the verifiers' attestations.
+    pub fn write_with_attestations<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.write(&mut writer)?;
+
+        writer.write_all(VERIFIER_MAGIC)?;
+        writer.write_u32::<BigEndian>(self.verifier_attestations.len() as u32)?;
+        for attestation in &self.verifier_attestations {
+            attestation.write(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `read`, also reading the verifiers' attestations that
+    /// `write_with_attestations` puts after the parameters, if there
+    /// are any. Fails if one of them doesn't `verify`.
+    pub fn read_with_attestations<R: Read>(
+        mut reader: R,
+        checked: bool,
+    ) -> io::Result<MPCParameters<E>> {
+        let mut params = MPCParameters::<E>::read(&mut reader, checked)?;
+
+        let mut magic = [0u8; 4];
+        match reader.read_exact(&mut magic) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(params),
+            result => result?,
+        }
+        if &magic != VERIFIER_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected data after the parameters",
+            ));
+        }
+
+        // We don't trust the count to preallocate
+        for _ in 0..reader.read_u32::<BigEndian>()? {
+            let attestation = TranscriptAttestation::read(&mut reader)?;
+            params
+                .add_verifier_attestation(attestation)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        }
+
+        Ok(params)
+    }
+
+    /// Like `contribute`, also signing an attestation of the
+    /// contribution with the participant's `signing_key`. Publish
+    /// the attestation along with the new parameters.
+    pub fn contribute_signed<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        signing_key: &SigningKey,
+        name: &str,
+        statement: &str,
+    ) -> Attestation {
+        let hash = self.contribute(rng);
+        Attestation::sign(hash, signing_key, name, statement)
+    }
+
+    /// Verify the parameters (see `verify`) and the `attestations`
+    /// of their contributions. Returns the hash of each contribution
+    /// with its attestation, if there is one. Fails with
+    /// `InvalidAttestation` if an attestation isn't validly signed,
+    /// or isn't of any of the contributions.
+    pub fn verify_attested<'a, C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+        attestations: &'a [Attestation],
+    ) -> Result<Vec<AttestedContribution<'a>>, VerificationError> {
+        let hashes: Vec<[u8; 64]> = self.verify(circuit)?.into_iter().map(Into::into).collect();
+
+        for (index, attestation) in attestations.iter().enumerate() {
+            if !attestation.is_valid() || !hashes.contains(&attestation.contribution) {
+                return Err(VerificationError::InvalidAttestation { index });
+            }
+        }
+
+        Ok(hashes
+            .into_iter()
+            .map(|hash| {
+                let attestation = attestations.iter().find(|a| a.contribution == hash);
+                (hash, attestation)
+            })
+            .collect())
+    }
+}
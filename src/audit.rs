@@ -0,0 +1,193 @@
+//! Auditing the constraint system of a circuit by the names of its
+//! namespaces and variables, which synthesizing for parameters
+//! otherwise ignores.
+
+use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use ff::PrimeField;
+use std::collections::BTreeMap;
+
+use crate::KeypairAssembly;
+
+/// How much of a circuit is in a namespace, including the namespaces
+/// within it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceCounts {
+    /// The number of constraints.
+    pub constraints: usize,
+    /// The number of public inputs.
+    pub inputs: usize,
+    /// The number of auxiliary (private) variables.
+    pub aux: usize,
+}
+
+/// What `audit_circuit` found out about a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditReport {
+    /// The number of constraints, including those on the inputs that
+    /// are added to every circuit.
+    pub num_constraints: usize,
+    /// The number of public inputs, including the "one" input.
+    pub num_inputs: usize,
+    /// The number of auxiliary variables.
+    pub num_aux: usize,
+    /// The counts of each namespace, by its path (the names of the
+    /// namespaces it's in and its own, separated by `/`).
+    pub namespaces: BTreeMap<String, NamespaceCounts>,
+    /// The paths of the auxiliary variables that appear in no
+    /// constraint, which makes creating parameters fail with
+    /// `SynthesisError::UnconstrainedVariable`.
+    pub unconstrained: Vec<String>,
+}
+
+/// A `KeypairAssembly` that keeps track of the names it's given.
+struct AuditAssembly<Fr: PrimeField> {
+    assembly: KeypairAssembly<Fr>,
+    namespace: Vec<String>,
+    aux_names: Vec<String>,
+    namespaces: BTreeMap<String, NamespaceCounts>,
+}
+
+impl<Fr: PrimeField> AuditAssembly<Fr> {
+    /// The path of `name` within the current namespace.
+    fn path(&self, name: String) -> String {
+        self.namespace
+            .iter()
+            .cloned()
+            .chain(Some(name))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Counts something in the current namespace and the ones it's in.
+    fn count(&mut self, f: impl Fn(&mut NamespaceCounts)) {
+        for depth in 1..=self.namespace.len() {
+            let path = self.namespace[..depth].join("/");
+            f(self.namespaces.entry(path).or_default());
+        }
+    }
+}
+
+impl<Fr: PrimeField> ConstraintSystem<Fr> for AuditAssembly<Fr> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let name = self.path(annotation().into());
+        self.aux_names.push(name);
+        self.count(|counts| counts.aux += 1);
+
+        self.assembly.alloc(|| "", f)
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.count(|counts| counts.inputs += 1);
+
+        self.assembly.alloc_input(|| "", f)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+        LB: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+        LC: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+    {
+        self.count(|counts| counts.constraints += 1);
+
+        self.assembly.enforce(|| "", a, b, c)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespace.pop();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Synthesizes `circuit` as `KeypairAssembly::synthesize` does,
+/// returning the assembly along with its audit.
+pub(crate) fn synthesize_audited<Fr, C>(
+    circuit: C,
+) -> Result<(KeypairAssembly<Fr>, AuditReport), SynthesisError>
+where
+    Fr: PrimeField,
+    C: Circuit<Fr>,
+{
+    let mut audit = AuditAssembly {
+        assembly: KeypairAssembly::new(),
+        namespace: vec![],
+        aux_names: vec![],
+        namespaces: BTreeMap::new(),
+    };
+
+    // Allocate the "one" input variable
+    audit.alloc_input(|| "one", || Ok(Fr::ONE))?;
+
+    circuit.synthesize(&mut audit)?;
+
+    // Input constraints to ensure full density of IC query
+    // x * 0 = 0
+    for i in 0..audit.assembly.num_inputs {
+        audit.enforce(
+            || "",
+            |lc| lc + Variable::new_unchecked(Index::Input(i)),
+            |lc| lc,
+            |lc| lc,
+        );
+    }
+
+    let assembly = audit.assembly;
+    let unconstrained = audit
+        .aux_names
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            assembly.at_aux[i].is_empty()
+                && assembly.bt_aux[i].is_empty()
+                && assembly.ct_aux[i].is_empty()
+        })
+        .map(|(_, name)| name)
+        .collect();
+
+    let report = AuditReport {
+        num_constraints: assembly.num_constraints,
+        num_inputs: assembly.num_inputs,
+        num_aux: assembly.num_aux,
+        namespaces: audit.namespaces,
+        unconstrained,
+    };
+
+    Ok((assembly, report))
+}
+
+/// Synthesize `circuit` keeping track of the names of its namespaces
+/// and variables, to report how big each namespace is and which
+/// variables are unconstrained. This doesn't need the radix files, so
+/// it's a cheap check to run before `MPCParameters::new`.
+pub fn audit_circuit<Fr, C>(circuit: C) -> Result<AuditReport, SynthesisError>
+where
+    Fr: PrimeField,
+    C: Circuit<Fr>,
+{
+    Ok(synthesize_audited(circuit)?.1)
+}
@@ -0,0 +1,193 @@
+//! Command line tool for taking part in a ceremony, enabled by the
+//! `cli` feature.
+//!
+//! Creating the initial parameters needs the circuit, so it's left
+//! to the coordinator, who does it with `MPCParameters::new`.
+
+use bls12_381::Bls12;
+use phase2::client::{Client, ClientError};
+use phase2::phase1::RadixFile;
+use phase2::{Config, ContributionEntropy, MPCParameters};
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+    phase2 contribute <old_params> <new_params>
+    phase2 beacon <old_params> <new_params> <beacon_hex> <iterations>
+    phase2 verify <old_params> <new_params>
+    phase2 export-keys <params> <proving_key> <verifying_key>
+    phase2 join <coordinator_url>
+    phase2 check-radix <radix_file> <exp>
+
+Any command can be preceded by --threads <n> to use at most n threads.
+contribute and join can be preceded by --entropy-file <path> to mix
+in the first 4096 bytes of a file, e.g. /dev/hwrng.";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn read_params(path: &str) -> io::Result<MPCParameters> {
+    let reader = BufReader::with_capacity(1024 * 1024, File::open(path)?);
+    MPCParameters::read(reader, true)
+}
+
+fn write_params(params: &MPCParameters, path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::with_capacity(1024 * 1024, File::create(path)?);
+    params.write(&mut writer)?;
+    writer.flush()
+}
+
+/// How much of the file given with `--entropy-file` is read.
+const ENTROPY_FILE_LEN: u64 = 4096;
+
+/// Mixes randomness from the OS with whatever the user types, the
+/// timing jitter of this machine and, if given, the contents of
+/// `entropy_file`, so that none of them has to be trusted alone.
+fn collect_entropy(entropy_file: Option<&str>) -> io::Result<ContributionEntropy> {
+    let mut entropy = ContributionEntropy::new().with_jitter();
+    if let Some(path) = entropy_file {
+        entropy = entropy.with_file(path, ENTROPY_FILE_LEN)?;
+    }
+
+    eprint!("Type some random text and press [ENTER] for extra entropy: ");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(entropy.with_input(input))
+}
+
+/// How many points of each vector `check-radix` decodes, besides the
+/// first and the last.
+const RADIX_SAMPLES: usize = 1024;
+
+fn run(args: &[String], entropy_file: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    match args[..] {
+        ["contribute", old, new] => {
+            let mut params = read_params(old)?;
+            let entropy = collect_entropy(entropy_file)?;
+            let attestation = entropy.attestation();
+            let hash = params.contribute_with_seed(entropy.seed());
+            write_params(&params, new)?;
+
+            println!("Your contribution hash is {}", hex(&hash));
+            println!("{}", attestation);
+        }
+        ["beacon", old, new, beacon, iterations] => {
+            let beacon = from_hex(beacon).ok_or("the beacon must be hex")?;
+            let iterations: u64 = iterations.parse()?;
+
+            let mut params = read_params(old)?;
+            let hash = params.contribute_beacon(&beacon, iterations);
+            write_params(&params, new)?;
+
+            println!("The beacon's contribution hash is {}", hex(&hash));
+        }
+        ["verify", old, new] => {
+            let before = read_params(old)?;
+            let after = read_params(new)?;
+            let hash = phase2::verify_contribution(&before, &after)?;
+
+            println!("Verified contribution {}", hash);
+        }
+        ["export-keys", params, pk, vk] => {
+            let params = read_params(params)?;
+
+            let mut writer = BufWriter::new(File::create(pk)?);
+            params.write_final_params(&mut writer)?;
+            writer.flush()?;
+
+            let mut writer = BufWriter::new(File::create(vk)?);
+            params.write_final_vk(&mut writer)?;
+            writer.flush()?;
+        }
+        ["join", url] => {
+            let entropy = collect_entropy(entropy_file)?;
+            let attestation = entropy.attestation();
+            let mut rng = ChaChaRng::from_seed(entropy.seed());
+            let client = Client::new(url, "phase2-join");
+
+            let hash = loop {
+                match client.join::<Bls12, _>(&mut rng) {
+                    Err(ClientError::SlotTaken) => {
+                        eprintln!("Someone else is contributing, waiting for the slot...");
+                        thread::sleep(Duration::from_secs(30));
+                    }
+                    result => break result?,
+                }
+            };
+
+            println!("Your contribution hash is {}", hex(&hash));
+            println!("{}", attestation);
+        }
+        ["check-radix", path, exp] => {
+            let exp: u32 = exp.parse()?;
+            let info = RadixFile::<Bls12>::open_sampled(path, exp, RADIX_SAMPLES)
+                .map_err(|e| format!("{}: {}", path, e))?;
+
+            println!(
+                "{} is a radix file for up to {} constraints ({} of its {} points checked)",
+                path,
+                info.degree,
+                info.checked,
+                info.g1_points + info.g2_points
+            );
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let mut config = Config::default();
+    let mut entropy_file = None;
+    loop {
+        match (args.first().map(|s| s.as_str()), args.get(1)) {
+            (Some("--threads"), Some(n)) => match n.parse() {
+                Ok(threads) => config.threads = threads,
+                Err(_) => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            (Some("--entropy-file"), Some(path)) => entropy_file = Some(path.clone()),
+            (Some("--threads" | "--entropy-file"), None) => {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            }
+            _ => break,
+        }
+        args.drain(..2);
+    }
+
+    // Errors aren't Send, so they're turned into messages in the pool
+    let result = config
+        .install(|| run(&args, entropy_file.as_deref()).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| Err(e.to_string()));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,254 @@
+//! Running the ceremonies of several circuits at once.
+//!
+//! Some projects need parameters for more than one circuit (Sapling
+//! has a Spend and an Output circuit), and run their ceremonies side
+//! by side so that each participant contributes to all of them in
+//! one go. An `MPCBundle` holds the parameters of each circuit under
+//! a name, and contributing to it contributes to all of them.
+//!
+//! A contribution to a bundle is identified by a single hash of the
+//! hashes of the contributions to each circuit, so participants only
+//! have one hash to look for.
+
+use blake2_rfc::blake2b::Blake2b;
+use bls12_381::Bls12;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use pairing::Engine;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::{ContributionHash, MPCEngine, MPCParameters, VerificationError};
+
+/// Domain separator for deriving the seed of each circuit's
+/// contribution from the bundle's.
+const BUNDLE_SEED_DOMAIN: &[u8] = b"phase2 bundle seed";
+
+/// Derives the seed of the RNG used to contribute to the circuit
+/// `name`, as BLAKE2b-256(BUNDLE_SEED_DOMAIN | seed | name). The name
+/// is last, so it needs no length prefix.
+fn member_seed(seed: &[u8; 32], name: &str) -> [u8; 32] {
+    let mut hasher = Blake2b::new(32);
+    hasher.update(BUNDLE_SEED_DOMAIN);
+    hasher.update(seed);
+    hasher.update(name.as_bytes());
+
+    let mut member = [0u8; 32];
+    member.copy_from_slice(hasher.finalize().as_ref());
+    member
+}
+
+/// Hashes the names of the circuits with the hashes of their
+/// contributions, in the order of the names.
+fn combined_hash<'a, I>(hashes: I) -> [u8; 64]
+where
+    I: IntoIterator<Item = (&'a str, [u8; 64])>,
+{
+    let mut hasher = Blake2b::new(64);
+    for (name, hash) in hashes {
+        hasher.update(&(name.len() as u32).to_be_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update(&hash);
+    }
+
+    let mut combined = [0u8; 64];
+    combined.copy_from_slice(hasher.finalize().as_ref());
+    combined
+}
+
+/// Why a bundle failed to verify.
+#[derive(Debug)]
+pub enum BundleError {
+    /// The parameters of circuit `name` failed to verify.
+    Member {
+        name: String,
+        error: VerificationError,
+    },
+    /// The parameters of circuit `name` don't have as many
+    /// contributions as those of the other circuits.
+    ContributionCountMismatch { name: String },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleError::Member { name, error } => {
+                write!(f, "the parameters of {} are invalid: {}", name, error)
+            }
+            BundleError::ContributionCountMismatch { name } => write!(
+                f,
+                "the parameters of {} don't have as many contributions as the others",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BundleError::Member { error, .. } => Some(error),
+            BundleError::ContributionCountMismatch { .. } => None,
+        }
+    }
+}
+
+/// The parameters of several circuits, by name, whose ceremonies
+/// are run together.
+#[derive(Clone)]
+pub struct MPCBundle<E: Engine = Bls12> {
+    params: BTreeMap<String, MPCParameters<E>>,
+}
+
+impl<E: MPCEngine> Default for MPCBundle<E> {
+    fn default() -> Self {
+        MPCBundle {
+            params: BTreeMap::new(),
+        }
+    }
+}
+
+impl<E: MPCEngine> MPCBundle<E> {
+    /// An empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the parameters of the circuit `name`, returning the
+    /// parameters it replaces, if any. The parameters of every
+    /// circuit should have the same contributions, which is usually
+    /// none.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        params: MPCParameters<E>,
+    ) -> Option<MPCParameters<E>> {
+        self.params.insert(name.into(), params)
+    }
+
+    /// Get the parameters of the circuit `name`.
+    pub fn get(&self, name: &str) -> Option<&MPCParameters<E>> {
+        self.params.get(name)
+    }
+
+    /// Iterate over the circuits and their parameters, in the order
+    /// of their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MPCParameters<E>)> {
+        self.params
+            .iter()
+            .map(|(name, params)| (name.as_str(), params))
+    }
+
+    /// The number of circuits.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Whether there are no circuits.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Unwrap the parameters of each circuit, by name.
+    pub fn into_inner(self) -> BTreeMap<String, MPCParameters<E>> {
+        self.params
+    }
+
+    /// Contributes to the parameters of every circuit, drawing a
+    /// single seed from `rng`. The randomness of each circuit's
+    /// contribution is derived from that seed and the circuit's
+    /// name, so no two circuits share a delta.
+    ///
+    /// Returns the combined hash of the contributions, which
+    /// `verify` returns along with those of the other participants.
+    pub fn contribute<R: Rng>(&mut self, rng: &mut R) -> [u8; 64] {
+        let seed: [u8; 32] = rng.gen();
+
+        let hashes = self
+            .params
+            .iter_mut()
+            .map(|(name, params)| {
+                let mut rng = ChaChaRng::from_seed(member_seed(&seed, name));
+                (name.as_str(), params.contribute(&mut rng))
+            })
+            .collect::<Vec<_>>();
+
+        combined_hash(hashes)
+    }
+
+    /// Verify the parameters of every circuit with `verify`, which
+    /// is given the name of the circuit along with its parameters and
+    /// should call `MPCParameters::verify` (or one of its variants)
+    /// with the matching circuit. Every circuit must have the same
+    /// number of contributions.
+    ///
+    /// Returns the combined hashes of the contributions, as
+    /// `contribute` returned them, in order.
+    pub fn verify<F>(&self, mut verify: F) -> Result<Vec<ContributionHash>, BundleError>
+    where
+        F: FnMut(&str, &MPCParameters<E>) -> Result<Vec<ContributionHash>, VerificationError>,
+    {
+        let mut members = vec![];
+        for (name, params) in self.iter() {
+            let hashes = verify(name, params).map_err(|error| BundleError::Member {
+                name: name.to_string(),
+                error,
+            })?;
+            members.push((name, hashes));
+        }
+
+        let count = members.first().map_or(0, |(_, hashes)| hashes.len());
+        if let Some((name, _)) = members.iter().find(|(_, hashes)| hashes.len() != count) {
+            return Err(BundleError::ContributionCountMismatch {
+                name: name.to_string(),
+            });
+        }
+
+        Ok((0..count)
+            .map(|i| {
+                let hashes = members
+                    .iter()
+                    .map(|(name, hashes)| (*name, hashes[i].into()));
+                combined_hash(hashes).into()
+            })
+            .collect())
+    }
+
+    /// Serialize the bundle: the number of circuits, followed by the
+    /// name and parameters of each.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.params.len() as u32)?;
+        for (name, params) in &self.params {
+            writer.write_u32::<BigEndian>(name.len() as u32)?;
+            writer.write_all(name.as_bytes())?;
+            params.write(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a bundle, checking the parameters of each circuit
+    /// as `MPCParameters::read` does if `checked` is set.
+    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<MPCBundle<E>> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut bundle = MPCBundle::new();
+        for _ in 0..reader.read_u32::<BigEndian>()? {
+            let len = reader.read_u32::<BigEndian>()? as u64;
+            let mut name = vec![];
+            if (&mut reader).take(len).read_to_end(&mut name)? as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let name = String::from_utf8(name).map_err(|_| invalid("name isn't UTF-8"))?;
+
+            let params = MPCParameters::read(&mut reader, checked)?;
+            if bundle.insert(name, params).is_some() {
+                return Err(invalid("duplicate circuit name"));
+            }
+        }
+
+        Ok(bundle)
+    }
+}
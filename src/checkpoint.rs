@@ -0,0 +1,335 @@
+//! Checkpoints for creating parameters, so that a crash partway
+//! through `MPCParameters::new` for a large circuit doesn't lose
+//! hours of work.
+//!
+//! A checkpoint file holds the synthesized circuit and the radix file
+//! it was read with, then the QAP evaluated at tau for each block of
+//! variables as it's done. A block that was cut short is dropped when
+//! resuming, and evaluated again.
+
+use bellman::Circuit;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use group::prime::PrimeCurveAffine;
+use pairing::group::{Curve, UncompressedEncoding};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::{
+    domain_exp, no_progress, phase1, KeypairAssembly, MPCEngine, MPCParameters, Phase2Error,
+    Progress, QapEvaluation, Radix,
+};
+
+/// Identifies checkpoint files.
+const MAGIC: &[u8; 16] = b"phase2checkpoint";
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn point_size<G: UncompressedEncoding>() -> u64 {
+    G::Uncompressed::default().as_ref().len() as u64
+}
+
+/// Reads a point written by `write_points`, which may be the point at
+/// infinity. It was computed by us, so it isn't checked.
+fn read_point<G: UncompressedEncoding, R: Read>(reader: &mut R) -> io::Result<G> {
+    let mut repr = G::Uncompressed::default();
+    reader.read_exact(repr.as_mut())?;
+
+    Option::from(G::from_uncompressed_unchecked(&repr)).ok_or_else(|| invalid("invalid point"))
+}
+
+fn write_points<G: UncompressedEncoding, W: Write>(writer: &mut W, points: &[G]) -> io::Result<()> {
+    for p in points {
+        writer.write_all(p.to_uncompressed().as_ref())?;
+    }
+
+    Ok(())
+}
+
+fn write_terms<Fr: PrimeField, W: Write>(
+    writer: &mut W,
+    terms: &[Vec<(Fr, usize)>],
+) -> io::Result<()> {
+    writer.write_u64::<BigEndian>(terms.len() as u64)?;
+    for var in terms {
+        writer.write_u32::<BigEndian>(var.len() as u32)?;
+        for (coeff, lag) in var {
+            writer.write_all(coeff.to_repr().as_ref())?;
+            writer.write_u64::<BigEndian>(*lag as u64)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_terms<Fr: PrimeField, R: Read>(
+    reader: &mut R,
+    m: usize,
+) -> io::Result<Vec<Vec<(Fr, usize)>>> {
+    let len = reader.read_u64::<BigEndian>()?;
+    let mut terms = vec![];
+    for _ in 0..len {
+        let mut var = vec![];
+        for _ in 0..reader.read_u32::<BigEndian>()? {
+            let mut repr = Fr::Repr::default();
+            reader.read_exact(repr.as_mut())?;
+            let coeff =
+                Option::from(Fr::from_repr(repr)).ok_or_else(|| invalid("invalid scalar"))?;
+            let lag = reader.read_u64::<BigEndian>()? as usize;
+            if lag >= m {
+                return Err(invalid("constraint outside of the domain"));
+            }
+            var.push((coeff, lag));
+        }
+        terms.push(var);
+    }
+
+    Ok(terms)
+}
+
+impl<Fr: PrimeField> KeypairAssembly<Fr> {
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u64::<BigEndian>(self.num_inputs as u64)?;
+        writer.write_u64::<BigEndian>(self.num_aux as u64)?;
+        writer.write_u64::<BigEndian>(self.num_constraints as u64)?;
+        for terms in [
+            &self.at_inputs,
+            &self.bt_inputs,
+            &self.ct_inputs,
+            &self.at_aux,
+            &self.bt_aux,
+            &self.ct_aux,
+        ] {
+            write_terms(writer, terms)?;
+        }
+
+        Ok(())
+    }
+
+    fn read<R: Read>(reader: &mut R, m: usize) -> io::Result<Self> {
+        let num_inputs = reader.read_u64::<BigEndian>()? as usize;
+        let num_aux = reader.read_u64::<BigEndian>()? as usize;
+        let num_constraints = reader.read_u64::<BigEndian>()? as usize;
+
+        let assembly = KeypairAssembly {
+            num_inputs,
+            num_aux,
+            num_constraints,
+            at_inputs: read_terms(reader, m)?,
+            bt_inputs: read_terms(reader, m)?,
+            ct_inputs: read_terms(reader, m)?,
+            at_aux: read_terms(reader, m)?,
+            bt_aux: read_terms(reader, m)?,
+            ct_aux: read_terms(reader, m)?,
+        };
+
+        let inputs = [
+            &assembly.at_inputs,
+            &assembly.bt_inputs,
+            &assembly.ct_inputs,
+        ];
+        let aux = [&assembly.at_aux, &assembly.bt_aux, &assembly.ct_aux];
+        if inputs.iter().any(|t| t.len() != num_inputs) || aux.iter().any(|t| t.len() != num_aux) {
+            return Err(invalid("inconsistent circuit"));
+        }
+
+        Ok(assembly)
+    }
+}
+
+impl<E: MPCEngine> Radix<E> {
+    /// Write the contents of the radix file back out, in its format.
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_points(writer, &[self.alpha, self.beta_g1])?;
+        write_points(writer, &[self.beta_g2])?;
+        write_points(writer, &self.coeffs_g1)?;
+        write_points(writer, &self.coeffs_g2)?;
+        write_points(writer, &self.alpha_coeffs_g1)?;
+        write_points(writer, &self.beta_coeffs_g1)?;
+        write_points(writer, &self.h)
+    }
+
+    /// The size of the radix file for a domain of size `m`.
+    fn file_size(m: usize) -> u64 {
+        let m = m as u64;
+        (1 + 4 * m) * point_size::<E::G1Affine>() + (1 + m) * point_size::<E::G2Affine>()
+    }
+}
+
+/// The size of a block of `len` evaluated variables in a checkpoint.
+fn block_size<E: MPCEngine>(len: u64) -> u64 {
+    16 + len * (3 * point_size::<E::G1Affine>() + point_size::<E::G2Affine>())
+}
+
+/// Appends the block of variables `block` of `evaluation` to the
+/// checkpoint, and waits for it to reach the disk.
+fn write_block<E: MPCEngine>(
+    file: &File,
+    evaluation: &QapEvaluation<E>,
+    block: Range<usize>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::with_capacity(1024 * 1024, file);
+    writer.write_u64::<BigEndian>(block.start as u64)?;
+    writer.write_u64::<BigEndian>(block.end as u64)?;
+
+    let mut g1 = vec![E::G1Affine::identity(); block.len()];
+    let mut g2 = vec![E::G2Affine::identity(); block.len()];
+    for query in [&evaluation.a_g1, &evaluation.b_g1] {
+        E::G1::batch_normalize(&query[block.clone()], &mut g1);
+        write_points(&mut writer, &g1)?;
+    }
+    E::G2::batch_normalize(&evaluation.b_g2[block.clone()], &mut g2);
+    write_points(&mut writer, &g2)?;
+    E::G1::batch_normalize(&evaluation.ext[block], &mut g1);
+    write_points(&mut writer, &g1)?;
+
+    writer.flush()?;
+    drop(writer);
+    file.sync_data()
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Like `new()`, keeping a checkpoint at `checkpoint_path` as it
+    /// goes: after the radix file is read, and after each block of
+    /// the QAP is evaluated. If creating the parameters is
+    /// interrupted, `resume` picks up from the last of them. The
+    /// checkpoint is removed once the parameters are created.
+    ///
+    /// The checkpoint holds a copy of the radix file and of the
+    /// evaluated QAP, so it takes about as much disk space as the
+    /// radix file and the parameters together.
+    pub fn new_with_checkpoint<C, P>(
+        circuit: C,
+        checkpoint_path: P,
+    ) -> Result<MPCParameters<E>, Phase2Error>
+    where
+        C: Circuit<E::Fr>,
+        P: AsRef<Path>,
+    {
+        let assembly = KeypairAssembly::synthesize(circuit)?;
+        let exp = domain_exp(&assembly, phase1::MAX_EXP)?;
+        let m = 1 << exp;
+
+        // Try to load "phase1radix2m{}"
+        let radix = Radix::read(
+            File::open(format!("phase1radix2m{}", exp))?,
+            m,
+            Progress::new(&no_progress),
+        )?;
+
+        let file = File::create(&checkpoint_path)?;
+        let mut writer = BufWriter::with_capacity(1024 * 1024, &file);
+        writer.write_all(MAGIC)?;
+        writer.write_u64::<BigEndian>(m as u64)?;
+        radix.write(&mut writer)?;
+        assembly.write(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        file.sync_data()?;
+
+        let evaluation = QapEvaluation::new(assembly.num_inputs + assembly.num_aux);
+        MPCParameters::finish_checkpoint(assembly, radix, evaluation, file, checkpoint_path)
+    }
+
+    /// Continue creating the parameters whose checkpoint is at
+    /// `checkpoint_path`, as `new_with_checkpoint` left it, from the
+    /// last block of the QAP that was evaluated. The checkpoint has
+    /// everything that's needed, so neither the circuit nor the radix
+    /// file are read again. The checkpoint is removed once the
+    /// parameters are created.
+    ///
+    /// The checkpoint is trusted, as it was written by us. If it was
+    /// interrupted before the radix file was copied into it, this
+    /// fails, and `new_with_checkpoint` must start over.
+    pub fn resume<P: AsRef<Path>>(checkpoint_path: P) -> Result<MPCParameters<E>, Phase2Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&checkpoint_path)?;
+        let len = file.metadata()?.len();
+        let mut reader = BufReader::with_capacity(1024 * 1024, &file);
+
+        let mut magic = [0u8; 16];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a checkpoint").into());
+        }
+
+        let m = reader.read_u64::<BigEndian>()? as usize;
+        if !m.is_power_of_two() || m > 1 << phase1::MAX_EXP.min(E::Fr::S) {
+            return Err(invalid("invalid domain size").into());
+        }
+
+        // The radix file was written in its own format, which
+        // `Radix::read` buffers, so it mustn't read past its end
+        let radix = Radix::read(
+            (&mut reader).take(Radix::<E>::file_size(m)),
+            m,
+            Progress::new(&no_progress),
+        )?;
+        let assembly = KeypairAssembly::read(&mut reader, m)?;
+
+        let num_vars = assembly.num_inputs + assembly.num_aux;
+        let mut evaluation = QapEvaluation::<E>::new(num_vars);
+        let mut pos = reader.stream_position()?;
+        while pos + 16 <= len {
+            let start = reader.read_u64::<BigEndian>()? as usize;
+            let end = reader.read_u64::<BigEndian>()? as usize;
+            if start != evaluation.done || end <= start || end > num_vars {
+                return Err(invalid("blocks out of order").into());
+            }
+
+            // The last block may have been cut short
+            if pos + block_size::<E>((end - start) as u64) > len {
+                break;
+            }
+
+            for query in [&mut evaluation.a_g1, &mut evaluation.b_g1] {
+                for p in &mut query[start..end] {
+                    *p = read_point::<E::G1Affine, _>(&mut reader)?.into();
+                }
+            }
+            for p in &mut evaluation.b_g2[start..end] {
+                *p = read_point::<E::G2Affine, _>(&mut reader)?.into();
+            }
+            for p in &mut evaluation.ext[start..end] {
+                *p = read_point::<E::G1Affine, _>(&mut reader)?.into();
+            }
+
+            evaluation.done = end;
+            pos = reader.stream_position()?;
+        }
+        drop(reader);
+
+        // Drop whatever was left of a block that was cut short
+        file.set_len(pos)?;
+        file.seek(SeekFrom::End(0))?;
+
+        MPCParameters::finish_checkpoint(assembly, radix, evaluation, file, checkpoint_path)
+    }
+
+    fn finish_checkpoint<P: AsRef<Path>>(
+        assembly: KeypairAssembly<E::Fr>,
+        radix: Radix<E>,
+        evaluation: QapEvaluation<E>,
+        file: File,
+        checkpoint_path: P,
+    ) -> Result<MPCParameters<E>, Phase2Error> {
+        let params = MPCParameters::from_radix(
+            assembly,
+            radix,
+            evaluation,
+            Progress::new(&no_progress),
+            |evaluation, block| write_block(&file, evaluation, block),
+        )?;
+
+        drop(file);
+        fs::remove_file(checkpoint_path)?;
+
+        Ok(params)
+    }
+}
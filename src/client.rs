@@ -0,0 +1,243 @@
+//! A client for taking part in a ceremony run by a `Coordinator`,
+//! enabled by the `client` feature.
+
+use rand::Rng;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::{hex, HashWriter, MPCEngine, MPCParameters};
+
+/// An error from a `Client`.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The parameters couldn't be read from or written to disk, or
+    /// are invalid.
+    Io(io::Error),
+    /// A request failed, even after retrying.
+    Network(Box<ureq::Error>),
+    /// Someone else holds the slot.
+    SlotTaken,
+    /// The downloaded parameters don't match the coordinator's hash
+    /// of them.
+    HashMismatch,
+    /// The coordinator rejected the request, with the reason it gave.
+    Rejected(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "I/O error: {}", e),
+            ClientError::Network(e) => write!(f, "request failed: {}", e),
+            ClientError::SlotTaken => write!(f, "someone else is contributing"),
+            ClientError::HashMismatch => {
+                write!(f, "the downloaded parameters have the wrong hash")
+            }
+            ClientError::Rejected(reason) => write!(f, "rejected: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Io(e) => Some(e),
+            ClientError::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> ClientError {
+        ClientError::Io(e)
+    }
+}
+
+impl From<ureq::Error> for ClientError {
+    fn from(e: ureq::Error) -> ClientError {
+        match e {
+            ureq::Error::Status(409, _) => ClientError::SlotTaken,
+            ureq::Error::Status(_, response) => {
+                ClientError::Rejected(response.into_string().unwrap_or_default())
+            }
+            e => ClientError::Network(Box::new(e)),
+        }
+    }
+}
+
+fn from_hex(s: &str) -> Option<[u8; 64]> {
+    let mut bytes = [0u8; 64];
+    if s.len() != 128 {
+        return None;
+    }
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+/// The BLAKE2b hash of the file at `path`.
+fn file_hash(path: &Path) -> io::Result<[u8; 64]> {
+    let mut hasher = HashWriter::new(io::sink());
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hasher.into_hash())
+}
+
+/// Takes part in the ceremony coordinated at `url` (see the
+/// `coordinator` module), keeping the parameters in `dir`.
+pub struct Client {
+    url: String,
+    dir: PathBuf,
+    retries: u32,
+}
+
+impl Client {
+    /// A client of the coordinator at `url`, which retries failed
+    /// transfers 3 times.
+    pub fn new<P: Into<PathBuf>>(url: &str, dir: P) -> Self {
+        Client {
+            url: url.trim_end_matches('/').to_string(),
+            dir: dir.into(),
+            retries: 3,
+        }
+    }
+
+    /// Set how many times failed transfers are retried.
+    pub fn with_retries(self, retries: u32) -> Self {
+        Client { retries, ..self }
+    }
+
+    /// Runs `f` until it doesn't fail with a network error, or there
+    /// are no retries left.
+    fn retry<T>(&self, mut f: impl FnMut() -> Result<T, ClientError>) -> Result<T, ClientError> {
+        let mut attempt = 0;
+
+        loop {
+            match f() {
+                Err(ClientError::Network(_)) | Err(ClientError::Io(_))
+                    if attempt < self.retries =>
+                {
+                    attempt += 1;
+                    thread::sleep(Duration::from_secs(1 << attempt));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Reserve the slot to contribute, returning its token. Fails
+    /// with `SlotTaken` if someone else holds it.
+    pub fn reserve(&self) -> Result<String, ClientError> {
+        let url = format!("{}/slot", self.url);
+        Ok(ureq::post(&url).call()?.into_string()?)
+    }
+
+    /// Download the current parameters into `dir`, returning their
+    /// path. An interrupted download is resumed where it stopped,
+    /// and the parameters are only kept if they match the
+    /// coordinator's hash of them.
+    pub fn download(&self) -> Result<PathBuf, ClientError> {
+        fs::create_dir_all(&self.dir)?;
+
+        let expected = ureq::get(&format!("{}/params/hash", self.url))
+            .call()?
+            .into_string()?;
+        let expected = from_hex(expected.trim()).ok_or(ClientError::HashMismatch)?;
+
+        let partial = self.dir.join("params.part");
+        let path = self.dir.join("params");
+
+        self.retry(|| {
+            let start = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+            let response = match ureq::get(&format!("{}/params", self.url))
+                .set("Range", &format!("bytes={}-", start))
+                .call()
+            {
+                // Nothing is left past the partial download: it's
+                // either complete, e.g. if we stopped before renaming
+                // it, or left over from other parameters
+                Err(ureq::Error::Status(416, _)) if start > 0 => {
+                    if file_hash(&partial)? == expected {
+                        return Ok(());
+                    }
+
+                    fs::remove_file(&partial)?;
+                    ureq::get(&format!("{}/params", self.url)).call()?
+                }
+                response => response?,
+            };
+
+            // Without a partial response, the download starts over
+            let resumed = response.status() == 206;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(resumed)
+                .truncate(!resumed)
+                .open(&partial)?;
+            io::copy(&mut response.into_reader(), &mut file)?;
+            file.flush()?;
+
+            Ok(())
+        })?;
+
+        if file_hash(&partial)? != expected {
+            // A stale or corrupted download mustn't be resumed
+            fs::remove_file(&partial)?;
+            return Err(ClientError::HashMismatch);
+        }
+
+        fs::rename(&partial, &path)?;
+
+        Ok(path)
+    }
+
+    /// Upload the parameters at `path`, made in the slot of `token`.
+    /// Returns the contribution's hash, as the coordinator computed
+    /// it.
+    pub fn upload(&self, token: &str, path: &Path) -> Result<[u8; 64], ClientError> {
+        let url = format!("{}/contribute/{}", self.url, token);
+
+        let hash = self.retry(|| {
+            let file = BufReader::with_capacity(1024 * 1024, File::open(path)?);
+            Ok(ureq::post(&url).send(file)?.into_string()?)
+        })?;
+
+        from_hex(hash.trim()).ok_or(ClientError::Rejected(hash))
+    }
+
+    /// Reserve the slot, download the current parameters, contribute
+    /// to them with randomness from `rng`, and upload the result.
+    /// Returns the hash of the contribution.
+    pub fn join<E: MPCEngine, R: Rng>(&self, rng: &mut R) -> Result<[u8; 64], ClientError> {
+        let token = self.reserve()?;
+        let path = self.download()?;
+
+        let reader = BufReader::with_capacity(1024 * 1024, File::open(&path)?);
+        let mut params = MPCParameters::<E>::read(reader, true)?;
+        let hash = params.contribute(rng);
+
+        let new_path = self.dir.join("new_params");
+        let mut writer = BufWriter::with_capacity(1024 * 1024, File::create(&new_path)?);
+        params.write(&mut writer)?;
+        writer.flush()?;
+
+        let accepted = self.upload(&token, &new_path)?;
+        if accepted != hash {
+            return Err(ClientError::Rejected(format!(
+                "the coordinator recorded contribution {}",
+                hex(&accepted)
+            )));
+        }
+
+        Ok(hash)
+    }
+}
@@ -0,0 +1,324 @@
+//! A minimal HTTP server for running a ceremony, enabled by the
+//! `coordinator` feature.
+//!
+//! Participants take turns: one at a time, they reserve a slot,
+//! download the current parameters, contribute, and upload the new
+//! parameters before their slot times out. The server verifies each
+//! upload against the current parameters and, if it's valid, makes
+//! it the current parameters and appends it to the transcript.
+//!
+//! The endpoints are:
+//!
+//! - `GET /params`: the current parameters, as written by `write`.
+//!   A `Range: bytes={start}-` header resumes a download.
+//! - `GET /params/hash`: the BLAKE2b hash of the current parameters,
+//!   as hex.
+//! - `GET /transcript`: the transcript, one JSON object per accepted
+//!   contribution.
+//! - `POST /slot`: reserves the slot, responding with a token, or
+//!   with 409 if someone else holds it.
+//! - `POST /contribute/{token}`: uploads the new parameters made in
+//!   the slot of `token`, responding with the contribution's hash.
+//!
+//! Given a `StorageBackend` (see `Coordinator::with_storage`), the
+//! parameters after each accepted contribution are kept there too:
+//!
+//! - `GET /contributions`: their keys, one per line, in order.
+//! - `GET /contributions/{key}`: the parameters stored under `key`.
+
+use bls12_381::Bls12;
+use pairing::Engine;
+use rand::RngCore;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server, StatusCode};
+
+#[cfg(feature = "s3")]
+pub use crate::storage::S3Storage;
+pub use crate::storage::{LocalStorage, StorageBackend};
+use crate::{hex, verify_contribution, HashWriter, MPCEngine, MPCParameters};
+
+/// The participant currently allowed to contribute.
+struct Slot {
+    token: String,
+    expires: Instant,
+}
+
+/// Serves a ceremony over HTTP. The current parameters are kept at
+/// `params_path`, and the transcript at `transcript_path`, so the
+/// ceremony can be resumed if the server is restarted.
+pub struct Coordinator<E: Engine = Bls12> {
+    params: MPCParameters<E>,
+    params_hash: [u8; 64],
+    params_path: PathBuf,
+    transcript_path: PathBuf,
+    slot_timeout: Duration,
+    slot: Option<Slot>,
+    storage: Option<Box<dyn StorageBackend>>,
+}
+
+fn text(code: u16, body: String) -> ResponseBox {
+    Response::from_string(body).with_status_code(code).boxed()
+}
+
+impl<E: MPCEngine> Coordinator<E> {
+    /// Coordinate the ceremony whose current parameters are at
+    /// `params_path`. Each participant gets `slot_timeout` to upload
+    /// their contribution once they've reserved the slot.
+    pub fn open<P: Into<PathBuf>>(
+        params_path: P,
+        transcript_path: P,
+        slot_timeout: Duration,
+    ) -> io::Result<Self> {
+        let params_path = params_path.into();
+        let reader = BufReader::with_capacity(1024 * 1024, File::open(&params_path)?);
+        let params = MPCParameters::read(reader, true)?;
+
+        let mut hasher = HashWriter::new(io::sink());
+        io::copy(&mut File::open(&params_path)?, &mut hasher)?;
+
+        Ok(Coordinator {
+            params,
+            params_hash: hasher.into_hash(),
+            params_path,
+            transcript_path: transcript_path.into(),
+            slot_timeout,
+            slot: None,
+            storage: None,
+        })
+    }
+
+    /// Also keep the parameters after each contribution in `storage`,
+    /// under keys that sort in the order they were accepted, and
+    /// serve them. Only the current parameters stay at `params_path`.
+    pub fn with_storage<S: StorageBackend + 'static>(mut self, storage: S) -> Self {
+        self.storage = Some(Box::new(storage));
+        self
+    }
+
+    /// Serve the ceremony on `addr`. Requests are handled one at a
+    /// time, so nothing else is served while an upload is verified.
+    /// This only returns if the server fails.
+    pub fn serve<A: ToSocketAddrs>(mut self, addr: A) -> io::Result<()> {
+        let server = Server::http(addr).map_err(io::Error::other)?;
+
+        loop {
+            let mut request = server.recv()?;
+            let response = self.handle(&mut request);
+
+            // A participant hanging up isn't the server's problem
+            let _ = request.respond(response);
+        }
+    }
+
+    fn handle(&mut self, request: &mut Request) -> ResponseBox {
+        let url = request.url().to_string();
+
+        match (request.method(), url.as_str()) {
+            (Method::Get, "/params") => match self.download(request) {
+                Ok(response) => response,
+                Err(e) => text(500, e.to_string()),
+            },
+            (Method::Get, "/params/hash") => text(200, hex(&self.params_hash)),
+            (Method::Get, "/transcript") => match File::open(&self.transcript_path) {
+                Ok(file) => Response::from_file(file).boxed(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => text(200, String::new()),
+                Err(e) => text(500, e.to_string()),
+            },
+            (Method::Get, "/contributions") => match self.storage.as_ref().map(|s| s.list()) {
+                Some(Ok(keys)) => text(200, keys.iter().map(|key| format!("{}\n", key)).collect()),
+                Some(Err(e)) => text(500, e.to_string()),
+                None => text(404, "contributions aren't stored".to_string()),
+            },
+            (Method::Get, _) if url.starts_with("/contributions/") => {
+                let key = &url["/contributions/".len()..];
+                match self.storage.as_ref().map(|s| s.get(key)) {
+                    Some(Ok(reader)) => {
+                        Response::new(StatusCode(200), vec![], reader, None, None).boxed()
+                    }
+                    Some(Err(e))
+                        if matches!(
+                            e.kind(),
+                            io::ErrorKind::NotFound | io::ErrorKind::InvalidInput
+                        ) =>
+                    {
+                        text(404, "not found".to_string())
+                    }
+                    Some(Err(e)) => text(500, e.to_string()),
+                    None => text(404, "contributions aren't stored".to_string()),
+                }
+            }
+            (Method::Post, "/slot") => self.reserve(),
+            (Method::Post, _) if url.starts_with("/contribute/") => {
+                let token = url["/contribute/".len()..].to_string();
+                self.contribute(&token, request)
+            }
+            _ => text(404, "not found".to_string()),
+        }
+    }
+
+    /// Serves the parameters, from the start of the `Range` of the
+    /// request if it has one.
+    fn download(&self, request: &Request) -> io::Result<ResponseBox> {
+        let mut file = File::open(&self.params_path)?;
+        let len = file.metadata()?.len();
+
+        let start = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Range"))
+            .and_then(|h| h.value.as_str().strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .and_then(|start| start.parse::<u64>().ok());
+
+        Ok(match start {
+            // Nothing is left to send from the end of the file on
+            Some(start) if start >= len => {
+                let range = format!("bytes */{}", len);
+                let header = Header::from_bytes("Content-Range", range).unwrap();
+
+                Response::empty(StatusCode(416)).with_header(header).boxed()
+            }
+            Some(start) => {
+                file.seek(SeekFrom::Start(start))?;
+                let range = format!("bytes {}-{}/{}", start, len - 1, len);
+                let header = Header::from_bytes("Content-Range", range).unwrap();
+
+                Response::new(
+                    StatusCode(206),
+                    vec![header],
+                    file,
+                    Some((len - start) as usize),
+                    None,
+                )
+                .boxed()
+            }
+            None => Response::from_file(file).boxed(),
+        })
+    }
+
+    fn reserve(&mut self) -> ResponseBox {
+        let now = Instant::now();
+
+        if let Some(slot) = &self.slot {
+            if slot.expires > now {
+                let secs = (slot.expires - now).as_secs();
+                return text(409, format!("the slot is taken for {} more seconds", secs));
+            }
+        }
+
+        let mut token = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut token);
+        let token = hex(&token);
+
+        self.slot = Some(Slot {
+            token: token.clone(),
+            expires: now + self.slot_timeout,
+        });
+
+        text(200, token)
+    }
+
+    fn contribute(&mut self, token: &str, request: &mut Request) -> ResponseBox {
+        match &self.slot {
+            Some(slot) if slot.token == token && slot.expires > Instant::now() => {}
+            _ => return text(403, "no such slot, or it has timed out".to_string()),
+        }
+
+        let reader = BufReader::with_capacity(1024 * 1024, request.as_reader());
+        let after = match MPCParameters::read(reader, true) {
+            Ok(after) => after,
+            Err(e) => return text(400, format!("invalid parameters: {}", e)),
+        };
+
+        let hash = match verify_contribution(&self.params, &after) {
+            Ok(hash) => hash,
+            Err(e) => return text(400, format!("invalid contribution: {}", e)),
+        };
+
+        if let Err(e) = self.accept(after, hash.as_bytes()) {
+            return text(500, e.to_string());
+        }
+        self.slot = None;
+
+        text(200, hash.to_string())
+    }
+
+    /// Makes `after` the current parameters, and records it in the
+    /// transcript. Nothing changes, in memory or on disk, unless every
+    /// step succeeds.
+    fn accept(&mut self, after: MPCParameters<E>, hash: &[u8; 64]) -> io::Result<()> {
+        let params_tmp = self.params_path.with_extension("tmp");
+        let transcript_tmp = self.transcript_path.with_extension("tmp");
+
+        let result = self.prepare(&after, hash, &params_tmp, &transcript_tmp);
+        let params_hash = match result {
+            Ok(params_hash) => params_hash,
+            Err(e) => {
+                let _ = fs::remove_file(&params_tmp);
+                let _ = fs::remove_file(&transcript_tmp);
+                return Err(e);
+            }
+        };
+
+        // The transcript is renamed first, so a crash in between
+        // leaves it recording a contribution the parameters lack,
+        // rather than parameters with an unrecorded contribution
+        fs::rename(&transcript_tmp, &self.transcript_path)?;
+        fs::rename(&params_tmp, &self.params_path)?;
+
+        self.params = after;
+        self.params_hash = params_hash;
+
+        Ok(())
+    }
+
+    /// Writes the parameters and transcript `accept` makes current to
+    /// the side, so they're never left half written, and stores the
+    /// parameters. Returns their hash.
+    fn prepare(
+        &mut self,
+        after: &MPCParameters<E>,
+        hash: &[u8; 64],
+        params_tmp: &Path,
+        transcript_tmp: &Path,
+    ) -> io::Result<[u8; 64]> {
+        let file = File::create(params_tmp)?;
+        let mut writer = HashWriter::new(BufWriter::with_capacity(1024 * 1024, &file));
+        after.write(&mut writer)?;
+        writer.flush()?;
+        let params_hash = writer.into_hash();
+        file.sync_all()?;
+
+        // Stored before they become current, so a contribution that
+        // can't be stored isn't accepted
+        if let Some(storage) = &mut self.storage {
+            let key = format!("{:06}.params", after.contributions.len());
+            let len = file.metadata()?.len();
+            storage.put(&key, &mut File::open(params_tmp)?, len)?;
+        }
+
+        match fs::copy(&self.transcript_path, transcript_tmp) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            result => {
+                result?;
+            }
+        }
+        let mut transcript = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(transcript_tmp)?;
+        writeln!(
+            transcript,
+            "{{\"contribution_hash\":\"{}\",\"entry\":{}}}",
+            hex(hash),
+            after.to_log_entry().to_json()
+        )?;
+        transcript.sync_all()?;
+
+        Ok(params_hash)
+    }
+}
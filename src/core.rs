@@ -0,0 +1,163 @@
+//! The math of a contribution on its own: sampling delta, computing
+//! the public key that proves knowledge of it, and applying it to
+//! the H and L queries. This module only uses `core` and `alloc`,
+//! with no I/O, threads or clock, so that a contribution can be
+//! computed inside an HSM or a secure enclave that is handed the H
+//! and L queries in RAM. Only the resulting `ContributionPackage`
+//! leaves it, and the host applies it with
+//! `MPCParameters::apply_package`, which checks it first.
+//!
+//! ```rust,ignore
+//! // On the host
+//! let challenge = params.prepare_challenge();
+//! let vk = &params.get_params().vk;
+//! let (h, l) = (params.get_params().h.to_vec(), params.get_params().l.to_vec());
+//!
+//! // In the enclave
+//! let pkg = phase2::core::contribute(&mut rng, &LegacyScheme, &challenge, vk.delta_g2, h, l);
+//!
+//! // Back on the host
+//! let hash = params.apply_package(pkg)?;
+//! ```
+
+use alloc::vec::Vec;
+use blake2_rfc::blake2b::Blake2b;
+use core::ops::Mul;
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, Group, UncompressedEncoding, Wnaf};
+use rand::RngCore;
+use zeroize::Zeroize;
+
+use crate::{Challenge, ContributionPackage, MPCEngine, PrivateKey, PublicKey, TranscriptScheme};
+
+extern crate alloc;
+
+/// Feeds `pubkey` to `hasher`, as `PublicKey::write` serializes it.
+fn hash_pubkey<E: MPCEngine>(hasher: &mut Blake2b, pubkey: &PublicKey<E>) {
+    hasher.update(pubkey.delta_after.to_uncompressed().as_ref());
+    hasher.update(pubkey.s.to_uncompressed().as_ref());
+    hasher.update(pubkey.s_delta.to_uncompressed().as_ref());
+    hasher.update(pubkey.r_delta.to_uncompressed().as_ref());
+    hasher.update(&pubkey.transcript);
+}
+
+/// H(cs_hash | <previous pubkeys> | s | s_delta), the transcript
+/// hash a contribution's public key commits to.
+pub(crate) fn transcript_hash<E: MPCEngine>(
+    cs_hash: &[u8; 64],
+    previous: &[PublicKey<E>],
+    s: E::G1Affine,
+    s_delta: E::G1Affine,
+) -> [u8; 64] {
+    let mut hasher = Blake2b::new(64);
+    hasher.update(cs_hash);
+    for pubkey in previous {
+        hash_pubkey(&mut hasher, pubkey);
+    }
+    hasher.update(s.to_uncompressed().as_ref());
+    hasher.update(s_delta.to_uncompressed().as_ref());
+
+    let mut transcript = [0u8; 64];
+    transcript.copy_from_slice(hasher.finalize().as_ref());
+    transcript
+}
+
+/// Compute a keypair, given the current parameters. Keypairs
+/// cannot be reused for multiple contributions or contributions
+/// in different parameters.
+pub(crate) fn keypair<E: MPCEngine, R: RngCore>(
+    rng: &mut R,
+    scheme: &dyn TranscriptScheme<E>,
+    cs_hash: &[u8; 64],
+    previous: &[PublicKey<E>],
+    delta_before: E::G1Affine,
+) -> (PublicKey<E>, PrivateKey<E>) {
+    // Sample random delta
+    let mut delta: E::Fr = E::Fr::random(&mut *rng);
+    let s = E::G1::random(rng).to_affine();
+
+    let keypair = keypair_with(scheme, delta, s, cs_hash, previous, delta_before);
+    delta.zeroize();
+
+    keypair
+}
+
+/// The rest of `keypair`, given the randomness it samples. This lets
+/// `contribute_streaming` sample it before the transcript is known.
+pub(crate) fn keypair_with<E: MPCEngine>(
+    scheme: &dyn TranscriptScheme<E>,
+    mut delta: E::Fr,
+    s: E::G1Affine,
+    cs_hash: &[u8; 64],
+    previous: &[PublicKey<E>],
+    delta_before: E::G1Affine,
+) -> (PublicKey<E>, PrivateKey<E>) {
+    // Compute delta s-pair in G1
+    let s_delta = s.mul(delta).to_affine();
+
+    let transcript = transcript_hash(cs_hash, previous, s, s_delta);
+
+    // Compute delta s-pair in G2
+    let r = scheme.hash_to_g2(&transcript).to_affine();
+    let r_delta = r.mul(delta).to_affine();
+
+    let pubkey = PublicKey {
+        delta_after: delta_before.mul(delta).to_affine(),
+        s,
+        s_delta,
+        r_delta,
+        transcript,
+    };
+    let privkey = PrivateKey { delta };
+    delta.zeroize();
+
+    (pubkey, privkey)
+}
+
+/// Exponentiates all of `bases` by `coeff`, on this thread.
+fn exp_query<E: MPCEngine>(bases: &mut [E::G1Affine], coeff: &E::Fr) {
+    let mut wnaf = Wnaf::new();
+    let mut wnaf = wnaf.scalar(coeff);
+
+    let projective: Vec<E::G1> = bases
+        .iter()
+        .map(|base| wnaf.base(base.to_curve()))
+        .collect();
+    E::G1::batch_normalize(&projective, bases);
+}
+
+/// Contribute to the parameters that `challenge` was made from, given
+/// their delta in G2 and their H and L queries, with randomness from
+/// `rng`. The contribution is the same as `MPCParameters::contribute`
+/// would make with the same `rng` and `scheme`, but only the package
+/// to apply to the parameters is returned, and the secret never
+/// leaves this function.
+pub fn contribute<E: MPCEngine, R: RngCore>(
+    rng: &mut R,
+    scheme: &dyn TranscriptScheme<E>,
+    challenge: &Challenge<E>,
+    delta_g2: E::G2Affine,
+    mut h: Vec<E::G1Affine>,
+    mut l: Vec<E::G1Affine>,
+) -> ContributionPackage<E> {
+    let (pubkey, privkey) = keypair(
+        rng,
+        scheme,
+        &challenge.cs_hash,
+        &challenge.contributions,
+        challenge.delta_g1,
+    );
+
+    let mut delta_inv = privkey.delta.invert().expect("nonzero");
+    exp_query::<E>(&mut l, &delta_inv);
+    exp_query::<E>(&mut h, &delta_inv);
+    delta_inv.zeroize();
+
+    ContributionPackage {
+        pubkey,
+        delta_g1: challenge.delta_g1.mul(privkey.delta).to_affine(),
+        delta_g2: delta_g2.mul(privkey.delta).to_affine(),
+        h,
+        l,
+    }
+}
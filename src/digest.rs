@@ -0,0 +1,281 @@
+//! Small summaries of parameters, for quickly checking contributions
+//! before they are verified in full, and for checking that a file is
+//! the one that was published.
+
+use bellman::groth16::VerifyingKey;
+use bls12_381::Bls12;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::prime::PrimeCurveAffine;
+use pairing::group::UncompressedEncoding;
+use pairing::Engine;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::{
+    check_pubkey, header, read_contributions_len, read_point, same_ratio, ContributionHash,
+    FormatVersion, HashReader, HashWriter, LegacyScheme, MPCEngine, MPCParameters, PublicKey,
+    SchemeId, TranscriptScheme, VerificationError,
+};
+
+/// A summary of parameters: their `cs_hash`, delta, contributions,
+/// transcript scheme, the lengths of the H and L queries, and the
+/// BLAKE2b hash of the parameters as serialized by
+/// `MPCParameters::write`, but none of the queries themselves. It's a few hundred bytes per
+/// contribution, however large the circuit.
+///
+/// A coordinator can publish the digest of each file it hands out.
+/// Participants then compute the digest of the file they received
+/// once, and compare it with the published one, and anyone can
+/// check the chain of published digests with `verify_digest_chain`.
+#[derive(Clone)]
+pub struct ParamsDigest<E: Engine = Bls12> {
+    cs_hash: [u8; 64],
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    h_len: usize,
+    l_len: usize,
+    contributions: Vec<PublicKey<E>>,
+    params_hash: [u8; 64],
+    scheme: Arc<dyn TranscriptScheme<E>>,
+}
+
+impl<E: Engine> PartialEq for ParamsDigest<E> {
+    fn eq(&self, other: &ParamsDigest<E>) -> bool {
+        self.cs_hash[..] == other.cs_hash[..]
+            && self.delta_g1 == other.delta_g1
+            && self.delta_g2 == other.delta_g2
+            && self.h_len == other.h_len
+            && self.l_len == other.l_len
+            && self.contributions == other.contributions
+            && self.params_hash[..] == other.params_hash[..]
+            && self.scheme.id() == other.scheme.id()
+    }
+}
+
+impl<E: MPCEngine> ParamsDigest<E> {
+    /// Compute the digest of serialized parameters (as written by
+    /// `MPCParameters::write`), reading them once. The queries are
+    /// hashed, but not decoded or checked.
+    pub fn compute<R: Read>(reader: R) -> io::Result<ParamsDigest<E>> {
+        // Skips a query, returning its length
+        fn skip_query<G: UncompressedEncoding, R: Read>(reader: &mut R) -> io::Result<usize> {
+            let len = reader.read_u32::<BigEndian>()?;
+            let bytes = u64::from(len) * G::Uncompressed::default().as_ref().len() as u64;
+            if io::copy(&mut reader.take(bytes), &mut io::sink())? != bytes {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            Ok(len as usize)
+        }
+
+        let mut hashed = HashReader::new(reader);
+        let (header, mut reader) = header::read_header::<E, _>(&mut hashed)?;
+        let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+        let scheme = header::scheme_of(&header)?;
+
+        let vk = VerifyingKey::<E>::read(&mut reader)?;
+        let h_len = skip_query::<E::G1Affine, _>(&mut reader)?;
+        let l_len = skip_query::<E::G1Affine, _>(&mut reader)?;
+        skip_query::<E::G1Affine, _>(&mut reader)?;
+        skip_query::<E::G1Affine, _>(&mut reader)?;
+        skip_query::<E::G2Affine, _>(&mut reader)?;
+
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&cs_hash)?;
+        }
+
+        let (_, count) = read_contributions_len(&mut reader, version)?;
+        let contributions = (0..count)
+            .map(|_| PublicKey::read(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // Anything after the parameters is hashed too, so that the
+        // hash is that of the whole file
+        io::copy(&mut reader, &mut io::sink())?;
+
+        Ok(ParamsDigest {
+            cs_hash,
+            delta_g1: vk.delta_g1,
+            delta_g2: vk.delta_g2,
+            h_len,
+            l_len,
+            contributions,
+            params_hash: hashed.into_hash(),
+            scheme,
+        })
+    }
+
+    /// The `cs_hash` of the parameters.
+    pub fn cs_hash(&self) -> &[u8; 64] {
+        &self.cs_hash
+    }
+
+    /// The number of contributions.
+    pub fn contribution_count(&self) -> usize {
+        self.contributions.len()
+    }
+
+    /// The delta of the parameters, in G1.
+    pub fn delta_g1(&self) -> E::G1Affine {
+        self.delta_g1
+    }
+
+    /// The delta of the parameters, in G2.
+    pub fn delta_g2(&self) -> E::G2Affine {
+        self.delta_g2
+    }
+
+    /// The BLAKE2b hash of the serialized parameters.
+    pub fn params_hash(&self) -> &[u8; 64] {
+        &self.params_hash
+    }
+
+    /// The transcript scheme of the parameters.
+    pub fn transcript_scheme(&self) -> &dyn TranscriptScheme<E> {
+        &*self.scheme
+    }
+
+    /// Serialize this digest.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.cs_hash)?;
+        writer.write_all(self.delta_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.delta_g2.to_uncompressed().as_ref())?;
+        writer.write_u32::<BigEndian>(self.h_len as u32)?;
+        writer.write_u32::<BigEndian>(self.l_len as u32)?;
+        writer.write_all(&self.params_hash)?;
+
+        writer.write_u32::<BigEndian>(self.contributions.len() as u32)?;
+        for pubkey in &self.contributions {
+            pubkey.write(&mut writer)?;
+        }
+
+        // Last, so that digests from before it was written can be read
+        self.scheme.id().write(writer)
+    }
+
+    /// Deserialize a digest, checking that its points are valid.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<ParamsDigest<E>> {
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
+        let delta_g1 = read_point(&mut reader)?;
+        let delta_g2 = read_point(&mut reader)?;
+        let h_len = reader.read_u32::<BigEndian>()? as usize;
+        let l_len = reader.read_u32::<BigEndian>()? as usize;
+        let mut params_hash = [0u8; 64];
+        reader.read_exact(&mut params_hash)?;
+
+        let contributions = (0..reader.read_u32::<BigEndian>()?)
+            .map(|_| PublicKey::read(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let scheme = match SchemeId::read(&mut reader) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Arc::new(LegacyScheme),
+            id => id?.scheme()?,
+        };
+
+        Ok(ParamsDigest {
+            cs_hash,
+            delta_g1,
+            delta_g2,
+            h_len,
+            l_len,
+            contributions,
+            params_hash,
+            scheme,
+        })
+    }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// The digest of these parameters. This serializes and hashes
+    /// them, which takes as long as writing them.
+    pub fn digest(&self) -> ParamsDigest<E> {
+        let mut sink = HashWriter::new(io::sink());
+        self.write(&mut sink).unwrap();
+
+        ParamsDigest {
+            cs_hash: self.cs_hash,
+            delta_g1: self.params.vk.delta_g1,
+            delta_g2: self.params.vk.delta_g2,
+            h_len: self.params.h.len(),
+            l_len: self.params.l.len(),
+            contributions: self.contributions.clone(),
+            params_hash: sink.into_hash(),
+            scheme: self.transcript_scheme.clone(),
+        }
+    }
+}
+
+/// The cheap part of `verify_contribution`: checks that `after` has
+/// exactly one more contribution than `before`, whose public key has
+/// a valid transcript and signature of knowledge and moves the delta
+/// of `before` to the delta of `after`. Takes a handful of pairings,
+/// however large the circuit, so a coordinator can turn away bad
+/// uploads before queuing them for `verify_contribution`.
+///
+/// The H and L queries aren't checked, nor are the queries and
+/// verifying key elements that contributions can't change, so
+/// parameters that pass must still be verified in full.
+pub fn quick_verify_contribution<E: MPCEngine>(
+    before: &ParamsDigest<E>,
+    after: &ParamsDigest<E>,
+) -> Result<ContributionHash, VerificationError> {
+    if after.contributions.len() != before.contributions.len() + 1 {
+        return Err(VerificationError::NotOneContribution);
+    }
+    if before.contributions[..] != after.contributions[..before.contributions.len()] {
+        return Err(VerificationError::PreviousContributionsChanged);
+    }
+    if before.cs_hash[..] != after.cs_hash[..] {
+        return Err(VerificationError::CsHashMismatch);
+    }
+    if before.h_len != after.h_len {
+        return Err(VerificationError::HQueryLengthMismatch);
+    }
+    if before.l_len != after.l_len {
+        return Err(VerificationError::LQueryLengthMismatch);
+    }
+    if before.scheme.id() != after.scheme.id() {
+        return Err(VerificationError::TranscriptSchemeMismatch);
+    }
+
+    let pubkey = after.contributions.last().unwrap();
+    check_pubkey(
+        &*before.scheme,
+        &before.cs_hash,
+        &before.contributions,
+        before.delta_g1,
+        pubkey,
+    )?;
+
+    if pubkey.delta_after != after.delta_g1 {
+        return Err(VerificationError::DeltaG1Mismatch);
+    }
+    if !same_ratio(
+        (E::G1Affine::generator(), pubkey.delta_after),
+        (E::G2Affine::generator(), after.delta_g2),
+    ) {
+        return Err(VerificationError::DeltaG2Mismatch);
+    }
+
+    Ok(pubkey.hash())
+}
+
+/// Check a chain of digests, e.g. those a coordinator published
+/// after each contribution, with `quick_verify_contribution`: each
+/// digest must follow from the one before it by a single valid
+/// contribution. Returns the hashes of those contributions.
+///
+/// As with `quick_verify_contribution`, this doesn't check the
+/// queries; it shows that the digests are consistent, and a file can
+/// be matched to one of them by comparing its digest.
+pub fn verify_digest_chain<E: MPCEngine>(
+    digests: &[ParamsDigest<E>],
+) -> Result<Vec<ContributionHash>, VerificationError> {
+    digests
+        .windows(2)
+        .map(|pair| quick_verify_contribution(&pair[0], &pair[1]))
+        .collect()
+}
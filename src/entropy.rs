@@ -0,0 +1,154 @@
+//! Gathering the randomness of a contribution from several sources,
+//! so that a participant doesn't have to trust any one of them.
+//!
+//! A contribution is safe as long as its randomness is unknown to
+//! everybody else. The OS's RNG is usually good enough, but mixing
+//! in sources the participant controls (some typed text, a dump of a
+//! hardware RNG, the timing of their own machine) means that a flawed
+//! or backdoored OS RNG isn't enough to recover the secret.
+
+use blake2_rfc::blake2b::Blake2b;
+use rand::RngCore;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{self, Read};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::time::Instant;
+
+/// Domain separator for deriving a seed from the sources.
+const ENTROPY_DOMAIN: &[u8] = b"phase2 contribution entropy";
+
+/// How many timings are taken for the timing jitter.
+const JITTER_SAMPLES: usize = 4096;
+
+/// Collects the timing jitter of a small busy loop, which varies with
+/// caches, interrupts and scheduling.
+fn jitter() -> Vec<u8> {
+    let mut samples = Vec::with_capacity(JITTER_SAMPLES * 4);
+    let mut x = 0u64;
+    let mut last = Instant::now();
+    for i in 0..JITTER_SAMPLES as u64 {
+        for j in 0..64 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(i ^ j);
+        }
+        std::hint::black_box(x);
+
+        let now = Instant::now();
+        let delta = now.duration_since(last).as_nanos() as u32;
+        samples.extend_from_slice(&delta.to_le_bytes());
+        last = now;
+    }
+
+    samples
+}
+
+/// A builder for the seed of a contribution, which mixes randomness
+/// from the OS with any of: text the participant typed, the contents
+/// of a file, and timing jitter. The sources are hashed together with
+/// BLAKE2b, so the seed is unpredictable if any one of them is.
+///
+/// ```rust,ignore
+/// let entropy = ContributionEntropy::new()
+///     .with_input(typed_text)
+///     .with_jitter();
+/// println!("{}", entropy.attestation());
+/// let hash = params.contribute_with_seed(entropy.seed());
+/// ```
+#[derive(Default)]
+pub struct ContributionEntropy {
+    input: Option<Vec<u8>>,
+    file: Option<Vec<u8>>,
+    jitter: bool,
+}
+
+impl ContributionEntropy {
+    /// Entropy from the OS's RNG only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mix in text the participant typed (or any other bytes they
+    /// provide).
+    pub fn with_input(mut self, input: impl AsRef<[u8]>) -> Self {
+        self.input
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(input.as_ref());
+        self
+    }
+
+    /// Mix in at most `max_len` bytes read from the file at `path`,
+    /// e.g. a dump of a hardware RNG, or the device itself. Fails if
+    /// nothing could be read.
+    #[cfg(feature = "fs")]
+    pub fn with_file<P: AsRef<Path>>(mut self, path: P, max_len: u64) -> io::Result<Self> {
+        let mut bytes = vec![];
+        File::open(path)?.take(max_len).read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.file.get_or_insert_with(Vec::new).extend(bytes);
+        Ok(self)
+    }
+
+    /// Mix in the timing jitter of this machine, measured when the
+    /// seed is derived.
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// The classes of entropy that are mixed, for the participant to
+    /// publish along with their contribution hash, e.g.
+    /// `entropy: os, user-input, timing-jitter`. The entropy itself
+    /// isn't revealed.
+    pub fn attestation(&self) -> String {
+        let mut classes = vec!["os"];
+        if self.input.is_some() {
+            classes.push("user-input");
+        }
+        if self.file.is_some() {
+            classes.push("file");
+        }
+        if self.jitter {
+            classes.push("timing-jitter");
+        }
+
+        format!("entropy: {}", classes.join(", "))
+    }
+
+    /// Draw randomness from the OS (and the timing jitter, if
+    /// enabled) and derive the seed for `contribute_with_seed` from
+    /// all the sources. Each source is hashed with its class and
+    /// length, so no two combinations of sources can collide.
+    pub fn seed(self) -> [u8; 32] {
+        let mut os = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut os);
+
+        let jitter = if self.jitter { Some(jitter()) } else { None };
+
+        let sources = [
+            ("os", Some(&os[..])),
+            ("user-input", self.input.as_deref()),
+            ("file", self.file.as_deref()),
+            ("timing-jitter", jitter.as_deref()),
+        ];
+
+        let mut hasher = Blake2b::new(32);
+        hasher.update(ENTROPY_DOMAIN);
+        for (class, bytes) in sources {
+            if let Some(bytes) = bytes {
+                hasher.update(&(class.len() as u32).to_be_bytes());
+                hasher.update(class.as_bytes());
+                hasher.update(&(bytes.len() as u64).to_be_bytes());
+                hasher.update(bytes);
+            }
+        }
+
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(hasher.finalize().as_ref());
+        seed
+    }
+}
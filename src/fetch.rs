@@ -0,0 +1,140 @@
+//! Fetching of missing Powers of Tau radix files, enabled by the
+//! `fetch` feature.
+
+use bellman::{Circuit, SynthesisError};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::{no_progress, phase1, HashWriter, MPCEngine, MPCParameters, Progress};
+
+/// An error from `MPCParameters::new_with_autofetch`.
+#[derive(Debug)]
+pub enum FetchError {
+    /// The circuit couldn't be synthesized, or the radix file
+    /// couldn't be parsed.
+    Synthesis(SynthesisError),
+    /// The radix file couldn't be read from or written to disk.
+    Io(io::Error),
+    /// The download failed.
+    Network(Box<ureq::Error>),
+    /// No checksum is known for the radix file of this exponent, so a
+    /// download of it can't be trusted.
+    UnknownChecksum { exp: u32 },
+    /// The downloaded radix file didn't match its known checksum.
+    ChecksumMismatch { exp: u32 },
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::Synthesis(e) => write!(f, "synthesis error: {}", e),
+            FetchError::Io(e) => write!(f, "I/O error: {}", e),
+            FetchError::Network(e) => write!(f, "download failed: {}", e),
+            FetchError::UnknownChecksum { exp } => {
+                write!(f, "no known checksum for phase1radix2m{}", exp)
+            }
+            FetchError::ChecksumMismatch { exp } => {
+                write!(f, "downloaded phase1radix2m{} has the wrong checksum", exp)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Synthesis(e) => Some(e),
+            FetchError::Io(e) => Some(e),
+            FetchError::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SynthesisError> for FetchError {
+    fn from(e: SynthesisError) -> FetchError {
+        FetchError::Synthesis(e)
+    }
+}
+
+impl From<io::Error> for FetchError {
+    fn from(e: io::Error) -> FetchError {
+        FetchError::Io(e)
+    }
+}
+
+impl From<ureq::Error> for FetchError {
+    fn from(e: ureq::Error) -> FetchError {
+        FetchError::Network(Box::new(e))
+    }
+}
+
+/// Downloads `phase1radix2m{exp}` into `path`, checking its BLAKE2b
+/// hash before moving it into place so a bad download is never cached.
+fn download(
+    url_template: &str,
+    exp: u32,
+    checksum: &[u8; 64],
+    path: &Path,
+) -> Result<(), FetchError> {
+    let url = url_template.replace("{exp}", &exp.to_string());
+    let mut reader = ureq::get(&url).call()?.into_reader();
+
+    let partial = path.with_extension("part");
+    let mut writer = HashWriter::new(File::create(&partial)?);
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+
+    if writer.into_hash() != *checksum {
+        fs::remove_file(&partial)?;
+        return Err(FetchError::ChecksumMismatch { exp });
+    }
+
+    fs::rename(&partial, path)?;
+
+    Ok(())
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Like `new()`, but reads the radix file from `dir`, first
+    /// downloading it from `url_template` (with `{exp}` replaced by
+    /// the domain's exponent) if it isn't there yet.
+    ///
+    /// `checksums` lists the known BLAKE2b hashes of radix files by
+    /// exponent. A download is only cached if it matches, and a radix
+    /// file with no known checksum isn't downloaded at all. Files
+    /// already in `dir` are used as they are.
+    pub fn new_with_autofetch<C>(
+        circuit: C,
+        url_template: &str,
+        dir: &Path,
+        checksums: &[(u32, [u8; 64])],
+    ) -> Result<MPCParameters<E>, FetchError>
+    where
+        C: Circuit<E::Fr>,
+    {
+        MPCParameters::new_with_radix(
+            circuit,
+            phase1::MAX_EXP,
+            |exp| {
+                let path = dir.join(format!("phase1radix2m{}", exp));
+
+                if !path.exists() {
+                    let checksum = checksums
+                        .iter()
+                        .find(|(e, _)| *e == exp)
+                        .map(|(_, checksum)| checksum)
+                        .ok_or(FetchError::UnknownChecksum { exp })?;
+
+                    fs::create_dir_all(dir)?;
+                    download(url_template, exp, checksum, &path)?;
+                }
+
+                Ok(File::open(path)?)
+            },
+            Progress::new(&no_progress),
+        )
+    }
+}
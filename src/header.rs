@@ -0,0 +1,162 @@
+//! The header `MPCParameters::write` puts before the parameters, so
+//! that a file for another curve, or in a layout this version doesn't
+//! know, is rejected up front instead of being misread:
+//!
+//! - the 4 bytes `\x89PH2`;
+//! - the `FormatVersion` of what follows, as a byte (1, 2 or 3);
+//! - the first 8 bytes of a BLAKE2b hash of the generators of G1 and
+//!   G2, uncompressed, which identifies the curve;
+//! - the first 8 bytes of the `cs_hash` of the parameters;
+//! - the `SchemeId` of the transcript scheme of the parameters: a
+//!   byte for its kind (0 for `LegacyScheme`, 1 for `Rfc9380Scheme`
+//!   and 2 for custom schemes), then for the latter two the length of
+//!   their tag or name as a byte, and the tag or name.
+//!
+//! Files from before the header start right away with the verifying
+//! key. The magic can't be mistaken for one: its first byte has the
+//! compression flag set, which an uncompressed point never has.
+
+use blake2_rfc::blake2b::Blake2b;
+use group::prime::PrimeCurveAffine;
+use pairing::group::UncompressedEncoding;
+use std::io::{self, Cursor, Read, Write};
+use std::sync::Arc;
+
+use crate::{FormatVersion, LegacyScheme, MPCEngine, SchemeId, TranscriptScheme};
+
+const MAGIC: [u8; 4] = *b"\x89PH2";
+
+/// The length of the header up to the scheme, in bytes.
+const FIXED_LEN: usize = 21;
+
+/// The identifier of the curve of `E` in the header.
+fn curve_id<E: MPCEngine>() -> [u8; 8] {
+    let mut hasher = Blake2b::new(64);
+    hasher.update(E::G1Affine::generator().to_uncompressed().as_ref());
+    hasher.update(E::G2Affine::generator().to_uncompressed().as_ref());
+
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&hasher.finalize().as_ref()[..8]);
+    id
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// What a header says about the parameters after it.
+pub(crate) struct Header {
+    pub(crate) version: FormatVersion,
+    cs_hash_prefix: [u8; 8],
+    pub(crate) scheme: SchemeId,
+}
+
+impl Header {
+    /// Fails unless the parameters after the header have `cs_hash`.
+    pub(crate) fn check_cs_hash(&self, cs_hash: &[u8; 64]) -> io::Result<()> {
+        if cs_hash[..8] != self.cs_hash_prefix {
+            return Err(invalid(
+                "the header doesn't match the cs_hash of the parameters".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The length of this header, in bytes.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn len(&self) -> usize {
+        FIXED_LEN + self.scheme.len()
+    }
+
+    /// Writes this header again, before parameters over `E`.
+    pub(crate) fn write<E: MPCEngine, W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let version = match self.version {
+            FormatVersion::V1 => 1,
+            FormatVersion::V2 => 2,
+            FormatVersion::V3 => 3,
+        };
+
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[version])?;
+        writer.write_all(&curve_id::<E>())?;
+        writer.write_all(&self.cs_hash_prefix)?;
+        self.scheme.write(writer)
+    }
+}
+
+/// Writes the header of parameters over `E` with `cs_hash` and the
+/// transcript scheme `scheme`, laid out as `version`.
+pub(crate) fn write_header<E: MPCEngine, W: Write>(
+    writer: W,
+    version: FormatVersion,
+    cs_hash: &[u8; 64],
+    scheme: SchemeId,
+) -> io::Result<()> {
+    let mut cs_hash_prefix = [0u8; 8];
+    cs_hash_prefix.copy_from_slice(&cs_hash[..8]);
+
+    Header {
+        version,
+        cs_hash_prefix,
+        scheme,
+    }
+    .write::<E, _>(writer)
+}
+
+/// A reader past the header, if there was one.
+pub(crate) type AfterHeader<R> = io::Chain<Cursor<[u8; 4]>, R>;
+
+/// Reads the header from `reader`, if it starts with one, checking
+/// that it's for `E` and a known version. Whatever was read of a
+/// headerless file is put back in front of the returned reader.
+pub(crate) fn read_header<E: MPCEngine, R: Read>(
+    mut reader: R,
+) -> io::Result<(Option<Header>, AfterHeader<R>)> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Ok((None, Cursor::new(magic).chain(reader)));
+    }
+
+    let mut rest = [0u8; FIXED_LEN - 4];
+    reader.read_exact(&mut rest)?;
+
+    let version = match rest[0] {
+        1 => FormatVersion::V1,
+        2 => FormatVersion::V2,
+        3 => FormatVersion::V3,
+        v => return Err(invalid(format!("unknown format version {}", v))),
+    };
+    if rest[1..9] != curve_id::<E>() {
+        return Err(invalid("the parameters are for a different curve".into()));
+    }
+
+    let mut cs_hash_prefix = [0u8; 8];
+    cs_hash_prefix.copy_from_slice(&rest[9..]);
+    let scheme = SchemeId::read(&mut reader)?;
+
+    // The magic has been read, so nothing is put back
+    let mut done = Cursor::new(magic);
+    done.set_position(4);
+
+    Ok((
+        Some(Header {
+            version,
+            cs_hash_prefix,
+            scheme,
+        }),
+        done.chain(reader),
+    ))
+}
+
+/// The transcript scheme of parameters with `header`: the one it
+/// names, or `LegacyScheme` for parameters without one.
+pub(crate) fn scheme_of<E: MPCEngine>(
+    header: &Option<Header>,
+) -> io::Result<Arc<dyn TranscriptScheme<E>>> {
+    match header {
+        Some(header) => header.scheme.scheme(),
+        None => Ok(Arc::new(LegacyScheme)),
+    }
+}
@@ -146,11 +146,15 @@
 //! for our circuit:
 //!
 //! ```rust,ignore
-//! let mut params = phase2::MPCParameters::new(CubeRoot {
+//! let mut params = phase2::MPCParameters::<Bls12>::new(CubeRoot {
 //!     cube_root: None
 //! }).unwrap();
 //! ```
 //!
+//! `MPCParameters` is generic over the pairing engine, so the same
+//! works for other curves implementing the `pairing` traits, such
+//! as BN254, given Powers of Tau for that curve.
+//!
 //! The first time you try this, it will try to read a file like
 //! `phase1radix2m2` from the current directory. You need to grab
 //! that from the [Powers of Tau](https://lists.z.cash.foundation/pipermail/zapps-wg/2018/000362.html).
@@ -189,22 +193,103 @@
 //! `params.params()`, so that you can interact with the bellman APIs
 //! just as before.
 
-use bellman::groth16::{Parameters, VerifyingKey};
+use bellman::groth16::{self, prepare_verifying_key, Parameters, Proof, VerifyingKey};
 use bellman::multicore::Worker;
 use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 use blake2_rfc::blake2b::Blake2b;
 use bls12_381::Bls12;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use ff::{Field, PrimeField};
-use group::{prime::PrimeCurveAffine, Wnaf, WnafGroup};
+use ff::{Field, PrimeField, PrimeFieldBits};
+use group::{prime::PrimeCurveAffine, GroupEncoding, Wnaf, WnafGroup};
 use pairing::group::{Curve, Group, UncompressedEncoding};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
+use std::fmt;
+#[cfg(feature = "fs")]
 use std::fs::File;
 use std::io;
-use std::io::{BufReader, Read, Write};
-use std::ops::{AddAssign, Mul};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::iter;
+use std::ops::{AddAssign, Mul, Range};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::{keypair, keypair_with};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "ark")]
+mod ark;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "attest")]
+mod attest;
+mod audit;
+mod bundle;
+#[cfg(feature = "fs")]
+mod checkpoint;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "coordinator")]
+pub mod coordinator;
+pub mod core;
+mod digest;
+mod entropy;
+#[cfg(feature = "fetch")]
+mod fetch;
+mod header;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multicore;
+mod offline;
+pub mod phase1;
+#[cfg(feature = "snarkjs")]
+mod r1cs;
+pub mod radix;
+mod scheme;
+mod sections;
+#[cfg(feature = "shamir")]
+pub mod shamir;
+#[cfg(feature = "coordinator")]
+mod storage;
+mod streaming;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+pub mod util;
+mod verifier;
+#[cfg(feature = "snarkjs")]
+mod zkey;
+
+#[cfg(feature = "async")]
+pub use async_io::verify_contribution_async;
+#[cfg(feature = "attest")]
+pub use attest::{Attestation, AttestedContribution, TranscriptAttestation};
+pub use audit::{audit_circuit, AuditReport, NamespaceCounts};
+pub use bundle::{BundleError, MPCBundle};
+pub use digest::{quick_verify_contribution, verify_digest_chain, ParamsDigest};
+pub use entropy::ContributionEntropy;
+#[cfg(feature = "fetch")]
+pub use fetch::FetchError;
+#[cfg(feature = "mmap")]
+pub use mmap::MPCParametersFile;
+pub use multicore::Config;
+pub use offline::{respond, respond_with_scheme, Challenge, Response};
+#[cfg(feature = "rfc9380")]
+pub use scheme::Rfc9380Scheme;
+pub use scheme::{LegacyScheme, SchemeId, TranscriptScheme};
+pub use sections::{Section, SectionError, SectionManifest};
+pub use streaming::verify_contribution_streamed;
+
+/// The pairing engines parameters can be created for: any engine
+/// from the `pairing` crate with efficient multi-pairings, whose
+/// groups support windowed exponentiation, and whose scalars can be
+/// zeroized, as the secrets of contributions are. `Bls12` from
+/// `bls12_381`, which is the default, is one of them.
+pub trait MPCEngine: MultiMillerLoop<G1: WnafGroup, G2: WnafGroup, Fr: Zeroize> {}
+
+impl<E> MPCEngine for E where E: MultiMillerLoop<G1: WnafGroup, G2: WnafGroup, Fr: Zeroize> {}
 
 /// This is our assembly structure that we'll use to synthesize the
 /// circuit into a QAP.
@@ -321,57 +406,212 @@ impl<Fr: PrimeField> ConstraintSystem<Fr> for KeypairAssembly<Fr> {
     }
 }
 
+impl<Fr: PrimeField> KeypairAssembly<Fr> {
+    /// An empty assembly.
+    fn new() -> Self {
+        KeypairAssembly {
+            num_inputs: 0,
+            num_aux: 0,
+            num_constraints: 0,
+            at_inputs: vec![],
+            bt_inputs: vec![],
+            ct_inputs: vec![],
+            at_aux: vec![],
+            bt_aux: vec![],
+            ct_aux: vec![],
+        }
+    }
+
+    /// Synthesize `circuit` into a new assembly, including the
+    /// "one" input and the constraints on the inputs.
+    fn synthesize<C: Circuit<Fr>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut assembly = KeypairAssembly::new();
+
+        // Allocate the "one" input variable
+        assembly.alloc_input(|| "", || Ok(Fr::ONE))?;
+
+        // Synthesize the circuit.
+        circuit.synthesize(&mut assembly)?;
+
+        // Input constraints to ensure full density of IC query
+        // x * 0 = 0
+        for i in 0..assembly.num_inputs {
+            assembly.enforce(
+                || "",
+                |lc| lc + Variable::new_unchecked(Index::Input(i)),
+                |lc| lc,
+                |lc| lc,
+            );
+        }
+
+        Ok(assembly)
+    }
+
+    /// The canonical hash of the R1CS of the circuit: see
+    /// `circuit_fingerprint`.
+    fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::new(32);
+        hasher.update(CIRCUIT_FINGERPRINT_DOMAIN);
+        hasher.update(&(self.num_constraints as u64).to_be_bytes());
+        hasher.update(&(self.num_inputs as u64).to_be_bytes());
+        hasher.update(&(self.num_aux as u64).to_be_bytes());
+
+        for (inputs, aux) in [
+            (&self.at_inputs, &self.at_aux),
+            (&self.bt_inputs, &self.bt_aux),
+            (&self.ct_inputs, &self.ct_aux),
+        ] {
+            // (constraint, wire, coefficient), numbering the wires
+            // with the inputs first
+            let mut terms: Vec<(usize, usize, Fr)> = inputs
+                .iter()
+                .chain(aux.iter())
+                .enumerate()
+                .flat_map(|(wire, terms)| {
+                    terms
+                        .iter()
+                        .map(move |&(coeff, constraint)| (constraint, wire, coeff))
+                })
+                .collect();
+            terms.sort_by_key(|&(constraint, wire, _)| (constraint, wire));
+
+            // A wire can appear more than once in a linear
+            // combination, so repeated terms are summed, and those
+            // that cancel out dropped
+            let mut merged: Vec<(usize, usize, Fr)> = vec![];
+            for (constraint, wire, coeff) in terms {
+                match merged.last_mut() {
+                    Some(last) if (last.0, last.1) == (constraint, wire) => last.2 += coeff,
+                    _ => merged.push((constraint, wire, coeff)),
+                }
+            }
+            merged.retain(|(_, _, coeff)| !bool::from(coeff.is_zero()));
+
+            hasher.update(&(merged.len() as u64).to_be_bytes());
+            for (constraint, wire, coeff) in merged {
+                hasher.update(&(constraint as u64).to_be_bytes());
+                hasher.update(&(wire as u64).to_be_bytes());
+                hasher.update(coeff.to_repr().as_ref());
+            }
+        }
+
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(hasher.finalize().as_ref());
+        fingerprint
+    }
+}
+
+/// Domain separator for `circuit_fingerprint`.
+const CIRCUIT_FINGERPRINT_DOMAIN: &[u8] = b"phase2 circuit fingerprint";
+
+/// Compute a fingerprint of a circuit from its R1CS alone, so that
+/// participants can check which circuit they're contributing to
+/// without the radix files `cs_hash` depends on. It's BLAKE2b-256
+/// over the number of constraints, inputs and auxiliary variables,
+/// then the nonzero entries of the A, B and C matrices, each sorted
+/// by constraint and variable, with repeated terms summed.
+///
+/// It includes the constraints on the inputs that are added to every
+/// circuit. Parameters created by `new` carry it (see
+/// `MPCParameters::circuit_fingerprint`).
+pub fn circuit_fingerprint<Fr, C>(circuit: C) -> Result<[u8; 32], SynthesisError>
+where
+    Fr: PrimeField,
+    C: Circuit<Fr>,
+{
+    Ok(KeypairAssembly::synthesize(circuit)?.fingerprint())
+}
+
+/// Expands a query that had its points at infinity filtered away
+/// back to one point per variable, given which variables have a
+/// non-empty polynomial.
+#[cfg(any(feature = "snarkjs", feature = "ark"))]
+fn expand<G: PrimeCurveAffine>(query: &[G], density: &[bool]) -> io::Result<Vec<G>> {
+    let mut query = query.iter();
+    let expanded = density
+        .iter()
+        .map(|&dense| {
+            if dense {
+                query.next().copied()
+            } else {
+                Some(G::identity())
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+
+    match expanded {
+        Some(expanded) if query.next().is_none() => Ok(expanded),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "parameters don't match the circuit",
+        )),
+    }
+}
+
 /// This allows others to verify that you contributed. The hash produced
 /// by `MPCParameters::contribute` is just a BLAKE2b hash of this object.
+/// See `ContributionInfo` for its contents.
 #[derive(Clone)]
-struct PublicKey {
+pub struct PublicKey<E: Engine = Bls12> {
     /// This is the delta (in G1) after the transformation, kept so that we
     /// can check correctness of the public keys without having the entire
     /// interstitial parameters for each contribution.
-    delta_after: bls12_381::G1Affine,
+    delta_after: E::G1Affine,
 
     /// Random element chosen by the contributor.
-    s: bls12_381::G1Affine,
+    s: E::G1Affine,
 
     /// That element, taken to the contributor's secret delta.
-    s_delta: bls12_381::G1Affine,
+    s_delta: E::G1Affine,
 
     /// r is H(last_pubkey | s | s_delta), r_delta proves knowledge of delta
-    r_delta: bls12_381::G2Affine,
+    r_delta: E::G2Affine,
 
     /// Hash of the transcript (used for mapping to r)
     transcript: [u8; 64],
 }
 
-impl PartialEq for PublicKey {
-    fn eq(&self, other: &PublicKey) -> bool {
+impl<E: Engine> PartialEq for PublicKey<E> {
+    fn eq(&self, other: &PublicKey<E>) -> bool {
         self.delta_after == other.delta_after
             && self.s == other.s
             && self.s_delta == other.s_delta
             && self.r_delta == other.r_delta
-            && &self.transcript[..] == &other.transcript[..]
+            && self.transcript[..] == other.transcript[..]
     }
 }
 
 /// MPC parameters are just like bellman `Parameters` except, when serialized,
-/// they contain a transcript of contributions at the end, which can be verified.
+/// they contain a transcript of contributions at the end, which can be verified,
+/// and a header at the start (see `write`).
 #[derive(Clone)]
-pub struct MPCParameters {
-    params: Parameters<Bls12>,
+pub struct MPCParameters<E: Engine = Bls12> {
+    params: Parameters<E>,
     cs_hash: [u8; 64],
-    contributions: Vec<PublicKey>,
+    contributions: Vec<PublicKey<E>>,
+    /// Known if the parameters were created here, or read with
+    /// `FormatVersion::V3`. It isn't compared by `eq`.
+    circuit_fingerprint: Option<[u8; 32]>,
+    /// Recorded in the header; `LegacyScheme` unless set otherwise.
+    transcript_scheme: Arc<dyn TranscriptScheme<E>>,
+    /// Only serialized by `write_with_attestations`, and not compared
+    /// by `eq`.
+    #[cfg(feature = "attest")]
+    verifier_attestations: Vec<TranscriptAttestation>,
 }
 
-impl PartialEq for MPCParameters {
-    fn eq(&self, other: &MPCParameters) -> bool {
+impl<E: Engine> PartialEq for MPCParameters<E> {
+    fn eq(&self, other: &MPCParameters<E>) -> bool {
         self.params == other.params
-            && &self.cs_hash[..] == &other.cs_hash[..]
+            && self.cs_hash[..] == other.cs_hash[..]
             && self.contributions == other.contributions
     }
 }
 
-impl PublicKey {
-    fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+impl<E: MPCEngine> PublicKey<E> {
+    /// Serialize this public key, as it appears in the transcript at
+    /// the end of serialized parameters.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.delta_after.to_uncompressed().as_ref())?;
         writer.write_all(self.s.to_uncompressed().as_ref())?;
         writer.write_all(self.s_delta.to_uncompressed().as_ref())?;
@@ -381,13 +621,15 @@ impl PublicKey {
         Ok(())
     }
 
-    fn read<R: Read>(mut reader: R) -> io::Result<PublicKey> {
-        let mut g1_repr = <bls12_381::G1Affine as UncompressedEncoding>::Uncompressed::default();
-        let mut g2_repr = <bls12_381::G2Affine as UncompressedEncoding>::Uncompressed::default();
+    /// Deserialize a public key, checking that its points are valid
+    /// and not the point at infinity.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<PublicKey<E>> {
+        let mut g1_repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
+        let mut g2_repr = <E::G2Affine as UncompressedEncoding>::Uncompressed::default();
 
         reader.read_exact(g1_repr.as_mut())?;
-        let delta_after: bls12_381::G1Affine = Option::from(
-            <bls12_381::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
+        let delta_after: E::G1Affine = Option::from(
+            <E::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
         )
         .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid Data!"))?;
 
@@ -399,8 +641,8 @@ impl PublicKey {
         }
 
         reader.read_exact(g1_repr.as_mut())?;
-        let s: bls12_381::G1Affine = Option::from(
-            <bls12_381::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
+        let s: E::G1Affine = Option::from(
+            <E::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
         )
         .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid Data!"))?;
 
@@ -412,8 +654,8 @@ impl PublicKey {
         }
 
         reader.read_exact(g1_repr.as_mut())?;
-        let s_delta: bls12_381::G1Affine = Option::from(
-            <bls12_381::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
+        let s_delta: E::G1Affine = Option::from(
+            <E::G1Affine as UncompressedEncoding>::from_uncompressed(&g1_repr),
         )
         .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid Data!"))?;
 
@@ -425,8 +667,8 @@ impl PublicKey {
         }
 
         reader.read_exact(g2_repr.as_mut())?;
-        let r_delta: bls12_381::G2Affine = Option::from(
-            <bls12_381::G2Affine as UncompressedEncoding>::from_uncompressed(&g2_repr),
+        let r_delta: E::G2Affine = Option::from(
+            <E::G2Affine as UncompressedEncoding>::from_uncompressed(&g2_repr),
         )
         .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid Data!"))?;
 
@@ -448,6 +690,184 @@ impl PublicKey {
             transcript,
         })
     }
+
+    /// Like `write`, but with compressed points, which over BLS12-381
+    /// takes 304 bytes instead of 544.
+    fn write_compressed<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.delta_after.to_bytes().as_ref())?;
+        writer.write_all(self.s.to_bytes().as_ref())?;
+        writer.write_all(self.s_delta.to_bytes().as_ref())?;
+        writer.write_all(self.r_delta.to_bytes().as_ref())?;
+        writer.write_all(&self.transcript)?;
+
+        Ok(())
+    }
+
+    /// Reads a public key written by `write_compressed`, with the
+    /// same checks as `read`.
+    fn read_compressed<R: Read>(mut reader: R) -> io::Result<PublicKey<E>> {
+        fn read_point<G: PrimeCurveAffine + GroupEncoding, R: Read>(
+            reader: &mut R,
+        ) -> io::Result<G> {
+            let mut repr = G::Repr::default();
+            reader.read_exact(repr.as_mut())?;
+
+            let p: G = Option::from(G::from_bytes(&repr))
+                .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid Data!"))?;
+
+            if p.is_identity().into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+
+            Ok(p)
+        }
+
+        let delta_after = read_point(&mut reader)?;
+        let s = read_point(&mut reader)?;
+        let s_delta = read_point(&mut reader)?;
+        let r_delta = read_point(&mut reader)?;
+
+        let mut transcript = [0u8; 64];
+        reader.read_exact(&mut transcript)?;
+
+        Ok(PublicKey {
+            delta_after,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        })
+    }
+
+    /// The hash of this public key, which is what contributors
+    /// are given to find their contribution in a transcript.
+    pub fn hash(&self) -> ContributionHash {
+        let sink = io::sink();
+        let mut sink = HashWriter::new(sink);
+        self.write(&mut sink).unwrap();
+        ContributionHash(sink.into_hash())
+    }
+}
+
+/// A read-only view of a contribution's public key, for tooling that
+/// inspects transcripts.
+#[derive(Clone, Copy)]
+pub struct ContributionInfo<'a, E: Engine = Bls12> {
+    pubkey: &'a PublicKey<E>,
+}
+
+impl<'a, E: MPCEngine> ContributionInfo<'a, E> {
+    /// The delta (in G1) of the parameters after this contribution.
+    pub fn delta_after(&self) -> E::G1Affine {
+        self.pubkey.delta_after
+    }
+
+    /// The random element the contributor chose.
+    pub fn s(&self) -> E::G1Affine {
+        self.pubkey.s
+    }
+
+    /// `s` taken to the contributor's delta.
+    pub fn s_delta(&self) -> E::G1Affine {
+        self.pubkey.s_delta
+    }
+
+    /// The point the transcript hashes to in G2, taken to the
+    /// contributor's delta, which proves knowledge of delta.
+    pub fn r_delta(&self) -> E::G2Affine {
+        self.pubkey.r_delta
+    }
+
+    /// The hash of the transcript up to this contribution, which is
+    /// hashed to G2 to get the `r` of `r_delta`.
+    pub fn transcript(&self) -> &'a [u8; 64] {
+        &self.pubkey.transcript
+    }
+
+    /// The hash of the contribution, as `contribute` returned it.
+    pub fn hash(&self) -> ContributionHash {
+        self.pubkey.hash()
+    }
+
+    /// The public key itself, e.g. to `write` it to a transcript file.
+    pub fn public_key(&self) -> &'a PublicKey<E> {
+        self.pubkey
+    }
+}
+
+/// A single contribution, without the parts of the parameters that
+/// contributions never change: the contributor's public key, the new
+/// delta, and the rescaled H and L queries. Sending this instead of
+/// the whole parameters saves the bandwidth of the A, B and IC
+/// queries. See `verify_package` and `MPCParameters::apply_package`.
+#[derive(Clone)]
+pub struct ContributionPackage<E: Engine = Bls12> {
+    pubkey: PublicKey<E>,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    h: Vec<E::G1Affine>,
+    l: Vec<E::G1Affine>,
+}
+
+impl<E: MPCEngine> ContributionPackage<E> {
+    /// Serialize this package.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.pubkey.write(&mut writer)?;
+        writer.write_all(self.delta_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.delta_g2.to_uncompressed().as_ref())?;
+
+        for query in [&self.h, &self.l] {
+            writer.write_u32::<BigEndian>(query.len() as u32)?;
+            for p in query {
+                writer.write_all(p.to_uncompressed().as_ref())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a package, performing all curve validity and group
+    /// order checks, and rejecting points at infinity.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<ContributionPackage<E>> {
+        fn read_nonzero<G: PrimeCurveAffine + UncompressedEncoding, R: Read>(
+            reader: &mut R,
+        ) -> io::Result<G> {
+            let p: G = read_point(reader)?;
+
+            if p.is_identity().into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+
+            Ok(p)
+        }
+
+        fn read_query<G: PrimeCurveAffine + UncompressedEncoding, R: Read>(
+            reader: &mut R,
+        ) -> io::Result<Vec<G>> {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            (0..len).map(|_| read_nonzero(reader)).collect()
+        }
+
+        let pubkey = PublicKey::read(&mut reader)?;
+        let delta_g1 = read_nonzero(&mut reader)?;
+        let delta_g2 = read_nonzero(&mut reader)?;
+        let h = read_query(&mut reader)?;
+        let l = read_query(&mut reader)?;
+
+        Ok(ContributionPackage {
+            pubkey,
+            delta_g1,
+            delta_g2,
+            h,
+            l,
+        })
+    }
 }
 
 /// Abstraction over a writer which hashes the data being written.
@@ -469,7 +889,7 @@ impl<W: Write> HashWriter<W> {
     /// Construct a new `HashWriter` given an existing `writer` by value.
     pub fn new(writer: W) -> Self {
         HashWriter {
-            writer: writer,
+            writer,
             hasher: Blake2b::new(64),
         }
     }
@@ -498,861 +918,5022 @@ impl<W: Write> Write for HashWriter<W> {
     }
 }
 
-/// Hashes to G2 using the first 32 bytes of `digest`. Panics if `digest` is less
-/// than 32 bytes.
-fn hash_to_g2(digest: &[u8]) -> bls12_381::G2Projective {
-    assert!(digest.len() >= 32);
-    let mut seed = [0u8; 32];
-    seed.copy_from_slice(&digest[..32]);
-    bls12_381::G2Projective::random(&mut ChaChaRng::from_seed(seed))
+/// Abstraction over a reader which hashes the data being read with
+/// BLAKE2b, so that a download can be hashed as it's parsed instead
+/// of being read twice.
+pub struct HashReader<R: Read> {
+    reader: R,
+    hasher: Blake2b,
 }
 
-/// Verify a contribution, given the old parameters and
-/// the new parameters. Returns the hash of the contribution.
-pub fn verify_contribution(before: &MPCParameters, after: &MPCParameters) -> Result<[u8; 64], ()> {
-    // Transformation involves a single new object
-    if after.contributions.len() != (before.contributions.len() + 1) {
-        return Err(());
+impl<R: Read> HashReader<R> {
+    /// Construct a new `HashReader` given an existing `reader` by value.
+    pub fn new(reader: R) -> Self {
+        HashReader {
+            reader,
+            hasher: Blake2b::new(64),
+        }
     }
 
-    // None of the previous transformations should change
-    if &before.contributions[..] != &after.contributions[0..before.contributions.len()] {
-        return Err(());
+    /// Destroy this reader and return the hash of what was read.
+    pub fn into_hash(self) -> [u8; 64] {
+        let mut tmp = [0u8; 64];
+        tmp.copy_from_slice(self.hasher.finalize().as_ref());
+        tmp
     }
+}
 
-    // H/L will change, but should have same length
-    if before.params.h.len() != after.params.h.len() {
-        return Err(());
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.reader.read(buf)?;
+        self.hasher.update(&buf[..bytes]);
+        Ok(bytes)
     }
-    if before.params.l.len() != after.params.l.len() {
-        return Err(());
+}
+
+/// The hash of a single contribution. Contributors use this to
+/// make sure their contribution is in the final parameters.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContributionHash([u8; 64]);
+
+/// The shortest hex prefix `from_hex_prefix` and
+/// `contains_contribution` accept, as with git's abbreviated hashes.
+pub const MIN_HEX_PREFIX: usize = 4;
+
+impl ContributionHash {
+    /// Get the raw bytes of this hash.
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
     }
 
-    // A/B_G1/B_G2 doesn't change at all
-    if before.params.a != after.params.a {
-        return Err(());
+    /// Whether the hex of this hash starts with `prefix`, in either
+    /// case. Prefixes shorter than `MIN_HEX_PREFIX` never match.
+    pub fn matches_hex_prefix(&self, prefix: &str) -> bool {
+        prefix.len() >= MIN_HEX_PREFIX
+            && prefix.chars().all(|c| c.is_ascii_hexdigit())
+            && hex(&self.0).starts_with(&prefix.to_ascii_lowercase())
     }
-    if before.params.b_g1 != after.params.b_g1 {
-        return Err(());
+
+    /// Find the one hash in `hashes` whose hex starts with `prefix`,
+    /// like git resolves an abbreviated commit hash.
+    pub fn from_hex_prefix<'a, I>(prefix: &str, hashes: I) -> Result<ContributionHash, HashError>
+    where
+        I: IntoIterator<Item = &'a ContributionHash>,
+    {
+        if prefix.len() < MIN_HEX_PREFIX || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(HashError::InvalidHex);
+        }
+
+        let mut matches = hashes.into_iter().filter(|h| h.matches_hex_prefix(prefix));
+        match (matches.next(), matches.next()) {
+            (Some(hash), None) => Ok(*hash),
+            (Some(_), Some(_)) => Err(HashError::Ambiguous),
+            (None, _) => Err(HashError::NotFound),
+        }
     }
-    if before.params.b_g2 != after.params.b_g2 {
-        return Err(());
+}
+
+/// Why a `ContributionHash` couldn't be parsed or resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashError {
+    /// It isn't hex of the right length.
+    InvalidHex,
+    /// No hash starts with the prefix.
+    NotFound,
+    /// More than one hash starts with the prefix.
+    Ambiguous,
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashError::InvalidHex => write!(f, "not a valid contribution hash"),
+            HashError::NotFound => write!(f, "no contribution hash has that prefix"),
+            HashError::Ambiguous => write!(f, "the prefix matches several contribution hashes"),
+        }
     }
+}
 
-    // alpha/beta/gamma don't change
-    if before.params.vk.alpha_g1 != after.params.vk.alpha_g1 {
-        return Err(());
+impl std::error::Error for HashError {}
+
+impl fmt::Display for ContributionHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex(&self.0))
     }
-    if before.params.vk.beta_g1 != after.params.vk.beta_g1 {
-        return Err(());
+}
+
+impl std::str::FromStr for ContributionHash {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<ContributionHash, HashError> {
+        if s.len() != 128 {
+            return Err(HashError::InvalidHex);
+        }
+
+        let mut hash = [0u8; 64];
+        for (i, byte) in hash.iter_mut().enumerate() {
+            let digits = s.get(2 * i..2 * i + 2).ok_or(HashError::InvalidHex)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| HashError::InvalidHex)?;
+        }
+
+        Ok(ContributionHash(hash))
     }
-    if before.params.vk.beta_g2 != after.params.vk.beta_g2 {
-        return Err(());
+}
+
+/// Something `contains_contribution` can look for: a full hash, as
+/// raw bytes or a `ContributionHash`, or a hex string, which may be
+/// an abbreviated prefix.
+pub trait ContributionQuery {
+    /// Whether `hash` is the contribution being looked for.
+    fn matches(&self, hash: &ContributionHash) -> bool;
+}
+
+impl ContributionQuery for [u8; 64] {
+    fn matches(&self, hash: &ContributionHash) -> bool {
+        hash.0[..] == self[..]
     }
-    if before.params.vk.gamma_g2 != after.params.vk.gamma_g2 {
-        return Err(());
+}
+
+impl ContributionQuery for ContributionHash {
+    fn matches(&self, hash: &ContributionHash) -> bool {
+        self == hash
     }
+}
 
-    // IC shouldn't change, as gamma doesn't change
-    if before.params.vk.ic != after.params.vk.ic {
-        return Err(());
+impl ContributionQuery for str {
+    fn matches(&self, hash: &ContributionHash) -> bool {
+        hash.matches_hex_prefix(self)
     }
+}
 
-    // cs_hash should be the same
-    if &before.cs_hash[..] != &after.cs_hash[..] {
-        return Err(());
+impl From<[u8; 64]> for ContributionHash {
+    fn from(hash: [u8; 64]) -> ContributionHash {
+        ContributionHash(hash)
     }
+}
 
-    let sink = io::sink();
-    let mut sink = HashWriter::new(sink);
-    sink.write_all(&before.cs_hash[..]).unwrap();
+impl From<ContributionHash> for [u8; 64] {
+    fn from(hash: ContributionHash) -> [u8; 64] {
+        hash.0
+    }
+}
 
-    for pubkey in &before.contributions {
-        pubkey.write(&mut sink).unwrap();
+/// Compares the hashes without branching on their bytes, for callers
+/// checking a hash they were sent against one they computed.
+impl ConstantTimeEq for ContributionHash {
+    fn ct_eq(&self, other: &ContributionHash) -> subtle::Choice {
+        self.0[..].ct_eq(&other.0[..])
     }
+}
 
-    let pubkey = after.contributions.last().unwrap();
-    sink.write_all(pubkey.s.to_uncompressed().as_ref()).unwrap();
-    sink.write_all(pubkey.s_delta.to_uncompressed().as_ref())
-        .unwrap();
+impl fmt::Debug for ContributionHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ContributionHash(")?;
+        for b in &self.0[..] {
+            write!(f, "{:02x}", b)?;
+        }
+        write!(f, ")")
+    }
+}
 
-    let h = sink.into_hash();
+/// The reason verification of parameters or a transcript failed.
+#[derive(Debug)]
+pub enum VerificationError {
+    /// The transcript could not be read.
+    Io(io::Error),
+    /// The transcript hash stored in contribution `index` doesn't
+    /// match the transcript that precedes it.
+    InvalidTranscript { index: usize },
+    /// The signature of knowledge of delta in contribution `index`
+    /// is invalid.
+    InvalidSignature { index: usize },
+    /// The delta of contribution `index` isn't consistent with the
+    /// delta that came before it.
+    InvalidDeltaRatio { index: usize },
+    /// The circuit could not be synthesized into initial parameters.
+    Synthesis(SynthesisError),
+    /// The H query has a different length than the circuit's.
+    HQueryLengthMismatch,
+    /// The L query has a different length than the circuit's.
+    LQueryLengthMismatch,
+    /// The A query differs from the circuit's.
+    AQueryMismatch,
+    /// The B query in G1 differs from the circuit's.
+    BG1QueryMismatch,
+    /// The B query in G2 differs from the circuit's.
+    BG2QueryMismatch,
+    /// Alpha in G1 differs from the circuit's.
+    AlphaG1Mismatch,
+    /// Beta in G1 differs from the circuit's.
+    BetaG1Mismatch,
+    /// Beta in G2 differs from the circuit's.
+    BetaG2Mismatch,
+    /// Gamma in G2 differs from the circuit's.
+    GammaG2Mismatch,
+    /// The IC query differs from the circuit's.
+    IcMismatch,
+    /// The `cs_hash` differs from the circuit's.
+    CsHashMismatch,
+    /// The parameters after a contribution name a different
+    /// transcript scheme than those before it.
+    TranscriptSchemeMismatch,
+    /// Delta in G1 isn't the delta of the last contribution.
+    DeltaG1Mismatch,
+    /// Delta in G2 isn't consistent with delta in G1.
+    DeltaG2Mismatch,
+    /// The H query wasn't updated consistently with delta.
+    HQueryRatioMismatch,
+    /// The L query wasn't updated consistently with delta.
+    LQueryRatioMismatch,
+    /// The aggregated pairing check of `verify_aggregate` failed.
+    /// It can't tell which equation failed; use `verify` for that.
+    AggregateCheckFailed,
+    /// There are contributions, but the net delta is still the
+    /// generator, i.e. the contributions canceled each other out.
+    NetDeltaIsIdentity,
+    /// The structural digest differs from the expected one.
+    StructuralDigestMismatch,
+    /// The parameters carry the fingerprint of a different circuit
+    /// than the one they were verified with.
+    CircuitFingerprintMismatch,
+    /// Verification was cancelled before it finished.
+    Cancelled,
+    /// Element `index` of `query` is the point at infinity.
+    PointAtInfinity { query: &'static str, index: usize },
+    /// Element `index` of `query` isn't on the curve, or isn't in
+    /// the prime order subgroup.
+    InvalidPoint { query: &'static str, index: usize },
+    /// The H query has `len` elements, which isn't one less than
+    /// the size of an evaluation domain: a power of two of at least
+    /// `min_domain`, the smallest that holds the input constraints.
+    HQueryArity { len: usize, min_domain: usize },
+    /// The parameters don't contain exactly the expected
+    /// contributions, in order.
+    ContributionSetMismatch {
+        expected: Vec<ContributionHash>,
+        actual: Vec<ContributionHash>,
+    },
+    /// The new parameters don't add exactly one contribution to the
+    /// old ones.
+    NotOneContribution,
+    /// The new parameters alter contributions that were already made.
+    PreviousContributionsChanged,
+    /// Delta of the initial parameters isn't the generator, which is
+    /// where the chain of contributions is checked from.
+    BaseDeltaNotGenerator,
+    /// The IC query is empty, so it lacks the "one" input.
+    IcEmpty,
+    /// Contribution `index` doesn't exist, or wasn't derived from
+    /// the given beacon.
+    NotBeaconDerived { index: usize },
+    /// The parameters read back from their serialization aren't
+    /// the same, or don't serialize to the same bytes.
+    NotCanonical,
+    /// Attestation `index` isn't validly signed, or attests to a
+    /// contribution the parameters don't have.
+    #[cfg(feature = "attest")]
+    InvalidAttestation { index: usize },
+    /// Contribution `index` isn't attested by enough of the verifiers
+    /// in a pool (see `MPCParameters::check_verifier_quorum`).
+    #[cfg(feature = "attest")]
+    NotEnoughVerifiers { index: usize },
+}
 
-    // The transcript must be consistent
-    if &pubkey.transcript[..] != h.as_ref() {
-        return Err(());
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::Io(e) => write!(f, "I/O error: {}", e),
+            VerificationError::InvalidTranscript { index } => {
+                write!(f, "contribution {} has an inconsistent transcript", index)
+            }
+            VerificationError::InvalidSignature { index } => {
+                write!(
+                    f,
+                    "contribution {} has an invalid signature of knowledge",
+                    index
+                )
+            }
+            VerificationError::InvalidDeltaRatio { index } => {
+                write!(f, "contribution {} has an inconsistent delta", index)
+            }
+            VerificationError::Synthesis(e) => write!(f, "synthesis error: {}", e),
+            VerificationError::HQueryLengthMismatch => write!(f, "H query length mismatch"),
+            VerificationError::LQueryLengthMismatch => write!(f, "L query length mismatch"),
+            VerificationError::AQueryMismatch => write!(f, "A query mismatch"),
+            VerificationError::BG1QueryMismatch => write!(f, "B query (G1) mismatch"),
+            VerificationError::BG2QueryMismatch => write!(f, "B query (G2) mismatch"),
+            VerificationError::AlphaG1Mismatch => write!(f, "alpha (G1) mismatch"),
+            VerificationError::BetaG1Mismatch => write!(f, "beta (G1) mismatch"),
+            VerificationError::BetaG2Mismatch => write!(f, "beta (G2) mismatch"),
+            VerificationError::GammaG2Mismatch => write!(f, "gamma (G2) mismatch"),
+            VerificationError::IcMismatch => write!(f, "IC query mismatch"),
+            VerificationError::CsHashMismatch => write!(f, "cs_hash mismatch"),
+            VerificationError::TranscriptSchemeMismatch => write!(f, "transcript scheme mismatch"),
+            VerificationError::DeltaG1Mismatch => {
+                write!(f, "delta (G1) doesn't match the last contribution")
+            }
+            VerificationError::DeltaG2Mismatch => {
+                write!(f, "delta (G2) is inconsistent with delta (G1)")
+            }
+            VerificationError::HQueryRatioMismatch => {
+                write!(f, "H query is inconsistent with delta")
+            }
+            VerificationError::LQueryRatioMismatch => {
+                write!(f, "L query is inconsistent with delta")
+            }
+            VerificationError::AggregateCheckFailed => write!(f, "aggregated pairing check failed"),
+            VerificationError::NetDeltaIsIdentity => {
+                write!(f, "contributions canceled out to the initial delta")
+            }
+            VerificationError::StructuralDigestMismatch => write!(f, "structural digest mismatch"),
+            VerificationError::CircuitFingerprintMismatch => {
+                write!(f, "circuit fingerprint mismatch")
+            }
+            VerificationError::Cancelled => write!(f, "verification was cancelled"),
+            VerificationError::PointAtInfinity { query, index } => {
+                write!(f, "element {} of {} is the point at infinity", index, query)
+            }
+            VerificationError::InvalidPoint { query, index } => {
+                write!(f, "element {} of {} is not a valid point", index, query)
+            }
+            VerificationError::HQueryArity { len, min_domain } => write!(
+                f,
+                "H query has {} elements, which is not one less than a power of two of at least {}",
+                len, min_domain
+            ),
+            VerificationError::ContributionSetMismatch { expected, actual } => write!(
+                f,
+                "expected {} contributions, found {} that don't match",
+                expected.len(),
+                actual.len()
+            ),
+            VerificationError::NotOneContribution => {
+                write!(f, "expected exactly one new contribution")
+            }
+            VerificationError::PreviousContributionsChanged => {
+                write!(f, "previous contributions were changed")
+            }
+            VerificationError::BaseDeltaNotGenerator => {
+                write!(f, "initial delta isn't the generator")
+            }
+            VerificationError::IcEmpty => write!(f, "IC query is empty"),
+            VerificationError::NotBeaconDerived { index } => {
+                write!(f, "contribution {} isn't derived from the beacon", index)
+            }
+            VerificationError::NotCanonical => {
+                write!(f, "parameters don't round-trip through serialization")
+            }
+            #[cfg(feature = "attest")]
+            VerificationError::InvalidAttestation { index } => {
+                write!(f, "attestation {} is invalid", index)
+            }
+            #[cfg(feature = "attest")]
+            VerificationError::NotEnoughVerifiers { index } => {
+                write!(
+                    f,
+                    "contribution {} isn't attested by enough verifiers",
+                    index
+                )
+            }
+        }
     }
+}
 
-    let r = hash_to_g2(h.as_ref()).to_affine();
+impl std::error::Error for VerificationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerificationError::Io(e) => Some(e),
+            VerificationError::Synthesis(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
-    // Check the signature of knowledge
-    if !same_ratio((r, pubkey.r_delta), (pubkey.s, pubkey.s_delta)) {
-        return Err(());
+impl From<io::Error> for VerificationError {
+    fn from(e: io::Error) -> VerificationError {
+        VerificationError::Io(e)
     }
+}
 
-    // Check the change from the old delta is consistent
-    if !same_ratio(
-        (before.params.vk.delta_g1, pubkey.delta_after),
-        (r, pubkey.r_delta),
-    ) {
-        return Err(());
+impl From<SynthesisError> for VerificationError {
+    fn from(e: SynthesisError) -> VerificationError {
+        VerificationError::Synthesis(e)
     }
+}
 
-    // Current parameters should have consistent delta in G1
-    if pubkey.delta_after != after.params.vk.delta_g1 {
-        return Err(());
+/// An error from an operation on parameters other than verification.
+#[derive(Debug)]
+pub enum Phase2Error {
+    /// An I/O error.
+    Io(io::Error),
+    /// The circuit could not be synthesized.
+    Synthesis(SynthesisError),
+    /// Fewer than `threshold` secret shares were supplied.
+    NotEnoughShares { threshold: usize, got: usize },
+    /// The secret shares are malformed, e.g. two of them have the
+    /// same index.
+    InvalidShares,
+    /// A proof was checked with `got` public inputs, but the circuit
+    /// has `expected` of them.
+    PublicInputCount { expected: usize, got: usize },
+    /// Nobody has contributed to the parameters yet.
+    NoContributions,
+    /// These auxiliary variables of the circuit appear in no
+    /// constraint.
+    UnconstrainedVariables(Vec<String>),
+    /// The operation was cancelled before it finished.
+    Cancelled,
+    /// The parameters failed a check.
+    Verification(VerificationError),
+}
+
+impl fmt::Display for Phase2Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase2Error::Io(e) => write!(f, "I/O error: {}", e),
+            Phase2Error::Synthesis(e) => write!(f, "synthesis error: {}", e),
+            Phase2Error::NotEnoughShares { threshold, got } => {
+                write!(f, "need {} shares, got {}", threshold, got)
+            }
+            Phase2Error::InvalidShares => write!(f, "invalid secret shares"),
+            Phase2Error::PublicInputCount { expected, got } => {
+                write!(f, "expected {} public inputs, got {}", expected, got)
+            }
+            Phase2Error::NoContributions => write!(f, "parameters have no contributions"),
+            Phase2Error::UnconstrainedVariables(names) => {
+                write!(f, "unconstrained variables: {}", names.join(", "))
+            }
+            Phase2Error::Cancelled => write!(f, "cancelled"),
+            Phase2Error::Verification(e) => write!(f, "verification failed: {}", e),
+        }
     }
+}
 
-    // Current parameters should have consistent delta in G2
-    if !same_ratio(
-        (bls12_381::G1Affine::generator(), pubkey.delta_after),
-        (bls12_381::G2Affine::generator(), after.params.vk.delta_g2),
-    ) {
-        return Err(());
+impl std::error::Error for Phase2Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Phase2Error::Io(e) => Some(e),
+            Phase2Error::Synthesis(e) => Some(e),
+            Phase2Error::Verification(e) => Some(e),
+            _ => None,
+        }
     }
+}
 
-    // H and L queries should be updated with delta^-1
-    if !same_ratio(
-        merge_pairs(&before.params.h, &after.params.h),
-        (after.params.vk.delta_g2, before.params.vk.delta_g2), // reversed for inverse
-    ) {
-        return Err(());
+impl From<io::Error> for Phase2Error {
+    fn from(e: io::Error) -> Phase2Error {
+        Phase2Error::Io(e)
     }
+}
 
-    if !same_ratio(
-        merge_pairs(&before.params.l, &after.params.l),
-        (after.params.vk.delta_g2, before.params.vk.delta_g2), // reversed for inverse
-    ) {
-        return Err(());
+impl From<SynthesisError> for Phase2Error {
+    fn from(e: SynthesisError) -> Phase2Error {
+        Phase2Error::Synthesis(e)
     }
+}
 
-    let sink = io::sink();
-    let mut sink = HashWriter::new(sink);
-    pubkey.write(&mut sink).unwrap();
-    let h = sink.into_hash();
-    let mut response = [0u8; 64];
-    response.copy_from_slice(h.as_ref());
+impl From<VerificationError> for Phase2Error {
+    fn from(e: VerificationError) -> Phase2Error {
+        Phase2Error::Verification(e)
+    }
+}
 
-    Ok(response)
+/// Why `MPCParameters::self_test` failed.
+#[derive(Debug)]
+pub enum SelfTestError {
+    /// The IC query is empty, so the parameters have no verifying key
+    /// to test (see `MPCParameters::check_ic_arity`).
+    IcEmpty,
+    /// The test was given `got` public inputs, but the circuit has
+    /// `expected` of them.
+    PublicInputCount { expected: usize, got: usize },
+    /// The proof could not be created, e.g. because the instance
+    /// doesn't match the circuit.
+    Proving(SynthesisError),
+    /// The proof was created, but doesn't verify.
+    ProofRejected,
 }
 
-/// Checks if pairs have the same ratio.
-fn same_ratio<G1: pairing::PairingCurveAffine>(g1: (G1, G1), g2: (G1::Pair, G1::Pair)) -> bool {
-    g1.0.pairing_with(&g2.1) == g1.1.pairing_with(&g2.0)
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelfTestError::IcEmpty => write!(f, "the IC query is empty"),
+            SelfTestError::PublicInputCount { expected, got } => {
+                write!(f, "expected {} public inputs, got {}", expected, got)
+            }
+            SelfTestError::Proving(e) => write!(f, "couldn't create a proof: {}", e),
+            SelfTestError::ProofRejected => write!(f, "the proof doesn't verify"),
+        }
+    }
 }
 
-/// Computes a random linear combination over v1/v2.
-///
-/// Checking that many pairs of elements are exponentiated by
-/// the same `x` can be achieved (with high probability) with
-/// the following technique:
-///
-/// Given v1 = [a, b, c] and v2 = [as, bs, cs], compute
-/// (a*r1 + b*r2 + c*r3, (as)*r1 + (bs)*r2 + (cs)*r3) for some
-/// random r1, r2, r3. Given (g, g^s)...
-///
-/// e(g, (as)*r1 + (bs)*r2 + (cs)*r3) = e(g^s, a*r1 + b*r2 + c*r3)
+impl std::error::Error for SelfTestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SelfTestError::Proving(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Incrementally computes the `cs_hash` of parameters. Sections
+/// must be fed in the order that `Parameters::write` serializes
+/// them: the verifying key, then the H, L, A and B (G1) queries and
+/// finally the B (G2) query. The result is the same as hashing the
+/// serialized parameters, but no serialization is ever buffered.
+pub struct CsHasher {
+    writer: HashWriter<io::Sink>,
+}
+
+impl Default for CsHasher {
+    fn default() -> Self {
+        CsHasher::new()
+    }
+}
+
+impl CsHasher {
+    /// Create a new hasher.
+    pub fn new() -> Self {
+        CsHasher {
+            writer: HashWriter::new(io::sink()),
+        }
+    }
+
+    /// Feed the verifying key.
+    pub fn update_vk<E: Engine>(&mut self, vk: &VerifyingKey<E>) {
+        vk.write(&mut self.writer).unwrap();
+    }
+
+    /// Feed a query, of G1 or G2 elements.
+    pub fn update_query<G: UncompressedEncoding>(&mut self, query: &[G]) {
+        self.writer
+            .write_u32::<BigEndian>(query.len() as u32)
+            .unwrap();
+        for g in query {
+            self.writer.write_all(g.to_uncompressed().as_ref()).unwrap();
+        }
+    }
+
+    /// Destroy this hasher and return the `cs_hash`.
+    pub fn finalize(self) -> [u8; 64] {
+        self.writer.into_hash()
+    }
+}
+
+/// Verify a contribution, given the old parameters and
+/// the new parameters. Returns the hash of the contribution, or
+/// which check it failed.
+pub fn verify_contribution<E: MPCEngine>(
+    before: &MPCParameters<E>,
+    after: &MPCParameters<E>,
+) -> Result<ContributionHash, VerificationError> {
+    check_contribution(before, after)
+}
+
+/// Like `verify_contribution`, for parameters that are still
+/// serialized (as written by `write`), e.g. as received from the
+/// network. See `MPCParameters::read` for what `checked` does.
+pub fn verify_contribution_bytes<E: MPCEngine>(
+    before: &[u8],
+    after: &[u8],
+    checked: bool,
+) -> Result<ContributionHash, VerificationError> {
+    let before = MPCParameters::<E>::read(before, checked)?;
+    let after = MPCParameters::read(after, checked)?;
+
+    check_contribution(&before, &after)
+}
+
+/// Contributes to serialized parameters (as written by `write`) with
+/// randomness derived from `seed`, as `contribute_with_seed` does,
+/// returning the new serialized parameters and the hash of the
+/// contribution. Nothing touches the filesystem, so this works in a
+/// browser, given a seed from its secure random source.
+pub fn contribute_bytes<E: MPCEngine>(
+    params: &[u8],
+    seed: [u8; 32],
+) -> io::Result<(Vec<u8>, [u8; 64])> {
+    let mut params = MPCParameters::<E>::read(params, true)?;
+    let hash = params.contribute_with_seed(seed);
+
+    let mut bytes = vec![];
+    params.write(&mut bytes)?;
+
+    Ok((bytes, hash))
+}
+
+/// Verify a whole chain of contributions on top of `initial`, given
+/// the serialized parameters (as written by `write`) after each of
+/// them, in order. Each set of parameters is read once and dropped as
+/// soon as the next one has been verified against it, so at most two
+/// are in memory. Returns the hashes of the contributions.
+pub fn verify_transcript<E, R, I>(
+    initial: &MPCParameters<E>,
+    contributions: I,
+) -> Result<Vec<[u8; 64]>, VerificationError>
+where
+    E: MPCEngine,
+    R: Read,
+    I: IntoIterator<Item = R>,
+{
+    let mut hashes = vec![];
+    let mut previous: Option<MPCParameters<E>> = None;
+
+    for reader in contributions {
+        let reader = BufReader::with_capacity(1024 * 1024, reader);
+        // The scheme of each file is the one its header names, which
+        // `check_contribution` compares with the one before it. Only
+        // a custom scheme can't be restored from the header.
+        let after = match initial.transcript_scheme.id() {
+            SchemeId::Custom(_) => MPCParameters::read_with_shared_scheme(
+                reader,
+                true,
+                initial.transcript_scheme.clone(),
+            )?,
+            _ => MPCParameters::read(reader, true)?,
+        };
+        let before = previous.as_ref().unwrap_or(initial);
+
+        hashes.push(check_contribution(before, &after)?.into());
+        previous = Some(after);
+    }
+
+    Ok(hashes)
+}
+
+/// Verify a contribution that was sent as a `ContributionPackage`,
+/// given the parameters it was made to. This makes the same checks
+/// as `verify_contribution` does of what a contribution changes.
+/// Returns the hash of the contribution.
+pub fn verify_package<E: MPCEngine>(
+    before: &MPCParameters<E>,
+    pkg: &ContributionPackage<E>,
+) -> Result<ContributionHash, VerificationError> {
+    check_update(
+        before,
+        &pkg.pubkey,
+        pkg.delta_g1,
+        pkg.delta_g2,
+        &pkg.h,
+        &pkg.l,
+    )
+}
+
+/// Verifies several candidates for the next contribution against the
+/// same `initial` parameters, in parallel. Each result is what
+/// `verify_contribution(initial, candidate)` would conclude, in the
+/// order of `candidates`.
+pub fn verify_candidates<E: MPCEngine>(
+    initial: &MPCParameters<E>,
+    candidates: &[MPCParameters<E>],
+) -> Vec<Result<ContributionHash, VerificationError>> {
+    if candidates.is_empty() {
+        return vec![];
+    }
+
+    let mut results: Vec<_> = candidates.iter().map(|_| None).collect();
+    let chunk = (candidates.len() / multicore::num_threads()).max(1);
+
+    multicore::scope(|scope| {
+        for (candidates, results) in candidates.chunks(chunk).zip(results.chunks_mut(chunk)) {
+            scope.spawn(move || {
+                for (candidate, result) in candidates.iter().zip(results.iter_mut()) {
+                    *result = Some(check_contribution(initial, candidate));
+                }
+            });
+        }
+    });
+
+    results.into_iter().map(|r| r.unwrap()).collect()
+}
+
+/// Contributes to the parameters of several independent ceremonies
+/// from one source of randomness, returning the hash of each
+/// contribution in order. Every contribution samples its own delta
+/// from `rng`, exactly as if `contribute` had been called on each of
+/// the parameters in turn, so no keypair is shared across ceremonies.
+pub fn contribute_batch<E: MPCEngine, R: Rng>(
+    params: &mut [MPCParameters<E>],
+    rng: &mut R,
+) -> Vec<[u8; 64]> {
+    params
+        .iter_mut()
+        .map(|params| params.contribute(rng))
+        .collect()
+}
+
+fn check_contribution<E: MPCEngine>(
+    before: &MPCParameters<E>,
+    after: &MPCParameters<E>,
+) -> Result<ContributionHash, VerificationError> {
+    // Transformation involves a single new object
+    if after.contributions.len() != (before.contributions.len() + 1) {
+        return Err(VerificationError::NotOneContribution);
+    }
+
+    // None of the previous transformations should change
+    if before.contributions[..] != after.contributions[0..before.contributions.len()] {
+        return Err(VerificationError::PreviousContributionsChanged);
+    }
+
+    // A/B_G1/B_G2 doesn't change at all
+    if before.params.a != after.params.a {
+        return Err(VerificationError::AQueryMismatch);
+    }
+    if before.params.b_g1 != after.params.b_g1 {
+        return Err(VerificationError::BG1QueryMismatch);
+    }
+    if before.params.b_g2 != after.params.b_g2 {
+        return Err(VerificationError::BG2QueryMismatch);
+    }
+
+    // alpha/beta/gamma don't change
+    if before.params.vk.alpha_g1 != after.params.vk.alpha_g1 {
+        return Err(VerificationError::AlphaG1Mismatch);
+    }
+    if before.params.vk.beta_g1 != after.params.vk.beta_g1 {
+        return Err(VerificationError::BetaG1Mismatch);
+    }
+    if before.params.vk.beta_g2 != after.params.vk.beta_g2 {
+        return Err(VerificationError::BetaG2Mismatch);
+    }
+    if before.params.vk.gamma_g2 != after.params.vk.gamma_g2 {
+        return Err(VerificationError::GammaG2Mismatch);
+    }
+
+    // IC shouldn't change, as gamma doesn't change
+    if before.params.vk.ic != after.params.vk.ic {
+        return Err(VerificationError::IcMismatch);
+    }
+
+    // cs_hash should be the same
+    if before.cs_hash[..] != after.cs_hash[..] {
+        return Err(VerificationError::CsHashMismatch);
+    }
+
+    // The scheme is the ceremony's, which a contribution can't change
+    if before.transcript_scheme.id() != after.transcript_scheme.id() {
+        return Err(VerificationError::TranscriptSchemeMismatch);
+    }
+
+    check_update(
+        before,
+        after.contributions.last().unwrap(),
+        after.params.vk.delta_g1,
+        after.params.vk.delta_g2,
+        &after.params.h,
+        &after.params.l,
+    )
+}
+
+/// The checks of a single contribution that involve what it changed:
+/// its public key, the new delta, and the rescaled H and L queries.
+fn check_update<E: MPCEngine>(
+    before: &MPCParameters<E>,
+    pubkey: &PublicKey<E>,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    h: &[E::G1Affine],
+    l: &[E::G1Affine],
+) -> Result<ContributionHash, VerificationError> {
+    // H/L will change, but should have same length
+    if before.params.h.len() != h.len() {
+        return Err(VerificationError::HQueryLengthMismatch);
+    }
+    if before.params.l.len() != l.len() {
+        return Err(VerificationError::LQueryLengthMismatch);
+    }
+
+    let base = UpdateBase {
+        scheme: &*before.transcript_scheme,
+        cs_hash: &before.cs_hash,
+        contributions: &before.contributions,
+        vk: &before.params.vk,
+    };
+    check_merged_update(
+        &base,
+        pubkey,
+        delta_g1,
+        delta_g2,
+        || merge_pairs(&before.params.h, h),
+        || merge_pairs(&before.params.l, l),
+    )
+}
+
+/// What `check_merged_update` needs of the parameters before a
+/// contribution, wherever they're read from.
+pub(crate) struct UpdateBase<'a, E: MPCEngine> {
+    pub(crate) scheme: &'a dyn TranscriptScheme<E>,
+    pub(crate) cs_hash: &'a [u8; 64],
+    pub(crate) contributions: &'a [PublicKey<E>],
+    pub(crate) vk: &'a VerifyingKey<E>,
+}
+
+/// The checks of `check_update` after the lengths of H and L, given
+/// their pairs as `merge_pairs` makes them from the queries before
+/// and after. Merging is the expensive part, so it's left to last.
+pub(crate) fn check_merged_update<E: MPCEngine>(
+    before: &UpdateBase<E>,
+    pubkey: &PublicKey<E>,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    h: impl FnOnce() -> (E::G1Affine, E::G1Affine),
+    l: impl FnOnce() -> (E::G1Affine, E::G1Affine),
+) -> Result<ContributionHash, VerificationError> {
+    check_pubkey(
+        before.scheme,
+        before.cs_hash,
+        before.contributions,
+        before.vk.delta_g1,
+        pubkey,
+    )?;
+
+    // Current parameters should have consistent delta in G1
+    if pubkey.delta_after != delta_g1 {
+        return Err(VerificationError::DeltaG1Mismatch);
+    }
+
+    let g2 = E::G2Prepared::from(E::G2Affine::generator());
+    let before_delta_g2 = E::G2Prepared::from(before.vk.delta_g2);
+    let after_delta_g2 = E::G2Prepared::from(delta_g2);
+
+    // Current parameters should have consistent delta in G2
+    if !same_ratio_prepared::<E>(
+        (E::G1Affine::generator(), pubkey.delta_after),
+        (&g2, &after_delta_g2),
+    ) {
+        return Err(VerificationError::DeltaG2Mismatch);
+    }
+
+    // H and L queries should be updated with delta^-1
+    if !same_ratio_prepared::<E>(
+        h(),
+        (&after_delta_g2, &before_delta_g2), // reversed for inverse
+    ) {
+        return Err(VerificationError::HQueryRatioMismatch);
+    }
+
+    if !same_ratio_prepared::<E>(
+        l(),
+        (&after_delta_g2, &before_delta_g2), // reversed for inverse
+    ) {
+        return Err(VerificationError::LQueryRatioMismatch);
+    }
+
+    Ok(pubkey.hash())
+}
+
+/// The checks of a contribution's public key alone: its transcript,
+/// its signature of knowledge of delta, and that it takes the delta
+/// before it, `delta_before`, to its `delta_after`.
+fn check_pubkey<E: MPCEngine>(
+    scheme: &dyn TranscriptScheme<E>,
+    cs_hash: &[u8; 64],
+    previous: &[PublicKey<E>],
+    delta_before: E::G1Affine,
+    pubkey: &PublicKey<E>,
+) -> Result<(), VerificationError> {
+    let mut prefix = HashWriter::new(io::sink());
+    prefix.write_all(&cs_hash[..]).unwrap();
+    for previous in previous {
+        previous.write(&mut prefix).unwrap();
+    }
+
+    check_pubkey_with(
+        scheme,
+        &prefix,
+        previous.len(),
+        delta_before,
+        pubkey,
+        same_ratio,
+    )
+}
+
+/// `check_pubkey`, for the contribution at `index`, given `prefix`,
+/// the transcript up to it: the `cs_hash` and the public keys before
+/// it. Whether two pairs have the same ratio is left to `ratio`, so
+/// the pairings can be deferred by returning `true`. Every check of
+/// a contribution's public key is made here.
+fn check_pubkey_with<E, F>(
+    scheme: &dyn TranscriptScheme<E>,
+    prefix: &HashWriter<io::Sink>,
+    index: usize,
+    delta_before: E::G1Affine,
+    pubkey: &PublicKey<E>,
+    mut ratio: F,
+) -> Result<(), VerificationError>
+where
+    E: MPCEngine,
+    F: FnMut((E::G1Affine, E::G1Affine), (E::G2Affine, E::G2Affine)) -> bool,
+{
+    let mut sink = prefix.clone();
+    sink.write_all(pubkey.s.to_uncompressed().as_ref()).unwrap();
+    sink.write_all(pubkey.s_delta.to_uncompressed().as_ref())
+        .unwrap();
+
+    let transcript = sink.into_hash();
+
+    // The transcript must be consistent
+    if pubkey.transcript[..] != transcript[..] {
+        return Err(VerificationError::InvalidTranscript { index });
+    }
+
+    let r = scheme.hash_to_g2(&transcript).to_affine();
+
+    // Check the signature of knowledge
+    if !ratio((pubkey.s, pubkey.s_delta), (r, pubkey.r_delta)) {
+        return Err(VerificationError::InvalidSignature { index });
+    }
+
+    // Check the change from the old delta is consistent
+    if !ratio((delta_before, pubkey.delta_after), (r, pubkey.r_delta)) {
+        return Err(VerificationError::InvalidDeltaRatio { index });
+    }
+
+    Ok(())
+}
+
+/// The result of `MPCParameters::verify_structure`: the initial
+/// parameters of a circuit, which are expensive to compute, and
+/// everything needed to verify chains of contributions on top of
+/// them with `MPCParameters::verify_chain`.
+#[derive(Clone)]
+pub struct StructureToken<E: Engine = Bls12> {
+    params: Parameters<E>,
+    cs_hash: [u8; 64],
+}
+
+impl<E: Engine> StructureToken<E> {
+    /// The `cs_hash` of the circuit.
+    pub fn cs_hash(&self) -> &[u8; 64] {
+        &self.cs_hash
+    }
+
+    /// The delta of the initial parameters, in G1 and G2, which
+    /// the first contribution builds on.
+    pub fn initial_delta(&self) -> (E::G1Affine, E::G2Affine) {
+        (self.params.vk.delta_g1, self.params.vk.delta_g2)
+    }
+}
+
+/// Walks a chain of contributions, checking that each public key
+/// is consistent with the transcript and the delta before it.
+struct ChainVerifier<'a, E: Engine> {
+    scheme: &'a dyn TranscriptScheme<E>,
+    sink: HashWriter<io::Sink>,
+    current_delta: E::G1Affine,
+    hashes: Vec<ContributionHash>,
+}
+
+impl<'a, E: MPCEngine> ChainVerifier<'a, E> {
+    fn new(cs_hash: &[u8; 64], scheme: &'a dyn TranscriptScheme<E>) -> Self {
+        let mut sink = HashWriter::new(io::sink());
+        sink.write_all(&cs_hash[..]).unwrap();
+
+        ChainVerifier {
+            scheme,
+            sink,
+            current_delta: E::G1Affine::generator(),
+            hashes: vec![],
+        }
+    }
+
+    fn push(&mut self, pubkey: &PublicKey<E>) -> Result<ContributionHash, VerificationError> {
+        check_pubkey_with(
+            self.scheme,
+            &self.sink,
+            self.hashes.len(),
+            self.current_delta,
+            pubkey,
+            same_ratio,
+        )?;
+
+        pubkey.write(&mut self.sink).unwrap();
+        self.current_delta = pubkey.delta_after;
+
+        let hash = pubkey.hash();
+        self.hashes.push(hash);
+
+        Ok(hash)
+    }
+}
+
+/// Checks a chain of contributions like `ChainVerifier` does, doing
+/// the checks of each contribution on `worker`'s threads. Fails on
+/// the same check as `ChainVerifier` would.
+fn check_pubkeys<E: MPCEngine>(
+    scheme: &dyn TranscriptScheme<E>,
+    cs_hash: &[u8; 64],
+    contributions: &[PublicKey<E>],
+    worker: &Worker,
+) -> Result<Vec<ContributionHash>, VerificationError> {
+    let mut sink = HashWriter::new(io::sink());
+    sink.write_all(&cs_hash[..]).unwrap();
+
+    // Only the transcript up to each contribution has to be hashed
+    // in order
+    let mut prefixes = vec![];
+    for pubkey in contributions {
+        prefixes.push(sink.clone());
+        pubkey.write(&mut sink).unwrap();
+    }
+
+    let mut errors: Vec<Option<VerificationError>> = contributions.iter().map(|_| None).collect();
+    worker.scope(contributions.len(), |scope, chunk| {
+        for (i, errors) in errors.chunks_mut(chunk).enumerate() {
+            let prefixes = &prefixes;
+
+            scope.spawn(move |_| {
+                for (j, error) in errors.iter_mut().enumerate() {
+                    let index = i * chunk + j;
+                    let delta_before = match index {
+                        0 => E::G1Affine::generator(),
+                        _ => contributions[index - 1].delta_after,
+                    };
+
+                    *error = check_pubkey_with(
+                        scheme,
+                        &prefixes[index],
+                        index,
+                        delta_before,
+                        &contributions[index],
+                        same_ratio,
+                    )
+                    .err();
+                }
+            });
+        }
+    });
+
+    if let Some(e) = errors.into_iter().flatten().next() {
+        return Err(e);
+    }
+
+    Ok(contributions.iter().map(|pubkey| pubkey.hash()).collect())
+}
+
+/// Verify a transcript of contributions that was archived across
+/// several chunk files (see `MPCParameters::write_transcript_chunk`),
+/// given the `cs_hash` of the parameters. The chunks are read in
+/// order as one stream and checked as one continuous chain, so where
+/// the chunk boundaries fall doesn't affect the result: a chunk may
+/// even end inside a public key, as when a transcript is split into
+/// files by size. Returns the hashes of all of the contributions.
 ///
-/// ... with high probability.
-fn merge_pairs<G: pairing::PairingCurveAffine>(v1: &[G], v2: &[G]) -> (G, G)
+/// Transcripts don't record their scheme, so this is for ceremonies
+/// with the `LegacyScheme`; see `verify_transcript_chunked_with_scheme`.
+pub fn verify_transcript_chunked<E, R, I>(
+    cs_hash: [u8; 64],
+    readers: I,
+) -> Result<Vec<ContributionHash>, VerificationError>
 where
-    G::Curve: WnafGroup,
+    E: MPCEngine,
+    R: Read,
+    I: IntoIterator<Item = R>,
 {
-    use rand::thread_rng;
-    use std::sync::Mutex;
+    verify_transcript_chunked_with_scheme::<E, _, _>(cs_hash, &LegacyScheme, readers)
+}
 
-    assert_eq!(v1.len(), v2.len());
+/// Like `verify_transcript_chunked`, for a ceremony that hashes its
+/// transcript to G2 with `scheme`.
+pub fn verify_transcript_chunked_with_scheme<E, R, I>(
+    cs_hash: [u8; 64],
+    scheme: &dyn TranscriptScheme<E>,
+    readers: I,
+) -> Result<Vec<ContributionHash>, VerificationError>
+where
+    E: MPCEngine,
+    R: Read,
+    I: IntoIterator<Item = R>,
+{
+    let mut chain = ChainVerifier::<E>::new(&cs_hash, scheme);
+
+    // Read as one stream, so a public key can span chunks
+    let mut reader = BufReader::new(Concat {
+        readers: readers.into_iter(),
+        current: None,
+    });
+    while !reader.fill_buf()?.is_empty() {
+        let pubkey = PublicKey::read(&mut reader)?;
+        chain.push(&pubkey)?;
+    }
+
+    Ok(chain.hashes)
+}
+
+/// The readers of `readers`, read one after another.
+struct Concat<I: Iterator> {
+    readers: I,
+    current: Option<I::Item>,
+}
 
-    let chunk = (v1.len() / num_cpus::get()) + 1;
+impl<I: Iterator<Item = R>, R: Read> Read for Concat<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(reader) = &mut self.current {
+                match reader.read(buf)? {
+                    0 if !buf.is_empty() => {}
+                    n => return Ok(n),
+                }
+            }
+
+            match self.readers.next() {
+                Some(reader) => self.current = Some(reader),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Like `verify_transcript_chunked`, for a transcript with
+/// compressed points, as written by
+/// `MPCParameters::write_transcript_compressed`. The same transcripts
+/// are accepted and the same hashes returned as for the uncompressed
+/// ones, for a bit over half the bytes. As that does, this assumes
+/// the `LegacyScheme`; see `verify_transcript_compressed_with_scheme`.
+pub fn verify_transcript_compressed<E: MPCEngine, R: Read>(
+    cs_hash: [u8; 64],
+    reader: R,
+) -> Result<Vec<ContributionHash>, VerificationError> {
+    verify_transcript_compressed_with_scheme::<E, _>(cs_hash, &LegacyScheme, reader)
+}
+
+/// Like `verify_transcript_compressed`, for a ceremony that hashes
+/// its transcript to G2 with `scheme`.
+pub fn verify_transcript_compressed_with_scheme<E: MPCEngine, R: Read>(
+    cs_hash: [u8; 64],
+    scheme: &dyn TranscriptScheme<E>,
+    reader: R,
+) -> Result<Vec<ContributionHash>, VerificationError> {
+    let mut chain = ChainVerifier::<E>::new(&cs_hash, scheme);
+    let mut reader = BufReader::new(reader);
+
+    while !reader.fill_buf()?.is_empty() {
+        let pubkey = PublicKey::read_compressed(&mut reader)?;
+        chain.push(&pubkey)?;
+    }
+
+    Ok(chain.hashes)
+}
+
+/// The outcome of `validate_file`. Every check is attempted even if
+/// an earlier one failed, except that nothing past a part of the file
+/// that can't be parsed is checked.
+#[derive(Debug)]
+pub struct FileValidationReport {
+    /// Every point in the file is a valid group element, and none of
+    /// the queries contain the point at infinity.
+    pub integrity_ok: bool,
+    /// The parts of the parameters that contributions never change
+    /// match the circuit.
+    pub structure_ok: bool,
+    /// The contributions form a valid chain ending at the delta of
+    /// the parameters, and H/L were updated consistently with it.
+    pub chain_ok: bool,
+    /// The hashes of all of the contributions that were read.
+    pub contribution_hashes: Vec<ContributionHash>,
+    /// The first problem that was found, if any.
+    pub first_error: Option<VerificationError>,
+}
+
+#[derive(Clone, Copy)]
+enum Check {
+    Integrity,
+    Structure,
+    Chain,
+}
+
+impl FileValidationReport {
+    /// Whether all of the checks passed.
+    pub fn is_valid(&self) -> bool {
+        self.integrity_ok && self.structure_ok && self.chain_ok
+    }
+
+    fn fail(&mut self, check: Check, e: VerificationError) {
+        match check {
+            Check::Integrity => self.integrity_ok = false,
+            Check::Structure => self.structure_ok = false,
+            Check::Chain => self.chain_ok = false,
+        }
+
+        if self.first_error.is_none() {
+            self.first_error = Some(e);
+        }
+    }
+}
+
+/// The number of points of a query `validate_file` holds at once.
+const VALIDATION_CHUNK: usize = 1 << 16;
+
+/// Fails with the index of the first point at infinity of `points`,
+/// the elements of `query`, if there's one.
+pub(crate) fn check_no_infinity_in<G: PrimeCurveAffine>(
+    query: &'static str,
+    points: &[G],
+) -> Result<(), VerificationError> {
+    match points.iter().position(|p| p.is_identity().into()) {
+        Some(index) => Err(VerificationError::PointAtInfinity { query, index }),
+        None => Ok(()),
+    }
+}
+
+/// Reads an uncompressed point, checking that it's on the curve and
+/// in the prime order subgroup.
+fn read_point<G: UncompressedEncoding, R: Read>(reader: &mut R) -> io::Result<G> {
+    let mut repr = G::Uncompressed::default();
+    reader.read_exact(repr.as_mut())?;
+
+    Option::from(G::from_uncompressed(&repr))
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "invalid point"))
+}
+
+/// Reads a query of `read_checked_parallel`, prefixed by its length.
+fn read_query_parallel<G, R>(
+    reader: &mut R,
+    query: &'static str,
+    worker: &Worker,
+) -> Result<Vec<G>, VerificationError>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    read_points_parallel(reader, query, len, worker)
+}
+
+/// Reads `len` uncompressed points, checking them on the threads of
+/// `worker`. Fails with the first of them that's invalid.
+fn read_points_parallel<G, R>(
+    reader: &mut R,
+    query: &'static str,
+    len: usize,
+    worker: &Worker,
+) -> Result<Vec<G>, VerificationError>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    let size = G::Uncompressed::default().as_ref().len();
+
+    // We don't trust the length to preallocate
+    let mut bytes = vec![];
+    reader.take((len * size) as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len * size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    let mut points = vec![G::identity(); len];
+    let mut failures = vec![];
+
+    worker.scope(len, |scope, chunk| {
+        // The first invalid point of each chunk, if any
+        failures.resize(len.div_ceil(chunk), None);
+
+        for (((i, points), bytes), failure) in points
+            .chunks_mut(chunk)
+            .enumerate()
+            .zip(bytes.chunks(chunk * size))
+            .zip(failures.iter_mut())
+        {
+            scope.spawn(move |_| {
+                for (j, (p, bytes)) in points.iter_mut().zip(bytes.chunks(size)).enumerate() {
+                    let mut repr = G::Uncompressed::default();
+                    repr.as_mut().copy_from_slice(bytes);
+
+                    match Option::from(G::from_uncompressed(&repr)) {
+                        Some(point) => *p = point,
+                        None => {
+                            *failure = Some(i * chunk + j);
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(index) = failures.into_iter().flatten().next() {
+        return Err(VerificationError::InvalidPoint { query, index });
+    }
+
+    Ok(points)
+}
+
+/// Reads a query that contributions don't change, returning whether
+/// it's the same as the `initial` one.
+fn read_query_compared<G, R>(
+    reader: &mut R,
+    query: &'static str,
+    initial: &[G],
+    report: &mut FileValidationReport,
+) -> io::Result<bool>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    let mut same = len == initial.len();
+
+    for index in 0..len {
+        let p: G = read_point(reader)?;
+
+        if p.is_identity().into() {
+            report.fail(
+                Check::Integrity,
+                VerificationError::PointAtInfinity { query, index },
+            );
+        }
+
+        same &= initial.get(index) == Some(&p);
+    }
+
+    Ok(same)
+}
+
+/// Reads a query that contributions scale by delta^-1, merging it
+/// chunk by chunk with the `initial` one (see `merge_pairs`). Returns
+/// `None` if it has a different length than the initial query.
+fn read_query_merged<G, R>(
+    reader: &mut R,
+    query: &'static str,
+    initial: &[G],
+    report: &mut FileValidationReport,
+) -> io::Result<Option<(G, G)>>
+where
+    G: pairing::PairingCurveAffine + UncompressedEncoding,
+    G::Curve: WnafGroup,
+    R: Read,
+{
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    let comparable = len == initial.len();
+
+    let mut s = G::Curve::identity();
+    let mut sx = G::Curve::identity();
+    let mut chunk = Vec::with_capacity(len.min(VALIDATION_CHUNK));
+    let mut start = 0;
+
+    while start < len {
+        chunk.clear();
+        for index in start..len.min(start + VALIDATION_CHUNK) {
+            let p: G = read_point(reader)?;
+
+            if p.is_identity().into() {
+                report.fail(
+                    Check::Integrity,
+                    VerificationError::PointAtInfinity { query, index },
+                );
+            }
+
+            chunk.push(p);
+        }
+
+        if comparable {
+            let (chunk_s, chunk_sx) = merge_pairs(&initial[start..start + chunk.len()], &chunk);
+            s.add_assign(&chunk_s);
+            sx.add_assign(&chunk_sx);
+        }
+
+        start += chunk.len();
+    }
+
+    Ok(if comparable {
+        Some((s.to_affine(), sx.to_affine()))
+    } else {
+        None
+    })
+}
+
+/// Validate serialized parameters (as written by `write`) against the
+/// initial parameters of their circuit, reading them only once and
+/// without holding the H and L queries in memory. This combines what
+/// `read_verified` and `verify_chain` check, but reports on all of
+/// the checks instead of stopping at the first failure.
+pub fn validate_file<E: MPCEngine, R: Read>(
+    reader: R,
+    token: &StructureToken<E>,
+) -> FileValidationReport {
+    let mut report = FileValidationReport {
+        integrity_ok: true,
+        structure_ok: true,
+        chain_ok: true,
+        contribution_hashes: vec![],
+        first_error: None,
+    };
+
+    let mut reader = BufReader::with_capacity(1024 * 1024, reader);
+
+    if let Err(e) = validate_stream(&mut reader, token, &mut report) {
+        // The rest of the file can't be checked
+        report.structure_ok = false;
+        report.chain_ok = false;
+        report.fail(Check::Integrity, e.into());
+    }
+
+    report
+}
+
+fn validate_stream<E: MPCEngine, R: Read>(
+    reader: &mut R,
+    token: &StructureToken<E>,
+    report: &mut FileValidationReport,
+) -> io::Result<()> {
+    let initial = &token.params;
+
+    let (header, mut reader) = header::read_header::<E, _>(reader)?;
+    let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+    let scheme = header::scheme_of(&header)?;
+    let reader = &mut reader;
+
+    let vk = VerifyingKey::<E>::read(&mut *reader)?;
+
+    let g1_points = [
+        ("alpha_g1", vk.alpha_g1),
+        ("beta_g1", vk.beta_g1),
+        ("delta_g1", vk.delta_g1),
+    ];
+    let g2_points = [
+        ("beta_g2", vk.beta_g2),
+        ("gamma_g2", vk.gamma_g2),
+        ("delta_g2", vk.delta_g2),
+    ];
+    for (query, p) in g1_points {
+        if p.is_identity().into() {
+            report.fail(
+                Check::Integrity,
+                VerificationError::PointAtInfinity { query, index: 0 },
+            );
+        }
+    }
+    for (query, p) in g2_points {
+        if p.is_identity().into() {
+            report.fail(
+                Check::Integrity,
+                VerificationError::PointAtInfinity { query, index: 0 },
+            );
+        }
+    }
+    if let Some(index) = vk.ic.iter().position(|p| p.is_identity().into()) {
+        report.fail(
+            Check::Integrity,
+            VerificationError::PointAtInfinity { query: "ic", index },
+        );
+    }
+
+    // alpha/beta/gamma/IC don't change
+    if initial.vk.alpha_g1 != vk.alpha_g1 {
+        report.fail(Check::Structure, VerificationError::AlphaG1Mismatch);
+    }
+    if initial.vk.beta_g1 != vk.beta_g1 {
+        report.fail(Check::Structure, VerificationError::BetaG1Mismatch);
+    }
+    if initial.vk.beta_g2 != vk.beta_g2 {
+        report.fail(Check::Structure, VerificationError::BetaG2Mismatch);
+    }
+    if initial.vk.gamma_g2 != vk.gamma_g2 {
+        report.fail(Check::Structure, VerificationError::GammaG2Mismatch);
+    }
+    if initial.vk.ic != vk.ic {
+        report.fail(Check::Structure, VerificationError::IcMismatch);
+    }
+
+    let h = read_query_merged(reader, "h", &initial.h, report)?;
+    if h.is_none() {
+        report.fail(Check::Structure, VerificationError::HQueryLengthMismatch);
+    }
+    let l = read_query_merged(reader, "l", &initial.l, report)?;
+    if l.is_none() {
+        report.fail(Check::Structure, VerificationError::LQueryLengthMismatch);
+    }
+
+    // A/B_G1/B_G2 don't change
+    if !read_query_compared(reader, "a", &initial.a, report)? {
+        report.fail(Check::Structure, VerificationError::AQueryMismatch);
+    }
+    if !read_query_compared(reader, "b_g1", &initial.b_g1, report)? {
+        report.fail(Check::Structure, VerificationError::BG1QueryMismatch);
+    }
+    if !read_query_compared(reader, "b_g2", &initial.b_g2, report)? {
+        report.fail(Check::Structure, VerificationError::BG2QueryMismatch);
+    }
+
+    let mut cs_hash = [0u8; 64];
+    reader.read_exact(&mut cs_hash)?;
+    if let Some(header) = &header {
+        header.check_cs_hash(&cs_hash)?;
+    }
+    if cs_hash[..] != token.cs_hash[..] {
+        report.fail(Check::Structure, VerificationError::CsHashMismatch);
+    }
+
+    let mut chain = ChainVerifier::<E>::new(&token.cs_hash, &*scheme);
+    let mut chain_intact = true;
+    let (_, count) = read_contributions_len(reader, version)?;
+    for _ in 0..count {
+        let pubkey = PublicKey::read(&mut *reader)?;
+        report.contribution_hashes.push(pubkey.hash());
+
+        if chain_intact {
+            if let Err(e) = chain.push(&pubkey) {
+                report.fail(Check::Chain, e);
+                chain_intact = false;
+            }
+        }
+    }
+
+    if !chain_intact {
+        // Delta can't be checked against a broken chain
+        return Ok(());
+    }
+
+    // Current parameters should have consistent delta
+    if chain.current_delta != vk.delta_g1 {
+        report.fail(Check::Chain, VerificationError::DeltaG1Mismatch);
+    }
+
+    let g2 = E::G2Prepared::from(E::G2Affine::generator());
+    let delta_g2 = E::G2Prepared::from(vk.delta_g2);
+
+    if !same_ratio_prepared::<E>((E::G1Affine::generator(), vk.delta_g1), (&g2, &delta_g2)) {
+        report.fail(Check::Chain, VerificationError::DeltaG2Mismatch);
+    }
+
+    if count > 0 && vk.delta_g1 == E::G1Affine::generator() {
+        report.fail(Check::Chain, VerificationError::NetDeltaIsIdentity);
+    }
+
+    // H and L queries should be updated with delta^-1
+    match h {
+        Some(h) if same_ratio_prepared::<E>(h, (&delta_g2, &g2)) => {}
+        Some(_) => report.fail(Check::Chain, VerificationError::HQueryRatioMismatch),
+        None => report.chain_ok = false,
+    }
+    match l {
+        Some(l) if same_ratio_prepared::<E>(l, (&delta_g2, &g2)) => {}
+        Some(_) => report.fail(Check::Chain, VerificationError::LQueryRatioMismatch),
+        None => report.chain_ok = false,
+    }
+
+    Ok(())
+}
+
+/// Checks if pairs have the same ratio.
+fn same_ratio<G1: pairing::PairingCurveAffine>(g1: (G1, G1), g2: (G1::Pair, G1::Pair)) -> bool {
+    g1.0.pairing_with(&g2.1) == g1.1.pairing_with(&g2.0)
+}
+
+/// Like `same_ratio`, for G2 elements that were already prepared
+/// for pairing. Verification compares many ratios against the same
+/// few G2 elements (the generator and delta), so preparing those once
+/// avoids redoing their line computations for every check.
+fn same_ratio_prepared<E: MultiMillerLoop>(
+    g1: (E::G1Affine, E::G1Affine),
+    g2: (&E::G2Prepared, &E::G2Prepared),
+) -> bool {
+    E::multi_miller_loop(&[(&g1.0, g2.1), (&-g1.1, g2.0)])
+        .final_exponentiation()
+        .is_identity()
+        .into()
+}
+
+/// A part of a long-running operation, as reported to the callbacks
+/// of `MPCParameters::new_with_progress`, `contribute_with_progress`
+/// and `verify_with_progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading the Powers of Tau radix file, counted in points.
+    ReadRadix,
+    /// Evaluating the QAP polynomials at tau, counted in variables.
+    EvaluateQap,
+    /// Updating the H and L queries with the new delta, counted in
+    /// points.
+    Contribute,
+    /// Checking that the H and L queries were updated consistently
+    /// with delta, counted in points.
+    Verify,
+}
+
+/// A flag for cancelling a long-running operation from another
+/// thread, e.g. once it has run over its time budget. Clones share
+/// the flag. See `MPCParameters::new_cancellable`,
+/// `contribute_cancellable` and `verify_cancellable`.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// A token that isn't cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the operations this token was given to. They stop at
+    /// the next chunk of work.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether `cancel` was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A progress callback, given a stage, how much of it is done and
+/// its total, along with the token that cancels the operation, if
+/// it can be cancelled.
+#[derive(Clone, Copy)]
+struct Progress<'a> {
+    report: &'a (dyn Fn(Stage, usize, usize) + Sync),
+    cancel: Option<&'a CancellationToken>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(report: &'a (dyn Fn(Stage, usize, usize) + Sync)) -> Self {
+        Progress {
+            report,
+            cancel: None,
+        }
+    }
+
+    fn cancellable(cancel: &'a CancellationToken) -> Self {
+        Progress {
+            report: &no_progress,
+            cancel: Some(cancel),
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(|cancel| cancel.is_cancelled())
+    }
+}
+
+pub(crate) fn no_progress(_: Stage, _: usize, _: usize) {}
+
+/// Counts the work done on a stage, which may be advanced from
+/// several threads at once, reporting it to a progress callback.
+struct Tracker<'a> {
+    progress: Progress<'a>,
+    stage: Stage,
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl<'a> Tracker<'a> {
+    fn new(progress: Progress<'a>, stage: Stage, total: usize) -> Self {
+        Tracker {
+            progress,
+            stage,
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    fn advance(&self, n: usize) {
+        let done = self.done.fetch_add(n, Ordering::Relaxed) + n;
+        (self.progress.report)(self.stage, done, self.total);
+    }
+
+    /// Fails if the operation was cancelled.
+    fn check(&self) -> io::Result<()> {
+        if self.progress.cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes a random linear combination over v1/v2.
+///
+/// Checking that many pairs of elements are exponentiated by
+/// the same `x` can be achieved (with high probability) with
+/// the following technique:
+///
+/// Given v1 = [a, b, c] and v2 = [as, bs, cs], compute
+/// (a*r1 + b*r2 + c*r3, (as)*r1 + (bs)*r2 + (cs)*r3) for some
+/// random r1, r2, r3. Given (g, g^s)...
+///
+/// e(g, (as)*r1 + (bs)*r2 + (cs)*r3) = e(g^s, a*r1 + b*r2 + c*r3)
+///
+/// ... with high probability.
+fn merge_pairs<G: pairing::PairingCurveAffine>(v1: &[G], v2: &[G]) -> (G, G)
+where
+    G::Curve: WnafGroup,
+{
+    use std::sync::Mutex;
+
+    assert_eq!(v1.len(), v2.len());
+
+    let chunk = (v1.len() / multicore::num_threads()) + 1;
+
+    let s = Arc::new(Mutex::new(G::Curve::identity()));
+    let sx = Arc::new(Mutex::new(G::Curve::identity()));
+
+    multicore::scope(|scope| {
+        for (v1, v2) in v1.chunks(chunk).zip(v2.chunks(chunk)) {
+            let s = s.clone();
+            let sx = sx.clone();
+
+            scope.spawn(move || {
+                let (local_s, local_sx) = merge_chunk(v1, v2);
+
+                s.lock().unwrap().add_assign(&local_s);
+                sx.lock().unwrap().add_assign(&local_sx);
+            });
+        }
+    });
+
+    let s = s.lock().unwrap().to_affine();
+    let sx = sx.lock().unwrap().to_affine();
+
+    (s, sx)
+}
+
+/// The number of pairs `merge_pairs_on` merges with one stream of
+/// scalars. It's fixed, so that seeded scalars don't depend on the
+/// number of threads, and small, so that the pieces of a query still
+/// spread over all of them.
+const MERGE_PIECE: usize = 1 << 12;
+
+/// Where `merge_pairs_on` takes the scalars it merges pairs with.
+#[derive(Clone, Copy)]
+enum MergeScalars<'a, F> {
+    /// Fresh ones from `thread_rng`.
+    Random,
+    /// For the `i`th piece of `MERGE_PIECE` pairs, ones drawn from
+    /// ChaCha stream `i` of the seed.
+    Seeded([u8; 32]),
+    /// These ones, one per pair, in order.
+    Given(&'a [F]),
+}
+
+/// Like `merge_pairs`, spreading the work over `worker`'s threads
+/// instead of one thread per CPU, and advancing `tracker` as the
+/// pairs are merged. The pairs are split into pieces of
+/// `MERGE_PIECE` before they're handed to the threads, so seeded
+/// scalars are the same however many threads there are.
+fn merge_pairs_on<G: pairing::PairingCurveAffine>(
+    worker: &Worker,
+    v1: &[G],
+    v2: &[G],
+    tracker: &Tracker,
+    scalars: MergeScalars<G::Scalar>,
+) -> (G, G)
+where
+    G::Curve: WnafGroup,
+{
+    merge_pieces_on(worker, v1, v2, MERGE_PIECE, tracker, scalars)
+}
+
+/// `merge_pairs_on`, with pieces of `piece` pairs.
+fn merge_pieces_on<G: pairing::PairingCurveAffine>(
+    worker: &Worker,
+    v1: &[G],
+    v2: &[G],
+    piece: usize,
+    tracker: &Tracker,
+    scalars: MergeScalars<G::Scalar>,
+) -> (G, G)
+where
+    G::Curve: WnafGroup,
+{
+    use std::sync::Mutex;
+
+    assert_eq!(v1.len(), v2.len());
+
+    let s = Mutex::new(G::Curve::identity());
+    let sx = Mutex::new(G::Curve::identity());
+
+    let pieces = v1.len().div_ceil(piece);
+    worker.scope(pieces, |scope, per_thread| {
+        // Without `multicore`, this is the number of pieces
+        let per_thread = per_thread.max(1);
+
+        for first in (0..pieces).step_by(per_thread) {
+            let (s, sx) = (&s, &sx);
+
+            scope.spawn(move |_| {
+                // Merged a piece at a time, so that a cancelled check
+                // stops soon after
+                for i in first..pieces.min(first + per_thread) {
+                    if tracker.progress.cancelled() {
+                        return;
+                    }
+
+                    let range = i * piece..v1.len().min((i + 1) * piece);
+                    let (v1, v2) = (&v1[range.clone()], &v2[range]);
+                    let (local_s, local_sx) = match scalars {
+                        MergeScalars::Random => merge_chunk(v1, v2),
+                        MergeScalars::Seeded(seed) => {
+                            let mut rng = ChaChaRng::from_seed(seed);
+                            rng.set_stream(i as u64);
+                            merge_chunk_with(&mut rng, v1, v2)
+                        }
+                        MergeScalars::Given(scalars) => {
+                            let scalars = &scalars[i * piece..];
+                            merge_chunk_scalars(v1, v2, scalars.iter().copied())
+                        }
+                    };
+
+                    s.lock().unwrap().add_assign(&local_s);
+                    sx.lock().unwrap().add_assign(&local_sx);
+
+                    tracker.advance(v1.len());
+                }
+            });
+        }
+    });
+
+    let s = s.into_inner().unwrap().to_affine();
+    let sx = sx.into_inner().unwrap().to_affine();
+
+    (s, sx)
+}
+
+/// Merges one chunk of the pairs for `merge_pairs`, on the current
+/// thread.
+fn merge_chunk<G: pairing::PairingCurveAffine>(v1: &[G], v2: &[G]) -> (G::Curve, G::Curve)
+where
+    G::Curve: WnafGroup,
+{
+    // We do not need to be overly cautious of the RNG
+    // used for this check.
+    merge_chunk_with(&mut rand::thread_rng(), v1, v2)
+}
+
+/// `merge_chunk`, with the random scalars drawn from `rng`.
+fn merge_chunk_with<G, R>(rng: &mut R, v1: &[G], v2: &[G]) -> (G::Curve, G::Curve)
+where
+    G: pairing::PairingCurveAffine,
+    G::Curve: WnafGroup,
+    R: rand::RngCore,
+{
+    merge_chunk_scalars(
+        v1,
+        v2,
+        std::iter::repeat_with(|| G::Scalar::random(&mut *rng)),
+    )
+}
+
+/// `merge_chunk`, with the pairs merged with `scalars`, in order.
+fn merge_chunk_scalars<G, I>(v1: &[G], v2: &[G], scalars: I) -> (G::Curve, G::Curve)
+where
+    G: pairing::PairingCurveAffine,
+    G::Curve: WnafGroup,
+    I: Iterator<Item = G::Scalar>,
+{
+    let mut wnaf = Wnaf::new();
+    let mut s = G::Curve::identity();
+    let mut sx = G::Curve::identity();
+
+    for ((v1, v2), rho) in v1.iter().zip(v2.iter()).zip(scalars) {
+        let mut wnaf = wnaf.scalar(&rho);
+        let v1 = wnaf.base(v1.to_curve());
+        let v2 = wnaf.base(v2.to_curve());
+
+        s.add_assign(&v1);
+        sx.add_assign(&v2);
+    }
+
+    (s, sx)
+}
+
+/// Like `merge_pairs`, but also returns the random scalars that
+/// were used, in order, so that an auditor can replay the exact
+/// same check with `merge_pairs_with_scalars`.
+pub fn merge_pairs_recorded<G: pairing::PairingCurveAffine>(
+    v1: &[G],
+    v2: &[G],
+) -> ((G, G), Vec<G::Scalar>)
+where
+    G::Curve: WnafGroup,
+{
+    let rng = &mut rand::thread_rng();
+    let scalars: Vec<_> = (0..v1.len())
+        .map(|_| G::Scalar::random(&mut *rng))
+        .collect();
+
+    (merge_pairs_with_scalars(v1, v2, &scalars), scalars)
+}
+
+/// Computes the linear combination over v1/v2 that `merge_pairs`
+/// computes, using the given `scalars` instead of random ones.
+pub fn merge_pairs_with_scalars<G: pairing::PairingCurveAffine>(
+    v1: &[G],
+    v2: &[G],
+    scalars: &[G::Scalar],
+) -> (G, G)
+where
+    G::Curve: WnafGroup,
+{
+    assert_eq!(v1.len(), scalars.len());
+
+    let tracker = Tracker::new(Progress::new(&no_progress), Stage::Verify, 0);
+    merge_pairs_on(
+        &Worker::new(),
+        v1,
+        v2,
+        &tracker,
+        MergeScalars::Given(scalars),
+    )
+}
+
+/// This needs to be destroyed by at least one participant
+/// for the final parameters to be secure. It's wiped when dropped.
+struct PrivateKey<E: MPCEngine> {
+    delta: E::Fr,
+}
+
+impl<E: MPCEngine> Zeroize for PrivateKey<E> {
+    fn zeroize(&mut self) {
+        self.delta.zeroize();
+    }
+}
+
+impl<E: MPCEngine> Drop for PrivateKey<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<E: MPCEngine> ZeroizeOnDrop for PrivateKey<E> {}
+
+/// Encodes `bytes` as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A record of the state of a ceremony, for publishing to an
+/// append-only transparency log. See `MPCParameters::to_log_entry`.
+#[derive(Clone, Debug)]
+pub struct LogEntry<E: Engine = Bls12> {
+    /// The hash of the circuit.
+    pub cs_hash: [u8; 64],
+    /// See `MPCParameters::chain_commitment`.
+    pub chain_commitment: [u8; 64],
+    /// The number of contributions so far.
+    pub contribution_count: usize,
+    /// Delta in G1 after the last contribution.
+    pub final_delta: E::G1Affine,
+    /// When the entry was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl<E: Engine> PartialEq for LogEntry<E> {
+    fn eq(&self, other: &LogEntry<E>) -> bool {
+        self.cs_hash[..] == other.cs_hash[..]
+            && self.chain_commitment[..] == other.chain_commitment[..]
+            && self.contribution_count == other.contribution_count
+            && self.final_delta == other.final_delta
+            && self.timestamp == other.timestamp
+    }
+}
+
+impl<E: Engine> Eq for LogEntry<E> {}
+
+impl<E: Engine> LogEntry<E> {
+    /// Serialize the entry as a JSON object, with hashes as hex and
+    /// `final_delta` as the hex of its compressed encoding. The
+    /// fields are always in the same order and without whitespace,
+    /// so the output can be signed as is.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"cs_hash\":\"{}\",\"chain_commitment\":\"{}\",\"contribution_count\":{},\"final_delta\":\"{}\",\"timestamp\":{}}}",
+            hex(&self.cs_hash),
+            hex(&self.chain_commitment),
+            self.contribution_count,
+            hex(self.final_delta.to_bytes().as_ref()),
+            self.timestamp
+        )
+    }
+}
+
+/// Encodes `bytes` as unpadded base64url.
+fn base64url(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    let mut out = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let mut buf = [0u8; 3];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let n = (u32::from(buf[0]) << 16) | (u32::from(buf[1]) << 8) | u32::from(buf[2]);
+
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+
+    out
+}
+
+/// The size of a circuit, as returned by `analyze_circuit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    /// The number of constraints, including the one `new` adds for
+    /// each input.
+    pub num_constraints: usize,
+    /// The number of public inputs, including the constant one.
+    pub num_inputs: usize,
+    /// The number of private variables.
+    pub num_aux: usize,
+    /// The size of the evaluation domain, the smallest power of two
+    /// that fits the constraints. This is also the length of the H
+    /// query, plus one.
+    pub domain_size: usize,
+    /// The exponent of the radix file `new` will read, i.e.
+    /// `phase1radix2m{required_radix_exp}`. This may be larger than
+    /// any radix file there is.
+    pub required_radix_exp: u32,
+}
+
+/// Synthesize `circuit` and measure it, without reading a radix file
+/// or evaluating the QAP, which is where `new` spends its time. This
+/// tells you which radix file you need, and how large the parameters
+/// will be.
+pub fn analyze_circuit<F, C>(circuit: C) -> Result<CircuitStats, SynthesisError>
+where
+    F: PrimeField,
+    C: Circuit<F>,
+{
+    let assembly = KeypairAssembly::synthesize(circuit)?;
+    let domain_size = assembly.num_constraints.next_power_of_two();
+
+    Ok(CircuitStats {
+        num_constraints: assembly.num_constraints,
+        num_inputs: assembly.num_inputs,
+        num_aux: assembly.num_aux,
+        domain_size,
+        required_radix_exp: domain_size.trailing_zeros(),
+    })
+}
+
+/// The rough cost of verifying some parameters, as returned by
+/// `MPCParameters::estimate_verify_cost`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifyCostEstimate {
+    /// The number of pairings computed.
+    pub pairings: usize,
+    /// The length of the H query, which is merged with `merge_pairs`.
+    pub h_query_len: usize,
+    /// The length of the L query, which is merged with `merge_pairs`.
+    pub l_query_len: usize,
+    /// The number of points `merge_pairs` multiplies by a random
+    /// scalar, which dominates verification for large circuits.
+    pub merge_pairs_points: usize,
+}
+
+/// The layout used to serialize the contributions at the end of
+/// `MPCParameters`. Everything before them is always laid out as
+/// bellman `Parameters`, followed by the `cs_hash`. `write` records
+/// the layout in a header, which `read` follows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatVersion {
+    /// The original layout: the number of contributions is a
+    /// big-endian `u32`. This is what `write` uses, and what `read`
+    /// assumes of files without a header.
+    V1,
+    /// The number of contributions is an unsigned LEB128 varint, so
+    /// it isn't limited to `u32`.
+    V2,
+    /// Like `V2`, with the `circuit_fingerprint` of the circuit
+    /// between the `cs_hash` and the contributions. Only parameters
+    /// whose fingerprint is known can be written this way.
+    V3,
+}
+
+/// The serialized parameters for a prover, as produced by
+/// `MPCParameters::split_keys`. They are exactly what `write_legacy`
+/// emits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProvingKeyBytes(pub Vec<u8>);
+
+/// The serialized verifying key of some parameters, as produced by
+/// `MPCParameters::split_keys`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKeyBytes(pub Vec<u8>);
+
+/// The record of the ceremony that `MPCParameters::into_keys` keeps
+/// alongside the bare keys, to tie them back to the published
+/// transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptSummary {
+    /// The `cs_hash` of the circuit.
+    pub cs_hash: [u8; 64],
+    /// The hash of each contribution, in order.
+    pub contribution_hashes: Vec<ContributionHash>,
+    /// The `chain_commitment` of the final parameters.
+    pub chain_commitment: [u8; 64],
+}
+
+impl VerifyingKeyBytes {
+    /// Deserialize the verifying key.
+    pub fn verifying_key<E: Engine>(&self) -> io::Result<VerifyingKey<E>> {
+        VerifyingKey::read(&self.0[..])
+    }
+}
+
+/// Writes `n` as an unsigned LEB128 varint.
+fn write_varint<W: Write>(writer: &mut W, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+
+        if n == 0 {
+            return writer.write_u8(byte);
+        }
+        writer.write_u8(byte | 0x80)?;
+    }
+}
+
+/// Reads an unsigned LEB128 varint, rejecting ones that overflow
+/// a `u64`.
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut n = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = reader.read_u8()?;
+        let bits = u64::from(byte & 0x7f);
+
+        if (bits << shift) >> shift != bits {
+            break;
+        }
+        n |= bits << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint overflow",
+    ))
+}
+
+/// The number of points in each piece of a query that
+/// `MPCParameters::body_pieces` serializes.
+const PIECE_POINTS: usize = 1 << 14;
+
+/// Serializes `query` as `Parameters::write` does, its length and
+/// then its points, uncompressed, in pieces of `PIECE_POINTS`.
+fn query_pieces<G: UncompressedEncoding>(query: &[G]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let len = (query.len() as u32).to_be_bytes().to_vec();
+
+    iter::once(len).chain(query.chunks(PIECE_POINTS).map(|chunk| {
+        let mut piece = vec![];
+        for p in chunk {
+            piece.extend_from_slice(p.to_uncompressed().as_ref());
+        }
+        piece
+    }))
+}
+
+/// Writes what `version` lays out between the `cs_hash` and the
+/// contributions: the circuit fingerprint, for `V3`, and the number
+/// of contributions.
+pub(crate) fn write_contributions_len<W: Write>(
+    writer: &mut W,
+    version: FormatVersion,
+    circuit_fingerprint: Option<&[u8; 32]>,
+    len: usize,
+) -> io::Result<()> {
+    match version {
+        FormatVersion::V1 => writer.write_u32::<BigEndian>(len as u32),
+        FormatVersion::V2 => write_varint(writer, len as u64),
+        FormatVersion::V3 => {
+            let fingerprint = circuit_fingerprint.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the circuit fingerprint isn't known",
+                )
+            })?;
+            writer.write_all(fingerprint)?;
+            write_varint(writer, len as u64)
+        }
+    }
+}
+
+/// Reads what `write_contributions_len` writes for `version`: the
+/// circuit fingerprint, if the layout has one, and the number of
+/// contributions.
+pub(crate) fn read_contributions_len<R: Read>(
+    reader: &mut R,
+    version: FormatVersion,
+) -> io::Result<(Option<[u8; 32]>, u64)> {
+    match version {
+        FormatVersion::V1 => Ok((None, u64::from(reader.read_u32::<BigEndian>()?))),
+        FormatVersion::V2 => Ok((None, read_varint(reader)?)),
+        FormatVersion::V3 => {
+            let mut fingerprint = [0u8; 32];
+            reader.read_exact(&mut fingerprint)?;
+            Ok((Some(fingerprint), read_varint(reader)?))
+        }
+    }
+}
+
+/// Header of the bytes `canonical_hash` hashes, before the
+/// `canonical_bytes`. The trailing version byte is bumped whenever
+/// their layout changes.
+const CANONICAL_HASH_MAGIC: &[u8; 17] = b"phase2 canonical\x01";
+
+/// Domain separator for expanding a contribution seed.
+const SEED_DOMAIN: &[u8] = b"phase2 contribution seed";
+
+/// Identifies the KDF `contribute_with_seed` uses: the seed is hashed
+/// as BLAKE2b-256("phase2 contribution seed" | seed), and the result
+/// seeds the ChaCha20 RNG that `contribute` then draws from. The
+/// transcript has no room for metadata, so contributors who want
+/// their contribution to be reproducible should publish this along
+/// with their contribution hash.
+pub const SEED_KDF: &str = "phase2-seed-blake2b256-chacha20-v1";
+
+/// Expands a user-supplied seed into the seed of the RNG used to
+/// contribute. This is BLAKE2b-256(SEED_DOMAIN | seed).
+fn expand_seed(seed: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Blake2b::new(32);
+    hasher.update(SEED_DOMAIN);
+    hasher.update(&seed[..]);
+
+    let mut expanded = [0u8; 32];
+    expanded.copy_from_slice(hasher.finalize().as_ref());
+    expanded
+}
+
+/// Derives the seed of the RNG used by `contribute_beacon`, by
+/// hashing the beacon value with BLAKE2b-256 and then rehashing the
+/// result `iterations` times, so that the seed is slow to compute.
+fn beacon_seed(beacon_hash: &[u8], iterations: u64) -> [u8; 32] {
+    let mut hasher = Blake2b::new(32);
+    hasher.update(beacon_hash);
+
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(hasher.finalize().as_ref());
+
+    for _ in 0..iterations {
+        let mut hasher = Blake2b::new(32);
+        hasher.update(&seed);
+        seed.copy_from_slice(hasher.finalize().as_ref());
+    }
+
+    seed
+}
+
+/// The number of points of the H and L queries a contribution
+/// exponentiates at once.
+const CONTRIBUTE_CHUNK: usize = 1 << 16;
+
+/// Exponentiates all of `bases` by `coeff`, in parallel.
+fn batch_exp<E: MPCEngine>(bases: &mut [E::G1Affine], mut coeff: E::Fr, tracker: &Tracker) {
+    let cpus = multicore::num_threads();
+    let chunk_size = if bases.len() < cpus {
+        1
+    } else {
+        bases.len() / cpus
+    };
+
+    // Perform wNAF over multiple cores, normalizing each core's
+    // results straight back into its bases. The threads borrow the
+    // scalar, so that there's a single copy of it to wipe.
+    let secret = &coeff;
+    multicore::scope(|scope| {
+        for bases in bases.chunks_mut(chunk_size) {
+            scope.spawn(move || {
+                let mut wnaf = Wnaf::new();
+                let mut wnaf = wnaf.scalar(secret);
+
+                let projective: Vec<E::G1> = bases
+                    .iter()
+                    .map(|base| wnaf.base(base.to_curve()))
+                    .collect();
+                E::G1::batch_normalize(&projective, bases);
+
+                tracker.advance(bases.len());
+            });
+        }
+    });
+
+    coeff.zeroize();
+}
+
+fn batch_normalization<C: group::Curve>(proj: &mut [C])
+where
+    C::AffineRepr: Clone + Into<C>,
+{
+    let mut affines = vec![C::identity().to_affine(); proj.len()];
+    C::batch_normalize(&*proj, &mut affines);
+    proj.iter_mut().zip(affines.iter()).for_each(|(a, b)| {
+        *a = b.clone().into();
+    });
+}
+
+/// The exponent of the size of the evaluation domain of `assembly`,
+/// which is at most `max_exp`.
+fn domain_exp<Fr: PrimeField>(
+    assembly: &KeypairAssembly<Fr>,
+    max_exp: u32,
+) -> Result<u32, SynthesisError> {
+    // The domain can't be larger than the 2-adicity of the field
+    phase1::required_exp_up_to(assembly.num_constraints, max_exp.min(Fr::S))
+        .map_err(|_| SynthesisError::PolynomialDegreeTooLarge)
+}
+
+/// The number of variables whose QAP polynomials are evaluated at a
+/// time when creating parameters, between checkpoints.
+const EVAL_BLOCK: usize = 1 << 16;
+
+/// The contents of a radix file.
+struct Radix<E: Engine> {
+    alpha: E::G1Affine,
+    beta_g1: E::G1Affine,
+    beta_g2: E::G2Affine,
+    coeffs_g1: Arc<Vec<E::G1Affine>>,
+    coeffs_g2: Arc<Vec<E::G2Affine>>,
+    alpha_coeffs_g1: Arc<Vec<E::G1Affine>>,
+    beta_coeffs_g1: Arc<Vec<E::G1Affine>>,
+    h: Vec<E::G1Affine>,
+}
+
+impl<E: MPCEngine> Radix<E> {
+    /// Read the radix file for a domain of size `m`.
+    fn read<R: Read>(f: R, m: usize, progress: Progress) -> io::Result<Self> {
+        let f = &mut BufReader::with_capacity(1024 * 1024, f);
+        let reading = Tracker::new(progress, Stage::ReadRadix, 3 + 5 * m - 1);
+
+        let read_g1 = |reader: &mut BufReader<R>| -> io::Result<E::G1Affine> {
+            let mut repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+
+            Option::from(<E::G1Affine as UncompressedEncoding>::from_uncompressed_unchecked(&repr))
+                .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))
+                .and_then(|e: E::G1Affine| {
+                    if e.is_identity().into() {
+                        Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "point at infinity",
+                        ))
+                    } else {
+                        Ok(e)
+                    }
+                })
+        };
+
+        let read_g2 = |reader: &mut BufReader<R>| -> io::Result<E::G2Affine> {
+            let mut repr = <E::G2Affine as UncompressedEncoding>::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+
+            Option::from(<E::G2Affine as UncompressedEncoding>::from_uncompressed_unchecked(&repr))
+                .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))
+                .and_then(|e: E::G2Affine| {
+                    if e.is_identity().into() {
+                        Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "point at infinity",
+                        ))
+                    } else {
+                        Ok(e)
+                    }
+                })
+        };
+
+        let alpha = read_g1(f)?;
+        let beta_g1 = read_g1(f)?;
+        let beta_g2 = read_g2(f)?;
+        reading.advance(3);
+        reading.check()?;
+
+        let mut coeffs_g1 = Vec::with_capacity(m);
+        for _ in 0..m {
+            coeffs_g1.push(read_g1(f)?);
+        }
+        reading.advance(m);
+        reading.check()?;
+
+        let mut coeffs_g2 = Vec::with_capacity(m);
+        for _ in 0..m {
+            coeffs_g2.push(read_g2(f)?);
+        }
+        reading.advance(m);
+        reading.check()?;
+
+        let mut alpha_coeffs_g1 = Vec::with_capacity(m);
+        for _ in 0..m {
+            alpha_coeffs_g1.push(read_g1(f)?);
+        }
+        reading.advance(m);
+        reading.check()?;
+
+        let mut beta_coeffs_g1 = Vec::with_capacity(m);
+        for _ in 0..m {
+            beta_coeffs_g1.push(read_g1(f)?);
+        }
+        reading.advance(m);
+        reading.check()?;
+
+        let mut h = Vec::with_capacity(m - 1);
+        for _ in 0..(m - 1) {
+            h.push(read_g1(f)?);
+        }
+        reading.advance(m - 1);
+
+        // These are `Arc` so that later it'll be easier
+        // to use multiexp during QAP evaluation (which
+        // requires a futures-based API)
+        Ok(Radix {
+            alpha,
+            beta_g1,
+            beta_g2,
+            coeffs_g1: Arc::new(coeffs_g1),
+            coeffs_g2: Arc::new(coeffs_g2),
+            alpha_coeffs_g1: Arc::new(alpha_coeffs_g1),
+            beta_coeffs_g1: Arc::new(beta_coeffs_g1),
+            h,
+        })
+    }
+}
+
+/// The QAP polynomials of every variable (the inputs, then the
+/// auxiliary variables) evaluated at tau, of which the first `done`
+/// have been computed so far.
+struct QapEvaluation<E: Engine> {
+    a_g1: Vec<E::G1>,
+    b_g1: Vec<E::G1>,
+    b_g2: Vec<E::G2>,
+    /// The IC query for the inputs, and the L query for the rest,
+    /// before dividing by gamma and delta (which are 1).
+    ext: Vec<E::G1>,
+    done: usize,
+}
+
+impl<E: MPCEngine> QapEvaluation<E> {
+    fn new(num_vars: usize) -> Self {
+        QapEvaluation {
+            a_g1: vec![E::G1::identity(); num_vars],
+            b_g1: vec![E::G1::identity(); num_vars],
+            b_g2: vec![E::G2::identity(); num_vars],
+            ext: vec![E::G1::identity(); num_vars],
+            done: 0,
+        }
+    }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Create new Groth16 parameters (compatible with bellman) for a
+    /// given circuit. The resulting parameters are unsafe to use
+    /// until there are contributions (see `contribute()`).
+    ///
+    /// This reads `phase1radix2m{exp}` from the current directory,
+    /// failing with an I/O error if it's missing. See
+    /// `new_from_source` to read it from elsewhere.
+    #[cfg(feature = "fs")]
+    pub fn new<C>(circuit: C) -> Result<MPCParameters<E>, SynthesisError>
+    where
+        C: Circuit<E::Fr>,
+    {
+        MPCParameters::new_with_progress(circuit, no_progress)
+    }
+
+    /// Like `new()`, calling `progress` as the radix file is read
+    /// and the QAP is evaluated (see `Stage`).
+    #[cfg(feature = "fs")]
+    pub fn new_with_progress<C, P>(
+        circuit: C,
+        progress: P,
+    ) -> Result<MPCParameters<E>, SynthesisError>
+    where
+        C: Circuit<E::Fr>,
+        P: Fn(Stage, usize, usize) + Sync,
+    {
+        MPCParameters::new_in_cwd(circuit, Progress::new(&progress))
+    }
+
+    /// Like `new()`, giving up with `Phase2Error::Cancelled` soon
+    /// after `cancel` is cancelled.
+    #[cfg(feature = "fs")]
+    pub fn new_cancellable<C>(
+        circuit: C,
+        cancel: &CancellationToken,
+    ) -> Result<MPCParameters<E>, Phase2Error>
+    where
+        C: Circuit<E::Fr>,
+    {
+        match MPCParameters::new_in_cwd(circuit, Progress::cancellable(cancel)) {
+            Err(_) if cancel.is_cancelled() => Err(Phase2Error::Cancelled),
+            result => Ok(result?),
+        }
+    }
+
+    /// `new()`, reporting to `progress`.
+    #[cfg(feature = "fs")]
+    fn new_in_cwd<C>(circuit: C, progress: Progress) -> Result<MPCParameters<E>, SynthesisError>
+    where
+        C: Circuit<E::Fr>,
+    {
+        MPCParameters::new_with_radix(
+            circuit,
+            phase1::MAX_EXP,
+            |exp| {
+                // Try to load "phase1radix2m{}"
+                Ok(File::open(format!("phase1radix2m{}", exp))?)
+            },
+            progress,
+        )
+    }
+
+    /// Like `new()`, reading the radix files described by `opts`,
+    /// e.g. to use the larger ones of a Powers of Tau transcript
+    /// beyond 2^21.
+    #[cfg(feature = "fs")]
+    pub fn new_with_opts<C>(
+        circuit: C,
+        opts: phase1::Phase1Options,
+    ) -> Result<MPCParameters<E>, Phase2Error>
+    where
+        C: Circuit<E::Fr>,
+    {
+        MPCParameters::new_with_radix(
+            circuit,
+            opts.max_exp,
+            |exp| Ok(File::open(opts.path(exp))?),
+            Progress::new(&no_progress),
+        )
+    }
+
+    /// Like `new()`, also auditing the circuit (see `audit_circuit`).
+    /// If any auxiliary variable is unconstrained, this fails with
+    /// `Phase2Error::UnconstrainedVariables` naming them, where `new()`
+    /// would only say that there is one.
+    #[cfg(feature = "fs")]
+    pub fn new_with_audit<C>(circuit: C) -> Result<(MPCParameters<E>, AuditReport), Phase2Error>
+    where
+        C: Circuit<E::Fr>,
+    {
+        let (assembly, report) = audit::synthesize_audited(circuit)?;
+        if !report.unconstrained.is_empty() {
+            return Err(Phase2Error::UnconstrainedVariables(report.unconstrained));
+        }
+
+        let params = MPCParameters::from_synthesized(
+            assembly,
+            phase1::MAX_EXP,
+            |exp| File::open(format!("phase1radix2m{}", exp)).map_err(Phase2Error::from),
+            Progress::new(&no_progress),
+        )?;
+
+        Ok((params, report))
+    }
+
+    /// Like `new()`, but reads the radix file from `source`.
+    pub fn new_from_source<C>(
+        circuit: C,
+        source: phase1::RadixSource,
+    ) -> Result<MPCParameters<E>, Phase2Error>
+    where
+        C: Circuit<E::Fr>,
+    {
+        MPCParameters::new_with_radix(
+            circuit,
+            phase1::MAX_EXP,
+            |exp| Ok(source.open(exp)?),
+            Progress::new(&no_progress),
+        )
+    }
+
+    /// Synthesizes the circuit and sizes its evaluation domain, then
+    /// builds the parameters from the radix file `open` returns for
+    /// that domain's exponent, which is at most `max_exp`.
+    fn new_with_radix<C, R, Err, F>(
+        circuit: C,
+        max_exp: u32,
+        open: F,
+        progress: Progress,
+    ) -> Result<MPCParameters<E>, Err>
+    where
+        C: Circuit<E::Fr>,
+        R: Read,
+        Err: From<SynthesisError>,
+        F: FnOnce(u32) -> Result<R, Err>,
+    {
+        let assembly = KeypairAssembly::synthesize(circuit)?;
+        MPCParameters::from_synthesized(assembly, max_exp, open, progress)
+    }
+
+    /// The rest of `new_with_radix`, for a circuit that's already
+    /// synthesized.
+    fn from_synthesized<R, Err, F>(
+        assembly: KeypairAssembly<E::Fr>,
+        max_exp: u32,
+        open: F,
+        progress: Progress,
+    ) -> Result<MPCParameters<E>, Err>
+    where
+        R: Read,
+        Err: From<SynthesisError>,
+        F: FnOnce(u32) -> Result<R, Err>,
+    {
+        let exp = domain_exp(&assembly, max_exp)?;
+        let f = open(exp)?;
+
+        Ok(MPCParameters::from_assembly(
+            assembly,
+            1 << exp,
+            f,
+            progress,
+        )?)
+    }
+
+    fn from_assembly<R: Read>(
+        assembly: KeypairAssembly<E::Fr>,
+        m: usize,
+        f: R,
+        progress: Progress,
+    ) -> Result<MPCParameters<E>, SynthesisError> {
+        let radix = Radix::read(f, m, progress)?;
+        let evaluation = QapEvaluation::new(assembly.num_inputs + assembly.num_aux);
+
+        MPCParameters::from_radix(assembly, radix, evaluation, progress, |_, _| Ok(()))
+    }
+
+    /// Finishes evaluating the QAP for the variables `evaluation`
+    /// doesn't have yet, a block at a time, calling `checkpoint` with
+    /// the range of variables of each block once it's done, then
+    /// builds the parameters.
+    fn from_radix<F>(
+        assembly: KeypairAssembly<E::Fr>,
+        radix: Radix<E>,
+        mut evaluation: QapEvaluation<E>,
+        progress: Progress,
+        mut checkpoint: F,
+    ) -> Result<MPCParameters<E>, SynthesisError>
+    where
+        F: FnMut(&QapEvaluation<E>, Range<usize>) -> io::Result<()>,
+    {
+        #[allow(clippy::too_many_arguments)]
+        fn eval<E: MPCEngine>(
+            // Lagrange coefficients for tau
+            coeffs_g1: Arc<Vec<E::G1Affine>>,
+            coeffs_g2: Arc<Vec<E::G2Affine>>,
+            alpha_coeffs_g1: Arc<Vec<E::G1Affine>>,
+            beta_coeffs_g1: Arc<Vec<E::G1Affine>>,
+
+            // QAP polynomials
+            at: &[Vec<(E::Fr, usize)>],
+            bt: &[Vec<(E::Fr, usize)>],
+            ct: &[Vec<(E::Fr, usize)>],
+
+            // Resulting evaluated QAP polynomials
+            a_g1: &mut [E::G1],
+            b_g1: &mut [E::G1],
+            b_g2: &mut [E::G2],
+            ext: &mut [E::G1],
+
+            // Worker
+            worker: &Worker,
+            tracker: &Tracker,
+        ) {
+            // Sanity check
+            assert_eq!(a_g1.len(), at.len());
+            assert_eq!(a_g1.len(), bt.len());
+            assert_eq!(a_g1.len(), ct.len());
+            assert_eq!(a_g1.len(), b_g1.len());
+            assert_eq!(a_g1.len(), b_g2.len());
+            assert_eq!(a_g1.len(), ext.len());
+
+            // Evaluate polynomials in multiple threads
+            worker.scope(a_g1.len(), |scope, chunk| {
+                for ((((((a_g1, b_g1), b_g2), ext), at), bt), ct) in a_g1
+                    .chunks_mut(chunk)
+                    .zip(b_g1.chunks_mut(chunk))
+                    .zip(b_g2.chunks_mut(chunk))
+                    .zip(ext.chunks_mut(chunk))
+                    .zip(at.chunks(chunk))
+                    .zip(bt.chunks(chunk))
+                    .zip(ct.chunks(chunk))
+                {
+                    let coeffs_g1 = coeffs_g1.clone();
+                    let coeffs_g2 = coeffs_g2.clone();
+                    let alpha_coeffs_g1 = alpha_coeffs_g1.clone();
+                    let beta_coeffs_g1 = beta_coeffs_g1.clone();
+
+                    scope.spawn(move |_| {
+                        for ((((((a_g1, b_g1), b_g2), ext), at), bt), ct) in a_g1
+                            .iter_mut()
+                            .zip(b_g1.iter_mut())
+                            .zip(b_g2.iter_mut())
+                            .zip(ext.iter_mut())
+                            .zip(at.iter())
+                            .zip(bt.iter())
+                            .zip(ct.iter())
+                        {
+                            for &(coeff, lag) in at {
+                                a_g1.add_assign(&coeffs_g1[lag].mul(coeff));
+                                ext.add_assign(&beta_coeffs_g1[lag].mul(coeff));
+                            }
+
+                            for &(coeff, lag) in bt {
+                                b_g1.add_assign(&coeffs_g1[lag].mul(coeff));
+                                b_g2.add_assign(&coeffs_g2[lag].mul(coeff));
+                                ext.add_assign(&alpha_coeffs_g1[lag].mul(coeff));
+                            }
+
+                            for &(coeff, lag) in ct {
+                                ext.add_assign(&coeffs_g1[lag].mul(coeff));
+                            }
+                        }
+
+                        // Batch normalize
+                        batch_normalization(a_g1);
+                        batch_normalization(b_g1);
+                        batch_normalization(b_g2);
+                        batch_normalization(ext);
+
+                        tracker.advance(a_g1.len());
+                    });
+                }
+            });
+        }
+
+        let circuit_fingerprint = assembly.fingerprint();
+        let worker = Worker::new();
+        let num_inputs = assembly.num_inputs;
+        let num_vars = num_inputs + assembly.num_aux;
+        let evaluating = Tracker::new(progress, Stage::EvaluateQap, num_vars);
+        if evaluation.done > 0 {
+            evaluating.advance(evaluation.done);
+        }
+
+        // Blocks never span both the inputs and the auxiliary
+        // variables, whose polynomials are kept apart
+        while evaluation.done < num_vars {
+            evaluating.check()?;
+
+            let start = evaluation.done;
+            let (end, offset) = if start < num_inputs {
+                (num_inputs.min(start + EVAL_BLOCK), 0)
+            } else {
+                (num_vars.min(start + EVAL_BLOCK), num_inputs)
+            };
+            let (at, bt, ct) = if start < num_inputs {
+                (
+                    &assembly.at_inputs,
+                    &assembly.bt_inputs,
+                    &assembly.ct_inputs,
+                )
+            } else {
+                (&assembly.at_aux, &assembly.bt_aux, &assembly.ct_aux)
+            };
+            let vars = start - offset..end - offset;
+
+            eval::<E>(
+                radix.coeffs_g1.clone(),
+                radix.coeffs_g2.clone(),
+                radix.alpha_coeffs_g1.clone(),
+                radix.beta_coeffs_g1.clone(),
+                &at[vars.clone()],
+                &bt[vars.clone()],
+                &ct[vars],
+                &mut evaluation.a_g1[start..end],
+                &mut evaluation.b_g1[start..end],
+                &mut evaluation.b_g2[start..end],
+                &mut evaluation.ext[start..end],
+                &worker,
+                &evaluating,
+            );
+
+            evaluation.done = end;
+            checkpoint(&evaluation, start..end)?;
+        }
+
+        // Don't allow any elements be unconstrained, so that
+        // the L query is always fully dense.
+        let l = evaluation.ext.split_off(assembly.num_inputs);
+        let ic = evaluation.ext;
+        for e in l.iter() {
+            if Into::<bool>::into(e.is_identity()) {
+                return Err(SynthesisError::UnconstrainedVariable);
+            }
+        }
+
+        let vk = VerifyingKey {
+            alpha_g1: radix.alpha,
+            beta_g1: radix.beta_g1,
+            beta_g2: radix.beta_g2,
+            gamma_g2: E::G2Affine::generator(),
+            delta_g1: E::G1Affine::generator(),
+            delta_g2: E::G2Affine::generator(),
+            ic: ic.into_iter().map(|e| e.to_affine()).collect(),
+        };
+
+        // The cs_hash covers the parameters exactly as they are
+        // serialized, so we feed each section to the hasher as soon
+        // as it's in its final form.
+        let mut cs_hasher = CsHasher::new();
+        cs_hasher.update_vk(&vk);
+        cs_hasher.update_query(&radix.h);
+
+        let l: Vec<_> = l.into_iter().map(|e| e.to_affine()).collect();
+        cs_hasher.update_query(&l);
+
+        // Filter points at infinity away from A/B queries
+        let a: Vec<_> = evaluation
+            .a_g1
+            .into_iter()
+            .filter(|e| !Into::<bool>::into(e.is_identity()))
+            .map(|e| e.to_affine())
+            .collect();
+        cs_hasher.update_query(&a);
+
+        let b_g1: Vec<_> = evaluation
+            .b_g1
+            .into_iter()
+            .filter(|e| !Into::<bool>::into(e.is_identity()))
+            .map(|e| e.to_affine())
+            .collect();
+        cs_hasher.update_query(&b_g1);
+
+        let b_g2: Vec<_> = evaluation
+            .b_g2
+            .into_iter()
+            .filter(|e| !Into::<bool>::into(e.is_identity()))
+            .map(|e| e.to_affine())
+            .collect();
+        cs_hasher.update_query(&b_g2);
+
+        let params = Parameters {
+            vk,
+            h: Arc::new(radix.h),
+            l: Arc::new(l),
+            a: Arc::new(a),
+            b_g1: Arc::new(b_g1),
+            b_g2: Arc::new(b_g2),
+        };
+
+        let cs_hash = cs_hasher.finalize();
+
+        Ok(MPCParameters {
+            params,
+            cs_hash,
+            contributions: vec![],
+            circuit_fingerprint: Some(circuit_fingerprint),
+            transcript_scheme: Arc::new(LegacyScheme),
+            #[cfg(feature = "attest")]
+            verifier_attestations: vec![],
+        })
+    }
+
+    /// Get the underlying Groth16 `Parameters`
+    pub fn get_params(&self) -> &Parameters<E> {
+        &self.params
+    }
+
+    /// Check a Groth16 proof against the verifying key of these
+    /// parameters. Returns whether the proof is valid for the given
+    /// public inputs, which must be as many as the circuit has. Fails
+    /// for parameters whose IC query is empty (see `check_ic_arity`).
+    pub fn verify_proof(
+        &self,
+        proof: &Proof<E>,
+        public_inputs: &[E::Fr],
+    ) -> Result<bool, Phase2Error> {
+        self.check_ic_arity()?;
+        let expected = self.params.vk.ic.len() - 1;
+        if public_inputs.len() != expected {
+            return Err(Phase2Error::PublicInputCount {
+                expected,
+                got: public_inputs.len(),
+            });
+        }
+
+        let pvk = prepare_verifying_key(&self.params.vk);
+
+        Ok(groth16::verify_proof(&pvk, proof, public_inputs).is_ok())
+    }
+
+    /// Prove `instance` with these parameters and check the proof, to
+    /// make sure the parameters work for their circuit before they
+    /// are published. `instance` is the circuit with a satisfying
+    /// witness, and `public_inputs` are its public inputs. A witness
+    /// that doesn't satisfy the circuit makes the proof fail to
+    /// verify, so the instance should be one that's known to be
+    /// valid.
+    pub fn self_test<C>(&self, instance: C, public_inputs: &[E::Fr]) -> Result<(), SelfTestError>
+    where
+        C: Circuit<E::Fr>,
+        E::Fr: PrimeFieldBits,
+    {
+        let expected = self
+            .params
+            .vk
+            .ic
+            .len()
+            .checked_sub(1)
+            .ok_or(SelfTestError::IcEmpty)?;
+        if public_inputs.len() != expected {
+            return Err(SelfTestError::PublicInputCount {
+                expected,
+                got: public_inputs.len(),
+            });
+        }
+
+        let proof = groth16::create_random_proof(instance, &self.params, &mut rand::thread_rng())
+            .map_err(SelfTestError::Proving)?;
+
+        let pvk = prepare_verifying_key(&self.params.vk);
+        groth16::verify_proof(&pvk, &proof, public_inputs).map_err(|_| SelfTestError::ProofRejected)
+    }
+
+    /// Get the hash of the circuit these parameters were created
+    /// for, which anchors the transcript of contributions.
+    pub fn cs_hash(&self) -> &[u8; 64] {
+        &self.cs_hash
+    }
+
+    /// The fingerprint of the circuit these parameters were created
+    /// for (see `circuit_fingerprint`), if it's known: parameters read
+    /// with a format that doesn't carry it don't have it.
+    pub fn circuit_fingerprint(&self) -> Option<&[u8; 32]> {
+        self.circuit_fingerprint.as_ref()
+    }
+
+    /// Use `scheme` to hash transcripts to G2, both when contributing
+    /// and when verifying. Its `SchemeId` is written in the header of
+    /// the parameters, so it only needs to be set when the ceremony
+    /// starts. Verifying against parameters takes the scheme of the
+    /// parameters before the contribution.
+    pub fn with_transcript_scheme(mut self, scheme: impl TranscriptScheme<E> + 'static) -> Self {
+        self.transcript_scheme = Arc::new(scheme);
+        self
+    }
+
+    /// The scheme these parameters hash transcripts to G2 with.
+    pub fn transcript_scheme(&self) -> &dyn TranscriptScheme<E> {
+        &*self.transcript_scheme
+    }
+
+    /// The contributions to these parameters, in order.
+    pub fn contributions(&self) -> impl ExactSizeIterator<Item = ContributionInfo<'_, E>> {
+        self.contributions
+            .iter()
+            .map(|pubkey| ContributionInfo { pubkey })
+    }
+
+    /// Get a commitment to the whole chain of contributions: the
+    /// hash of the `cs_hash` followed by every contribution's public
+    /// key. This is the transcript the next contribution signs, so it
+    /// changes with every contribution.
+    pub fn chain_commitment(&self) -> [u8; 64] {
+        let mut sink = HashWriter::new(io::sink());
+        sink.write_all(&self.cs_hash[..]).unwrap();
+        for pubkey in &self.contributions {
+            pubkey.write(&mut sink).unwrap();
+        }
+
+        sink.into_hash()
+    }
+
+    /// Export the transcript as JSON: the `cs_hash`, and for each
+    /// contribution its public key and hash. Points are the hex of
+    /// their compressed encoding, and hashes are hex. Like
+    /// `LogEntry::to_json`, the output has a fixed field order and
+    /// no whitespace, so the same parameters always give the same
+    /// bytes, which can be published and hashed.
+    #[cfg(feature = "serde")]
+    pub fn export_transcript_json(&self) -> String {
+        let contributions: Vec<String> = self
+            .contributions
+            .iter()
+            .map(|pubkey| {
+                format!(
+                    "{{\"delta_after\":\"{}\",\"s\":\"{}\",\"s_delta\":\"{}\",\"r_delta\":\"{}\",\"transcript\":\"{}\",\"contribution_hash\":\"{}\"}}",
+                    hex(pubkey.delta_after.to_bytes().as_ref()),
+                    hex(pubkey.s.to_bytes().as_ref()),
+                    hex(pubkey.s_delta.to_bytes().as_ref()),
+                    hex(pubkey.r_delta.to_bytes().as_ref()),
+                    hex(&pubkey.transcript),
+                    pubkey.hash()
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"cs_hash\":\"{}\",\"contributions\":[{}]}}",
+            hex(&self.cs_hash),
+            contributions.join(",")
+        )
+    }
+
+    /// Summarize the state of the ceremony as a `LogEntry`, stamped
+    /// with the current time.
+    pub fn to_log_entry(&self) -> LogEntry<E> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        LogEntry {
+            cs_hash: self.cs_hash,
+            chain_commitment: self.chain_commitment(),
+            contribution_count: self.contributions.len(),
+            final_delta: self.params.vk.delta_g1,
+            timestamp,
+        }
+    }
+
+    /// Get the running delta in G1 after each contribution, in order.
+    /// For valid parameters the last one is `vk.delta_g1`.
+    pub fn delta_chain(&self) -> Vec<E::G1Affine> {
+        self.contributions
+            .iter()
+            .map(|pubkey| pubkey.delta_after)
+            .collect()
+    }
+
+    /// Check that the final delta of these parameters is the expected
+    /// one, e.g. one published when the ceremony finished. This says
+    /// nothing about the rest of the parameters; see `verify`.
+    pub fn matches_final_delta(&self, expected_g1: E::G1Affine, expected_g2: E::G2Affine) -> bool {
+        self.params.vk.delta_g1 == expected_g1 && self.params.vk.delta_g2 == expected_g2
+    }
+
+    /// Serialize the public keys of contributions `range` as one
+    /// chunk of the transcript, so that the transcript of a long
+    /// ceremony can be archived across several files. See
+    /// `verify_transcript_chunked`. Fails if `range` is reversed or
+    /// runs past the last contribution.
+    pub fn write_transcript_chunk<W: Write>(
+        &self,
+        range: Range<usize>,
+        mut writer: W,
+    ) -> io::Result<()> {
+        if range.start > range.end || range.end > self.contributions.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "contributions {}..{} are out of range of {} contributions",
+                    range.start,
+                    range.end,
+                    self.contributions.len()
+                ),
+            ));
+        }
+
+        for pubkey in &self.contributions[range] {
+            pubkey.write(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Package the last contribution to these parameters, to send to
+    /// whoever has the parameters from before it. Returns `None` if
+    /// there are no contributions.
+    pub fn contribution_package(&self) -> Option<ContributionPackage<E>> {
+        let pubkey = self.contributions.last()?;
+
+        Some(ContributionPackage {
+            pubkey: pubkey.clone(),
+            delta_g1: self.params.vk.delta_g1,
+            delta_g2: self.params.vk.delta_g2,
+            h: self.params.h.to_vec(),
+            l: self.params.l.to_vec(),
+        })
+    }
+
+    /// Verify a packaged contribution to these parameters (see
+    /// `verify_package`) and, if it's valid, apply it, leaving the
+    /// parameters as the contributor's were. Returns the hash of the
+    /// contribution.
+    pub fn apply_package(
+        &mut self,
+        pkg: ContributionPackage<E>,
+    ) -> Result<ContributionHash, VerificationError> {
+        let hash = verify_package(self, &pkg)?;
+
+        self.params.vk.delta_g1 = pkg.delta_g1;
+        self.params.vk.delta_g2 = pkg.delta_g2;
+        self.params.h = Arc::new(pkg.h);
+        self.params.l = Arc::new(pkg.l);
+        self.contributions.push(pkg.pubkey);
+
+        Ok(hash)
+    }
+
+    /// Serialize the public keys of all of the contributions with
+    /// compressed points, to be checked with
+    /// `verify_transcript_compressed`.
+    pub fn write_transcript_compressed<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for pubkey in &self.contributions {
+            pubkey.write_compressed(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Serialize the immutable base of these parameters, i.e. with
+    /// delta reset to the generators and without contributions. The
+    /// H and L queries are stripped too, as they are scaled by every
+    /// contribution's delta and can't be recovered. Anyone can
+    /// rebuild the same bytes from the circuit using `new` to check
+    /// the fields that contributions never change.
+    pub fn base_params_bytes(&self) -> Vec<u8> {
+        let mut params = self.params.clone();
+        params.vk.delta_g1 = E::G1Affine::generator();
+        params.vk.delta_g2 = E::G2Affine::generator();
+        params.h = Arc::new(vec![]);
+        params.l = Arc::new(vec![]);
+
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Compute a digest of the immutable base of these parameters
+    /// (see `base_params_bytes`), which together with the `cs_hash`
+    /// identifies the circuit they were created for. This is the
+    /// first 32 bytes of a BLAKE2b hash of the base.
+    pub fn structural_digest(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::new(64);
+        hasher.update(&self.base_params_bytes());
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize().as_ref()[..32]);
+        digest
+    }
+
+    /// Compute a short fingerprint of these parameters, suitable
+    /// for publishing alongside a parameter file. This is the first
+    /// 32 bytes of a BLAKE2b hash of the serialized parameters.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let h = {
+            let sink = io::sink();
+            let mut sink = HashWriter::new(sink);
+
+            self.write(&mut sink).unwrap();
+
+            sink.into_hash()
+        };
+
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&h[..32]);
+        fingerprint
+    }
+
+    /// Check that these parameters match a published fingerprint
+    /// (see `fingerprint()`). The comparison is constant-time.
+    pub fn matches_fingerprint(&self, expected: &[u8; 32]) -> bool {
+        self.fingerprint()[..].ct_eq(&expected[..]).into()
+    }
+
+    /// Produce a short attestation of contribution `index`, small
+    /// enough to fit in a QR code, which can be checked against the
+    /// published transcript. It is the unpadded base64url encoding
+    /// of the first 8 bytes of the `cs_hash`, the contribution's
+    /// hash and its delta (compressed, in G1).
+    pub fn contribution_attestation(&self, index: usize) -> Option<String> {
+        let pubkey = self.contributions.get(index)?;
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.cs_hash[..8]);
+        bytes.extend_from_slice(&pubkey.hash().0[..]);
+        bytes.extend_from_slice(pubkey.delta_after.to_bytes().as_ref());
+
+        Some(base64url(&bytes))
+    }
+
+    /// The number of exponentiations `contribute` will perform,
+    /// one per element of the H and L queries. Useful for showing
+    /// progress and estimating how long a contribution will take.
+    pub fn contribution_work_units(&self) -> usize {
+        self.params.l.len() + self.params.h.len()
+    }
+
+    /// Estimate the cost of checking these parameters with
+    /// `verify_chain`, not counting the synthesis and QAP evaluation
+    /// `verify_structure` needs to rebuild the initial parameters.
+    pub fn estimate_verify_cost(&self) -> VerifyCostEstimate {
+        VerifyCostEstimate {
+            // Two ratio checks per contribution, one for delta in G2
+            // and one each for H and L, at two pairings per check
+            pairings: 4 * self.contributions.len() + 6,
+            h_query_len: self.params.h.len(),
+            l_query_len: self.params.l.len(),
+            merge_pairs_points: 2 * (self.params.h.len() + self.params.l.len()),
+        }
+    }
+
+    /// Contributes some randomness to the parameters. Only one
+    /// contributor needs to be honest for the parameters to be
+    /// secure.
+    ///
+    /// This function returns a "hash" that is bound to the
+    /// contribution. Contributors can use this hash to make
+    /// sure their contribution is in the final parameters, by
+    /// checking to see if it appears in the output of
+    /// `MPCParameters::verify`.
+    pub fn contribute<R: Rng>(&mut self, rng: &mut R) -> [u8; 64] {
+        self.contribute_with_progress(rng, no_progress)
+    }
+
+    /// Like `contribute`, calling `progress` as the H and L queries
+    /// are updated (see `Stage`).
+    pub fn contribute_with_progress<R, P>(&mut self, rng: &mut R, progress: P) -> [u8; 64]
+    where
+        R: Rng,
+        P: Fn(Stage, usize, usize) + Sync,
+    {
+        // Generate a keypair
+        let (pubkey, privkey) = keypair(
+            rng,
+            &*self.transcript_scheme,
+            &self.cs_hash,
+            &self.contributions,
+            self.params.vk.delta_g1,
+        );
+
+        self.apply_keypair(pubkey, &privkey, Progress::new(&progress))
+    }
+
+    /// Like `contribute`, giving up with `Phase2Error::Cancelled` soon
+    /// after `cancel` is cancelled, in which case the parameters are
+    /// left as they were. The queries are updated on a copy, so this
+    /// needs twice the memory of `contribute`.
+    pub fn contribute_cancellable<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        cancel: &CancellationToken,
+    ) -> Result<[u8; 64], Phase2Error> {
+        let (pubkey, privkey) = keypair(
+            rng,
+            &*self.transcript_scheme,
+            &self.cs_hash,
+            &self.contributions,
+            self.params.vk.delta_g1,
+        );
+
+        self.try_apply_keypair(pubkey, &privkey, Progress::cancellable(cancel))
+            .map_err(|_| Phase2Error::Cancelled)
+    }
+
+    /// Updates the parameters with the delta of `privkey`, and adds
+    /// `pubkey` to the contributions.
+    fn apply_keypair(
+        &mut self,
+        pubkey: PublicKey<E>,
+        privkey: &PrivateKey<E>,
+        progress: Progress,
+    ) -> [u8; 64] {
+        self.try_apply_keypair(pubkey, privkey, progress)
+            .expect("only cancelling fails")
+    }
+
+    /// Like `apply_keypair`, leaving the parameters as they were if
+    /// `progress` is cancelled.
+    fn try_apply_keypair(
+        &mut self,
+        pubkey: PublicKey<E>,
+        privkey: &PrivateKey<E>,
+        progress: Progress,
+    ) -> io::Result<[u8; 64]> {
+        let mut delta_inv = privkey.delta.invert().expect("nonzero");
+        let tracker = Tracker::new(
+            progress,
+            Stage::Contribute,
+            self.params.l.len() + self.params.h.len(),
+        );
+
+        // Update the queries in place, unless they're shared with a
+        // clone of the parameters, a chunk at a time so that
+        // `batch_exp` never needs more than a chunk of scratch space.
+        // If the update can be cancelled, it's made on a copy instead.
+        let (mut l, mut h) = match progress.cancel {
+            Some(_) => (self.params.l.clone(), self.params.h.clone()),
+            None => (
+                std::mem::take(&mut self.params.l),
+                std::mem::take(&mut self.params.h),
+            ),
+        };
+        for query in [&mut l, &mut h] {
+            for chunk in Arc::make_mut(query).chunks_mut(CONTRIBUTE_CHUNK) {
+                if let Err(e) = tracker.check() {
+                    delta_inv.zeroize();
+                    return Err(e);
+                }
+                batch_exp::<E>(chunk, delta_inv, &tracker);
+            }
+        }
+        self.params.l = l;
+        self.params.h = h;
+
+        delta_inv.zeroize();
+
+        self.params.vk.delta_g1 = self.params.vk.delta_g1.mul(privkey.delta).to_affine();
+        self.params.vk.delta_g2 = self.params.vk.delta_g2.mul(privkey.delta).to_affine();
+
+        self.contributions.push(pubkey.clone());
+
+        // Calculate the hash of the public key and return it
+        Ok({
+            let sink = io::sink();
+            let mut sink = HashWriter::new(sink);
+            pubkey.write(&mut sink).unwrap();
+            let h = sink.into_hash();
+            let mut response = [0u8; 64];
+            response.copy_from_slice(h.as_ref());
+            response
+        })
+    }
+
+    /// Contributes randomness derived deterministically from a
+    /// 32-byte `seed`, e.g. one generated offline. The seed is
+    /// expanded with a domain-separated BLAKE2b hash into the seed
+    /// of a ChaCha RNG, which is then used as in `contribute` (see
+    /// `SEED_KDF`). The same seed on the same parameters always
+    /// yields the same contribution.
+    ///
+    /// Anyone who learns the seed learns the contribution's secret,
+    /// so it must be destroyed just like the randomness used by
+    /// `contribute`.
+    pub fn contribute_with_seed(&mut self, seed: [u8; 32]) -> [u8; 64] {
+        let mut rng = ChaChaRng::from_seed(expand_seed(&seed));
+        self.contribute(&mut rng)
+    }
+
+    /// Like `contribute_with_seed`, wiping the secrets from memory as
+    /// soon as they're no longer needed: `seed` itself, once it's
+    /// expanded, and the RNG, once delta is sampled. The delta is
+    /// wiped by `contribute` either way.
+    ///
+    /// This is best effort: copies the compiler makes when moving
+    /// values, or that bellman and the curve arithmetic make
+    /// internally, aren't reached.
+    pub fn contribute_and_wipe(&mut self, seed: &mut [u8; 32]) -> [u8; 64] {
+        let mut expanded = expand_seed(seed);
+        seed.zeroize();
+
+        let mut rng = ChaChaRng::from_seed(expanded);
+        expanded.zeroize();
+
+        let hash = self.contribute(&mut rng);
+        // The RNG can't be zeroized, so it's replaced, in a way the
+        // compiler has to assume is observed
+        rng = ChaChaRng::from_seed([0; 32]);
+        std::hint::black_box(&mut rng);
+
+        hash
+    }
+
+    /// Contributes randomness derived from a public random beacon,
+    /// as ceremonies do after the last participant so that nobody
+    /// gets the final say over the parameters. The delta is derived
+    /// from `beacon_hash` by iterated hashing (see `verify_beacon`),
+    /// with more `iterations` making it harder to grind the beacon.
+    pub fn contribute_beacon(&mut self, beacon_hash: &[u8], iterations: u64) -> [u8; 64] {
+        let mut rng = ChaChaRng::from_seed(beacon_seed(beacon_hash, iterations));
+        self.contribute(&mut rng)
+    }
+
+    /// Check that contribution `index` is the one `contribute_beacon`
+    /// makes for `beacon_hash` and `iterations`, by replaying it on
+    /// top of the contributions before it. This is meant to be used
+    /// alongside `verify`, which checks the chain itself.
+    pub fn verify_beacon(
+        &self,
+        index: usize,
+        beacon_hash: &[u8],
+        iterations: u64,
+    ) -> Result<(), VerificationError> {
+        let pubkey = self
+            .contributions
+            .get(index)
+            .ok_or(VerificationError::NotBeaconDerived { index })?;
+        let delta_before = match index {
+            0 => E::G1Affine::generator(),
+            _ => self.contributions[index - 1].delta_after,
+        };
+
+        let mut rng = ChaChaRng::from_seed(beacon_seed(beacon_hash, iterations));
+        let (expected, _) = keypair(
+            &mut rng,
+            &*self.transcript_scheme,
+            &self.cs_hash,
+            &self.contributions[..index],
+            delta_before,
+        );
+
+        if *pubkey != expected {
+            return Err(VerificationError::NotBeaconDerived { index });
+        }
+
+        Ok(())
+    }
+
+    /// Verify the correctness of the parameters, given a circuit
+    /// instance. This will return all of the hashes that
+    /// contributors obtained when they ran
+    /// `MPCParameters::contribute`, for ensuring that contributions
+    /// exist in the final parameters. On failure, the error says
+    /// which check failed.
+    #[cfg(feature = "fs")]
+    pub fn verify<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        self.verify_with_progress(circuit, no_progress)
+    }
+
+    /// Like `verify`, calling `progress` as the initial parameters
+    /// are created and as H and L are checked (see `Stage`).
+    #[cfg(feature = "fs")]
+    pub fn verify_with_progress<C, P>(
+        &self,
+        circuit: C,
+        progress: P,
+    ) -> Result<Vec<ContributionHash>, VerificationError>
+    where
+        C: Circuit<E::Fr>,
+        P: Fn(Stage, usize, usize) + Sync,
+    {
+        let progress = Progress::new(&progress);
+        let token = self.structure_token(circuit, progress)?;
+        self.check_chain(&token, progress, &Worker::new(), None)
+    }
+
+    /// Like `verify`, with the random scalars that H and L are merged
+    /// with (see `merge_pairs`) derived from a seed drawn from `rng`,
+    /// instead of `thread_rng`. Verifying again with an `rng` in the
+    /// same state repeats exactly the same checks, on any machine
+    /// and with any number of threads (see `Config`), so an audit can
+    /// replay a verification.
+    #[cfg(feature = "fs")]
+    pub fn verify_with_rng<C, R>(
+        &self,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<Vec<ContributionHash>, VerificationError>
+    where
+        C: Circuit<E::Fr>,
+        R: Rng,
+    {
+        let progress = Progress::new(&no_progress);
+        let token = self.structure_token(circuit, progress)?;
+        self.check_chain(&token, progress, &Worker::new(), Some(rng.gen()))
+    }
+
+    /// Like `verify`, giving up with `VerificationError::Cancelled`
+    /// soon after `cancel` is cancelled.
+    #[cfg(feature = "fs")]
+    pub fn verify_cancellable<C>(
+        &self,
+        circuit: C,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<ContributionHash>, VerificationError>
+    where
+        C: Circuit<E::Fr>,
+    {
+        let progress = Progress::cancellable(cancel);
+        let result = self
+            .structure_token(circuit, progress)
+            .and_then(|token| self.check_chain(&token, progress, &Worker::new(), None));
+        match result {
+            Err(_) if cancel.is_cancelled() => Err(VerificationError::Cancelled),
+            result => result,
+        }
+    }
+
+    /// Like `verify`, given the `initial` parameters of the circuit
+    /// (as made by `new`) instead of the circuit itself. Making them
+    /// is by far the most expensive part of verifying, so a
+    /// coordinator can make them once, keep them around with `write`
+    /// and `read`, and verify every contribution against them.
+    pub fn verify_with_initial(
+        &self,
+        initial: &MPCParameters<E>,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        let token = StructureToken {
+            params: initial.params.clone(),
+            cs_hash: initial.cs_hash,
+        };
+        self.check_chain(&token, Progress::new(&no_progress), &Worker::new(), None)
+    }
+
+    /// Like `verify`, checking the contributions, H and L on the
+    /// threads of `worker`. The worker runs on the current rayon
+    /// pool, so verifying from within a pool's `install` limits how
+    /// many threads are used.
+    #[cfg(feature = "fs")]
+    pub fn verify_with_worker<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+        worker: &Worker,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        let token = self.structure_token(circuit, Progress::new(&no_progress))?;
+        self.check_chain(&token, Progress::new(&no_progress), worker, None)
+    }
+
+    /// Verify the parameters (see `verify`) and check that they
+    /// contain exactly the `expected` contributions, in order. This
+    /// lets a coordinator enforce a known roster of contributors.
+    #[cfg(feature = "fs")]
+    pub fn verify_expected<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+        expected: &[ContributionHash],
+    ) -> Result<(), VerificationError> {
+        let actual = self.verify_chain(&self.verify_structure(circuit)?)?;
+
+        if actual[..] != expected[..] {
+            return Err(VerificationError::ContributionSetMismatch {
+                expected: expected.to_vec(),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The first, circuit-dependent half of `verify`: synthesizes
+    /// the circuit and checks that none of the fields contributions
+    /// can't change have changed. The returned token can be used to
+    /// verify the chain of contributions with `verify_chain` as
+    /// many times as needed, without synthesizing the circuit again.
+    #[cfg(feature = "fs")]
+    pub fn verify_structure<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<StructureToken<E>, VerificationError> {
+        self.structure_token(circuit, Progress::new(&no_progress))
+    }
+
+    #[cfg(feature = "fs")]
+    fn structure_token<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+        progress: Progress,
+    ) -> Result<StructureToken<E>, VerificationError> {
+        let initial_params = MPCParameters::new_in_cwd(circuit, progress)?;
+        if self.circuit_fingerprint.is_some()
+            && self.circuit_fingerprint != initial_params.circuit_fingerprint
+        {
+            return Err(VerificationError::CircuitFingerprintMismatch);
+        }
 
-    let s = Arc::new(Mutex::new(G::Curve::identity()));
-    let sx = Arc::new(Mutex::new(G::Curve::identity()));
+        let token = StructureToken {
+            params: initial_params.params,
+            cs_hash: initial_params.cs_hash,
+        };
 
-    crossbeam::scope(|scope| {
-        for (v1, v2) in v1.chunks(chunk).zip(v2.chunks(chunk)) {
-            let s = s.clone();
-            let sx = sx.clone();
+        self.check_structure(&token)?;
 
-            scope.spawn(move || {
-                // We do not need to be overly cautious of the RNG
-                // used for this check.
-                let rng = &mut thread_rng();
+        Ok(token)
+    }
 
-                let mut wnaf = Wnaf::new();
-                let mut local_s = G::Curve::identity();
-                let mut local_sx = G::Curve::identity();
+    /// The second half of `verify`: checks the chain of
+    /// contributions and that H and L were updated consistently
+    /// with delta, against a token from `verify_structure`. The
+    /// (cheap) structural comparison is repeated, so the token can
+    /// be reused as the parameters gain more contributions.
+    pub fn verify_chain(
+        &self,
+        token: &StructureToken<E>,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        self.check_chain(token, Progress::new(&no_progress), &Worker::new(), None)
+    }
 
-                for (v1, v2) in v1.iter().zip(v2.iter()) {
-                    let rho = G::Scalar::random(&mut *rng);
-                    let mut wnaf = wnaf.scalar(&rho);
-                    let v1 = wnaf.base(v1.to_curve());
-                    let v2 = wnaf.base(v2.to_curve());
+    /// The checks of `verify` after the structure, merging H and L
+    /// with scalars derived from `seed` if there's one (see
+    /// `verify_with_rng`), or from `thread_rng` otherwise.
+    fn check_chain(
+        &self,
+        token: &StructureToken<E>,
+        progress: Progress,
+        worker: &Worker,
+        seed: Option<[u8; 32]>,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        self.check_structure(token)?;
+
+        let (h_scalars, l_scalars) = match seed {
+            Some(seed) => {
+                let mut rng = ChaChaRng::from_seed(seed);
+                (
+                    MergeScalars::Seeded(rng.gen()),
+                    MergeScalars::Seeded(rng.gen()),
+                )
+            }
+            None => (MergeScalars::Random, MergeScalars::Random),
+        };
 
-                    local_s.add_assign(&v1);
-                    local_sx.add_assign(&v2);
-                }
+        let tracker = Tracker::new(
+            progress,
+            Stage::Verify,
+            self.params.h.len() + self.params.l.len(),
+        );
 
-                s.lock().unwrap().add_assign(&local_s);
-                sx.lock().unwrap().add_assign(&local_sx);
+        // The contributions, H and L are independent of each other,
+        // so they're checked at the same time
+        let (mut hashes, mut h, mut l) = (Ok(vec![]), None, None);
+        worker.scope(0, |scope, _| {
+            scope.spawn(|_| {
+                hashes = check_pubkeys(
+                    &*self.transcript_scheme,
+                    &token.cs_hash,
+                    &self.contributions,
+                    worker,
+                );
             });
+            scope.spawn(|_| {
+                h = Some(merge_pairs_on(
+                    worker,
+                    &token.params.h,
+                    &self.params.h,
+                    &tracker,
+                    h_scalars,
+                ));
+            });
+            scope.spawn(|_| {
+                l = Some(merge_pairs_on(
+                    worker,
+                    &token.params.l,
+                    &self.params.l,
+                    &tracker,
+                    l_scalars,
+                ));
+            });
+        });
+        if progress.cancelled() {
+            return Err(VerificationError::Cancelled);
         }
-    });
+        let hashes = hashes?;
 
-    let s = s.lock().unwrap().to_affine();
-    let sx = sx.lock().unwrap().to_affine();
+        let current_delta = self
+            .contributions
+            .last()
+            .map_or(E::G1Affine::generator(), |pubkey| pubkey.delta_after);
 
-    (s, sx)
-}
+        // Current parameters should have consistent delta in G1
+        if current_delta != self.params.vk.delta_g1 {
+            return Err(VerificationError::DeltaG1Mismatch);
+        }
 
-/// This needs to be destroyed by at least one participant
-/// for the final parameters to be secure.
-struct PrivateKey {
-    delta: bls12_381::Scalar,
-}
+        let g2 = E::G2Prepared::from(E::G2Affine::generator());
+        let delta_g2 = E::G2Prepared::from(self.params.vk.delta_g2);
 
-/// Compute a keypair, given the current parameters. Keypairs
-/// cannot be reused for multiple contributions or contributions
-/// in different parameters.
-fn keypair<R: Rng>(rng: &mut R, current: &MPCParameters) -> (PublicKey, PrivateKey) {
-    // Sample random delta
-    let delta: bls12_381::Scalar = bls12_381::Scalar::random(&mut *rng);
+        // Current parameters should have consistent delta in G2
+        if !same_ratio_prepared::<E>((E::G1Affine::generator(), current_delta), (&g2, &delta_g2)) {
+            return Err(VerificationError::DeltaG2Mismatch);
+        }
 
-    // Compute delta s-pair in G1
-    let s = bls12_381::G1Projective::random(rng).to_affine();
-    let s_delta = s.mul(delta).to_affine();
+        self.check_net_delta()?;
 
-    // H(cs_hash | <previous pubkeys> | s | s_delta)
-    let h = {
-        let sink = io::sink();
-        let mut sink = HashWriter::new(sink);
+        // H and L queries should be updated with delta^-1
+        if !same_ratio_prepared::<E>(h.unwrap(), (&delta_g2, &g2)) {
+            return Err(VerificationError::HQueryRatioMismatch);
+        }
+
+        if !same_ratio_prepared::<E>(l.unwrap(), (&delta_g2, &g2)) {
+            return Err(VerificationError::LQueryRatioMismatch);
+        }
+
+        Ok(hashes)
+    }
+
+    /// Verify the parameters like `verify`, but check all of the
+    /// pairing equations at once, with one multi-Miller loop and a
+    /// single final exponentiation.
+    ///
+    /// Every check of the form e(a, d) = e(b, c) is turned into
+    /// e(rho * a, d) * e(-rho * b, c) = 1 for an independent random
+    /// scalar rho, and all of these products are multiplied
+    /// together. If any single equation doesn't hold, the product
+    /// is 1 only if the rhos happen to cancel its error out, which
+    /// (as the target group has prime order r) happens with
+    /// probability at most 1/r.
+    #[cfg(feature = "fs")]
+    pub fn verify_aggregate<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        let token = self.verify_structure(circuit)?;
+
+        let g1 = E::G1Affine::generator();
+        let g2 = E::G2Affine::generator();
+
+        let rng = &mut rand::thread_rng();
+        let mut terms = vec![];
+        let mut equation = |a: E::G1Affine, b: E::G1Affine, c: E::G2Affine, d: E::G2Affine| {
+            let rho = E::Fr::random(&mut *rng);
+            terms.push(((a * rho).to_affine(), E::G2Prepared::from(d)));
+            terms.push(((-(b * rho)).to_affine(), E::G2Prepared::from(c)));
+        };
 
-        sink.write_all(&current.cs_hash[..]).unwrap();
-        for pubkey in &current.contributions {
+        let mut sink = HashWriter::new(io::sink());
+        sink.write_all(&token.cs_hash[..]).unwrap();
+
+        let mut current_delta = g1;
+        let mut hashes = vec![];
+
+        for (index, pubkey) in self.contributions.iter().enumerate() {
+            // The signature of knowledge and the change from the old
+            // delta, as equations
+            check_pubkey_with(
+                &*self.transcript_scheme,
+                &sink,
+                index,
+                current_delta,
+                pubkey,
+                |(a, b), (c, d)| {
+                    equation(a, b, c, d);
+                    true
+                },
+            )?;
             pubkey.write(&mut sink).unwrap();
+
+            current_delta = pubkey.delta_after;
+            hashes.push(pubkey.hash());
         }
-        sink.write_all(s.to_uncompressed().as_ref()).unwrap();
-        sink.write_all(s_delta.to_uncompressed().as_ref()).unwrap();
 
-        sink.into_hash()
-    };
+        // Current parameters should have consistent delta in G1
+        if current_delta != self.params.vk.delta_g1 {
+            return Err(VerificationError::DeltaG1Mismatch);
+        }
 
-    // This avoids making a weird assumption about the hash into the
-    // group.
-    let mut transcript = [0; 64];
-    transcript.copy_from_slice(h.as_ref());
-
-    // Compute delta s-pair in G2
-    let r = hash_to_g2(h.as_ref()).to_affine();
-    let r_delta = r.mul(delta).to_affine();
-
-    (
-        PublicKey {
-            delta_after: current.params.vk.delta_g1.mul(delta).to_affine(),
-            s: s,
-            s_delta: s_delta,
-            r_delta: r_delta,
-            transcript: transcript,
-        },
-        PrivateKey { delta: delta },
-    )
-}
+        self.check_net_delta()?;
 
-fn batch_normalization<C: group::Curve>(proj: &mut [C])
-where
-    C::AffineRepr: Clone + Into<C>,
-{
-    let mut affines = vec![C::identity().to_affine(); proj.len()];
-    C::batch_normalize(&*proj, &mut affines);
-    proj.iter_mut().zip(affines.iter()).for_each(|(a, b)| {
-        *a = b.clone().into();
-    });
-}
+        // Delta in G2 should be consistent with delta in G1
+        equation(g1, current_delta, g2, self.params.vk.delta_g2);
 
-impl MPCParameters {
-    /// Create new Groth16 parameters (compatible with bellman) for a
-    /// given circuit. The resulting parameters are unsafe to use
-    /// until there are contributions (see `contribute()`).
-    pub fn new<C>(circuit: C) -> Result<MPCParameters, SynthesisError>
-    where
-        C: Circuit<bls12_381::Scalar>,
-    {
-        let mut assembly = KeypairAssembly {
-            num_inputs: 0,
-            num_aux: 0,
-            num_constraints: 0,
-            at_inputs: vec![],
-            bt_inputs: vec![],
-            ct_inputs: vec![],
-            at_aux: vec![],
-            bt_aux: vec![],
-            ct_aux: vec![],
-        };
+        // H and L queries should be updated with delta^-1
+        let (h, h_delta) = merge_pairs(&token.params.h, &self.params.h);
+        equation(h, h_delta, self.params.vk.delta_g2, g2);
+        let (l, l_delta) = merge_pairs(&token.params.l, &self.params.l);
+        equation(l, l_delta, self.params.vk.delta_g2, g2);
 
-        // Allocate the "one" input variable
-        assembly.alloc_input(|| "", || Ok(bls12_381::Scalar::ONE))?;
+        let terms: Vec<_> = terms.iter().map(|(a, b)| (a, b)).collect();
+        let result = E::multi_miller_loop(&terms).final_exponentiation();
 
-        // Synthesize the circuit.
-        circuit.synthesize(&mut assembly)?;
+        if !bool::from(result.is_identity()) {
+            return Err(VerificationError::AggregateCheckFailed);
+        }
 
-        // Input constraints to ensure full density of IC query
-        // x * 0 = 0
-        for i in 0..assembly.num_inputs {
-            assembly.enforce(
-                || "",
-                |lc| lc + Variable::new_unchecked(Index::Input(i)),
-                |lc| lc,
-                |lc| lc,
-            );
+        Ok(hashes)
+    }
+
+    /// Check the chain of contributions against a trusted circuit
+    /// identity (its `cs_hash` and `structural_digest`) instead of
+    /// synthesizing the circuit: the identity, the chain of
+    /// contributions and that delta is consistent with it.
+    ///
+    /// This doesn't verify the parameters: the H and L queries can't
+    /// be checked this way, as that needs the queries of the initial
+    /// parameters, so parameters with garbage in them pass. Use
+    /// `verify_chain` with a cached `StructureToken` before proving
+    /// with them.
+    pub fn verify_chain_by_id(
+        &self,
+        cs_hash: [u8; 64],
+        structural_digest: [u8; 32],
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        if cs_hash[..] != self.cs_hash[..] {
+            return Err(VerificationError::CsHashMismatch);
+        }
+        if structural_digest != self.structural_digest() {
+            return Err(VerificationError::StructuralDigestMismatch);
         }
 
-        // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < assembly.num_constraints {
-            m *= 2;
-            exp += 1;
+        let mut chain = ChainVerifier::<E>::new(&self.cs_hash, &*self.transcript_scheme);
+        for pubkey in &self.contributions {
+            chain.push(pubkey)?;
+        }
 
-            // Powers of Tau ceremony can't support more than 2^21
-            if exp > 21 {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
+        // Current parameters should have consistent delta in G1
+        if chain.current_delta != self.params.vk.delta_g1 {
+            return Err(VerificationError::DeltaG1Mismatch);
         }
 
-        // Try to load "phase1radix2m{}"
-        let f = match File::open(format!("phase1radix2m{}", exp)) {
-            Ok(f) => f,
-            Err(e) => {
-                panic!("Couldn't load phase1radix2m{}: {:?}", exp, e);
-            }
-        };
-        let f = &mut BufReader::with_capacity(1024 * 1024, f);
+        // Current parameters should have consistent delta in G2
+        if !same_ratio(
+            (E::G1Affine::generator(), chain.current_delta),
+            (E::G2Affine::generator(), self.params.vk.delta_g2),
+        ) {
+            return Err(VerificationError::DeltaG2Mismatch);
+        }
 
-        let read_g1 = |reader: &mut BufReader<File>| -> io::Result<bls12_381::G1Affine> {
-            let mut repr = <bls12_381::G1Affine as UncompressedEncoding>::Uncompressed::default();
-            reader.read_exact(repr.as_mut())?;
+        self.check_net_delta()?;
 
-            Option::from(
-                <bls12_381::G1Affine as UncompressedEncoding>::from_uncompressed_unchecked(&repr),
-            )
-            .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))
-            .and_then(|e: bls12_381::G1Affine| {
-                if e.is_identity().into() {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "point at infinity",
-                    ))
-                } else {
-                    Ok(e)
-                }
-            })
-        };
+        Ok(chain.hashes)
+    }
 
-        let read_g2 = |reader: &mut BufReader<File>| -> io::Result<bls12_381::G2Affine> {
-            let mut repr = <bls12_381::G2Affine as UncompressedEncoding>::Uncompressed::default();
-            reader.read_exact(repr.as_mut())?;
+    /// Checks the fields that contributions don't change against
+    /// the initial parameters in `token`.
+    fn check_structure(&self, token: &StructureToken<E>) -> Result<(), VerificationError> {
+        let initial = &token.params;
 
-            Option::from(
-                <bls12_381::G2Affine as UncompressedEncoding>::from_uncompressed_unchecked(&repr),
-            )
-            .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid data"))
-            .and_then(|e: bls12_381::G2Affine| {
-                if e.is_identity().into() {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "point at infinity",
-                    ))
-                } else {
-                    Ok(e)
-                }
-            })
-        };
+        // The chain of contributions is checked starting from the
+        // generators, so the initial parameters must start there too
+        if initial.vk.delta_g1 != E::G1Affine::generator()
+            || initial.vk.delta_g2 != E::G2Affine::generator()
+        {
+            return Err(VerificationError::BaseDeltaNotGenerator);
+        }
 
-        let alpha = read_g1(f)?;
-        let beta_g1 = read_g1(f)?;
-        let beta_g2 = read_g2(f)?;
+        // H/L will change, but should have same length
+        if initial.h.len() != self.params.h.len() {
+            return Err(VerificationError::HQueryLengthMismatch);
+        }
+        if initial.l.len() != self.params.l.len() {
+            return Err(VerificationError::LQueryLengthMismatch);
+        }
 
-        let mut coeffs_g1 = Vec::with_capacity(m);
-        for _ in 0..m {
-            coeffs_g1.push(read_g1(f)?);
+        // A/B_G1/B_G2 doesn't change at all
+        if initial.a != self.params.a {
+            return Err(VerificationError::AQueryMismatch);
+        }
+        if initial.b_g1 != self.params.b_g1 {
+            return Err(VerificationError::BG1QueryMismatch);
+        }
+        if initial.b_g2 != self.params.b_g2 {
+            return Err(VerificationError::BG2QueryMismatch);
         }
 
-        let mut coeffs_g2 = Vec::with_capacity(m);
-        for _ in 0..m {
-            coeffs_g2.push(read_g2(f)?);
+        // alpha/beta/gamma don't change
+        if initial.vk.alpha_g1 != self.params.vk.alpha_g1 {
+            return Err(VerificationError::AlphaG1Mismatch);
+        }
+        if initial.vk.beta_g1 != self.params.vk.beta_g1 {
+            return Err(VerificationError::BetaG1Mismatch);
+        }
+        if initial.vk.beta_g2 != self.params.vk.beta_g2 {
+            return Err(VerificationError::BetaG2Mismatch);
+        }
+        if initial.vk.gamma_g2 != self.params.vk.gamma_g2 {
+            return Err(VerificationError::GammaG2Mismatch);
         }
 
-        let mut alpha_coeffs_g1 = Vec::with_capacity(m);
-        for _ in 0..m {
-            alpha_coeffs_g1.push(read_g1(f)?);
+        // IC shouldn't change, as gamma doesn't change
+        if initial.vk.ic != self.params.vk.ic {
+            return Err(VerificationError::IcMismatch);
         }
 
-        let mut beta_coeffs_g1 = Vec::with_capacity(m);
-        for _ in 0..m {
-            beta_coeffs_g1.push(read_g1(f)?);
+        // cs_hash should be the same
+        if token.cs_hash[..] != self.cs_hash[..] {
+            return Err(VerificationError::CsHashMismatch);
         }
 
-        // These are `Arc` so that later it'll be easier
-        // to use multiexp during QAP evaluation (which
-        // requires a futures-based API)
-        let coeffs_g1 = Arc::new(coeffs_g1);
-        let coeffs_g2 = Arc::new(coeffs_g2);
-        let alpha_coeffs_g1 = Arc::new(alpha_coeffs_g1);
-        let beta_coeffs_g1 = Arc::new(beta_coeffs_g1);
+        Ok(())
+    }
 
-        let mut h = Vec::with_capacity(m - 1);
-        for _ in 0..(m - 1) {
-            h.push(read_g1(f)?);
+    /// Serialize these parameters, after a header recording the curve,
+    /// the layout, the start of the `cs_hash` and the transcript
+    /// scheme, which `read` checks and restores. See `write_legacy`
+    /// for the layout without it.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_with_version(writer, FormatVersion::V1)
+    }
+
+    /// Serialize these parameters without a header, as versions of
+    /// this crate before it did. The serialized parameters can be
+    /// read by bellman as Groth16 `Parameters`.
+    pub fn write_legacy<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_body(writer, FormatVersion::V1)
+    }
+
+    /// Serialize these parameters into bytes suitable for content
+    /// addressing: equal parameters always produce equal bytes, as
+    /// every point has a single uncompressed encoding and everything
+    /// is written in a fixed order. The bytes are the output of
+    /// `write_legacy`, laid out as:
+    ///
+    /// - alpha_g1, beta_g1, beta_g2, gamma_g2, delta_g1 and delta_g2,
+    ///   as uncompressed points (96 bytes in G1, 192 in G2);
+    /// - the IC, H, L, A, B_G1 and B_G2 queries, in that order, each
+    ///   as a big-endian u32 count followed by uncompressed points;
+    /// - the 64-byte `cs_hash`;
+    /// - a big-endian u32 count of contributions, followed by each
+    ///   public key as `PublicKey::write` serializes it: delta_after,
+    ///   s, s_delta and r_delta uncompressed, then the 64-byte
+    ///   transcript hash.
+    ///
+    /// Use this rather than `write_with_version`, whose layouts
+    /// differ.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_legacy(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Checks that these parameters serialize and deserialize without
+    /// losing anything: reading back their `canonical_bytes` (the
+    /// output of `write_legacy`, with all curve checks) gives equal
+    /// parameters, which serialize to the same bytes again. Run this
+    /// before publishing the hash of a file.
+    pub fn verify_roundtrip(&self) -> Result<(), VerificationError> {
+        let bytes = self.canonical_bytes();
+        let read = MPCParameters::<E>::read(&bytes[..], true)?;
+        if read != *self || read.canonical_bytes() != bytes {
+            return Err(VerificationError::NotCanonical);
         }
 
-        let mut ic = vec![bls12_381::G1Projective::identity(); assembly.num_inputs];
-        let mut l = vec![bls12_381::G1Projective::identity(); assembly.num_aux];
-        let mut a_g1 =
-            vec![bls12_381::G1Projective::identity(); assembly.num_inputs + assembly.num_aux];
-        let mut b_g1 =
-            vec![bls12_381::G1Projective::identity(); assembly.num_inputs + assembly.num_aux];
-        let mut b_g2 =
-            vec![bls12_381::G2Projective::identity(); assembly.num_inputs + assembly.num_aux];
+        Ok(())
+    }
 
-        fn eval(
-            // Lagrange coefficients for tau
-            coeffs_g1: Arc<Vec<bls12_381::G1Affine>>,
-            coeffs_g2: Arc<Vec<bls12_381::G2Affine>>,
-            alpha_coeffs_g1: Arc<Vec<bls12_381::G1Affine>>,
-            beta_coeffs_g1: Arc<Vec<bls12_381::G1Affine>>,
+    /// A BLAKE2b-512 hash of these parameters: of the 17-byte header
+    /// `phase2 canonical\x01` followed by their `canonical_bytes`.
+    /// The layout of those is fixed by this crate, so the hash won't
+    /// change between versions of it; if the layout ever has to
+    /// change, so will the version byte of the header.
+    pub fn canonical_hash(&self) -> [u8; 64] {
+        let mut sink = HashWriter::new(io::sink());
+        sink.write_all(CANONICAL_HASH_MAGIC).unwrap();
+        self.write_legacy(&mut sink).unwrap();
+        sink.into_hash()
+    }
 
-            // QAP polynomials
-            at: &[Vec<(bls12_381::Scalar, usize)>],
-            bt: &[Vec<(bls12_381::Scalar, usize)>],
-            ct: &[Vec<(bls12_381::Scalar, usize)>],
+    /// Like `write()`, but refuses to serialize parameters nobody has
+    /// contributed to yet, as their toxic waste is known to whoever
+    /// ran `new()`. Use this when publishing final parameters.
+    pub fn write_secure<W: Write>(&self, writer: W) -> io::Result<()> {
+        if self.contributions.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "parameters have no contributions",
+            ));
+        }
 
-            // Resulting evaluated QAP polynomials
-            a_g1: &mut [bls12_381::G1Projective],
-            b_g1: &mut [bls12_381::G1Projective],
-            b_g2: &mut [bls12_381::G2Projective],
-            ext: &mut [bls12_381::G1Projective],
+        self.write(writer)
+    }
 
-            // Worker
-            worker: &Worker,
-        ) {
-            // Sanity check
-            assert_eq!(a_g1.len(), at.len());
-            assert_eq!(a_g1.len(), bt.len());
-            assert_eq!(a_g1.len(), ct.len());
-            assert_eq!(a_g1.len(), b_g1.len());
-            assert_eq!(a_g1.len(), b_g2.len());
-            assert_eq!(a_g1.len(), ext.len());
+    /// Like `write()`, using a specific layout of the contributions
+    /// (see `FormatVersion`). The header records the layout, so
+    /// `read` and every other reader of files follow it.
+    pub fn write_with_version<W: Write>(
+        &self,
+        mut writer: W,
+        version: FormatVersion,
+    ) -> io::Result<()> {
+        if version == FormatVersion::V3 && self.circuit_fingerprint.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the circuit fingerprint isn't known",
+            ));
+        }
 
-            // Evaluate polynomials in multiple threads
-            worker.scope(a_g1.len(), |scope, chunk| {
-                for ((((((a_g1, b_g1), b_g2), ext), at), bt), ct) in a_g1
-                    .chunks_mut(chunk)
-                    .zip(b_g1.chunks_mut(chunk))
-                    .zip(b_g2.chunks_mut(chunk))
-                    .zip(ext.chunks_mut(chunk))
-                    .zip(at.chunks(chunk))
-                    .zip(bt.chunks(chunk))
-                    .zip(ct.chunks(chunk))
-                {
-                    let coeffs_g1 = coeffs_g1.clone();
-                    let coeffs_g2 = coeffs_g2.clone();
-                    let alpha_coeffs_g1 = alpha_coeffs_g1.clone();
-                    let beta_coeffs_g1 = beta_coeffs_g1.clone();
+        header::write_header::<E, _>(
+            &mut writer,
+            version,
+            &self.cs_hash,
+            self.transcript_scheme.id(),
+        )?;
+        self.write_body(writer, version)
+    }
 
-                    scope.spawn(move |_| {
-                        for ((((((a_g1, b_g1), b_g2), ext), at), bt), ct) in a_g1
-                            .iter_mut()
-                            .zip(b_g1.iter_mut())
-                            .zip(b_g2.iter_mut())
-                            .zip(ext.iter_mut())
-                            .zip(at.iter())
-                            .zip(bt.iter())
-                            .zip(ct.iter())
-                        {
-                            for &(coeff, lag) in at {
-                                a_g1.add_assign(&coeffs_g1[lag].mul(coeff));
-                                ext.add_assign(&beta_coeffs_g1[lag].mul(coeff));
-                            }
+    /// Serialize these parameters, without a header, in `version`.
+    fn write_body<W: Write>(&self, mut writer: W, version: FormatVersion) -> io::Result<()> {
+        for piece in self.body_pieces(version)? {
+            writer.write_all(&piece)?;
+        }
 
-                            for &(coeff, lag) in bt {
-                                b_g1.add_assign(&coeffs_g1[lag].mul(coeff));
-                                b_g2.add_assign(&coeffs_g2[lag].mul(coeff));
-                                ext.add_assign(&alpha_coeffs_g1[lag].mul(coeff));
-                            }
+        Ok(())
+    }
+
+    /// The serialization of these parameters, without a header, in
+    /// `version`, a piece at a time: the verifying key, each query
+    /// in pieces of at most `PIECE_POINTS` points, and then the
+    /// `cs_hash` and the contributions. Both `write_body` and
+    /// `write_async` write these pieces, so they lay out the file the
+    /// same way, and neither holds a second copy of the queries.
+    pub(crate) fn body_pieces(
+        &self,
+        version: FormatVersion,
+    ) -> io::Result<impl Iterator<Item = Vec<u8>> + '_> {
+        let mut vk = vec![];
+        self.params.vk.write(&mut vk)?;
+
+        let mut contributions = self.cs_hash.to_vec();
+        write_contributions_len(
+            &mut contributions,
+            version,
+            self.circuit_fingerprint.as_ref(),
+            self.contributions.len(),
+        )?;
+        for pubkey in &self.contributions {
+            pubkey.write(&mut contributions)?;
+        }
+
+        let params = &self.params;
+        Ok(iter::once(vk)
+            .chain(query_pieces(&params.h))
+            .chain(query_pieces(&params.l))
+            .chain(query_pieces(&params.a))
+            .chain(query_pieces(&params.b_g1))
+            .chain(query_pieces(&params.b_g2))
+            .chain(iter::once(contributions)))
+    }
+
+    /// Deserialize these parameters. If `checked` is false,
+    /// we won't perform curve validity and group order
+    /// checks. Parameters written by `write` are checked to be for
+    /// this curve, read in the layout their header names and given
+    /// the transcript scheme it names; those written without a
+    /// header are read as `FormatVersion::V1`, with `LegacyScheme`.
+    pub fn read<R: Read>(reader: R, checked: bool) -> io::Result<MPCParameters<E>> {
+        let (header, reader) = header::read_header::<E, _>(reader)?;
+        let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+
+        MPCParameters::read_after_header(header, reader, checked, version)
+    }
+
+    /// Reads the parameters after `header`, laid out as `version`.
+    fn read_after_header<R: Read>(
+        header: Option<header::Header>,
+        reader: R,
+        checked: bool,
+        version: FormatVersion,
+    ) -> io::Result<MPCParameters<E>> {
+        let scheme = header::scheme_of(&header)?;
+        let mut params = MPCParameters::read_body(reader, checked, version)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&params.cs_hash)?;
+        }
+        params.transcript_scheme = scheme;
+
+        Ok(params)
+    }
+
+    /// Like `read()`, for parameters whose transcript scheme is one
+    /// of your own (see `SchemeId::Custom`). Fails if the header of
+    /// the parameters names a different scheme than `scheme` does.
+    pub fn read_with_scheme<R: Read>(
+        reader: R,
+        checked: bool,
+        scheme: impl TranscriptScheme<E> + 'static,
+    ) -> io::Result<MPCParameters<E>> {
+        MPCParameters::read_with_shared_scheme(reader, checked, Arc::new(scheme))
+    }
+
+    /// Like `read_with_scheme`, with a scheme other parameters share.
+    fn read_with_shared_scheme<R: Read>(
+        reader: R,
+        checked: bool,
+        scheme: Arc<dyn TranscriptScheme<E>>,
+    ) -> io::Result<MPCParameters<E>> {
+        let (header, reader) = header::read_header::<E, _>(reader)?;
+        let version = match &header {
+            Some(header) if header.scheme != scheme.id() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the parameters use a different transcript scheme",
+                ));
+            }
+            Some(header) => header.version,
+            None => FormatVersion::V1,
+        };
+
+        let mut params = MPCParameters::read_body(reader, checked, version)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&params.cs_hash)?;
+        }
+        params.transcript_scheme = scheme;
+
+        Ok(params)
+    }
+
+    /// Like `read()`, also returning the BLAKE2b hash of the bytes
+    /// the parameters were read from, as `ParamsDigest::params_hash`
+    /// has it (if nothing follows the parameters). Comparing it with
+    /// a published hash checks a download without reading it again.
+    pub fn read_with_hash<R: Read>(
+        reader: R,
+        checked: bool,
+    ) -> io::Result<(MPCParameters<E>, [u8; 64])> {
+        let mut reader = HashReader::new(reader);
+        let params = MPCParameters::read(&mut reader, checked)?;
+
+        Ok((params, reader.into_hash()))
+    }
+
+    /// Deserialize parameters that were serialized using a specific
+    /// layout of the contributions (see `FormatVersion`). Parameters
+    /// with a header must be laid out as it says; those without one,
+    /// as versions of this crate wrote them, are read as `version`.
+    pub fn read_with_version<R: Read>(
+        reader: R,
+        checked: bool,
+        version: FormatVersion,
+    ) -> io::Result<MPCParameters<E>> {
+        let (header, reader) = header::read_header::<E, _>(reader)?;
+        if header.as_ref().is_some_and(|h| h.version != version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the parameters are laid out in a different format version",
+            ));
+        }
+
+        MPCParameters::read_after_header(header, reader, checked, version)
+    }
+
+    /// Reads the parameters that `write_body` wrote as `version`.
+    fn read_body<R: Read>(
+        mut reader: R,
+        checked: bool,
+        version: FormatVersion,
+    ) -> io::Result<MPCParameters<E>> {
+        let params = Parameters::read(&mut reader, checked)?;
+
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
 
-                            for &(coeff, lag) in ct {
-                                ext.add_assign(&coeffs_g1[lag].mul(coeff));
-                            }
-                        }
+        let (circuit_fingerprint, contributions_len) =
+            read_contributions_len(&mut reader, version)?;
 
-                        // Batch normalize
-                        batch_normalization(a_g1);
-                        batch_normalization(b_g1);
-                        batch_normalization(b_g2);
-                        batch_normalization(ext);
-                    });
-                }
-            });
+        // We don't trust the length to preallocate; a bogus length
+        // just makes us run out of public keys to read.
+        let mut contributions = vec![];
+        for _ in 0..contributions_len {
+            contributions.push(PublicKey::read(&mut reader)?);
         }
 
-        let worker = Worker::new();
+        Ok(MPCParameters {
+            params,
+            cs_hash,
+            contributions,
+            circuit_fingerprint,
+            transcript_scheme: Arc::new(LegacyScheme),
+            #[cfg(feature = "attest")]
+            verifier_attestations: vec![],
+        })
+    }
 
-        // Evaluate for inputs.
-        eval(
-            coeffs_g1.clone(),
-            coeffs_g2.clone(),
-            alpha_coeffs_g1.clone(),
-            beta_coeffs_g1.clone(),
-            &assembly.at_inputs,
-            &assembly.bt_inputs,
-            &assembly.ct_inputs,
-            &mut a_g1[0..assembly.num_inputs],
-            &mut b_g1[0..assembly.num_inputs],
-            &mut b_g2[0..assembly.num_inputs],
-            &mut ic,
-            &worker,
-        );
+    /// Deserialize these parameters, performing all curve validity
+    /// and group order checks, and make sure none of the queries
+    /// contain the point at infinity (see `check_no_infinity`).
+    pub fn read_verified<R: Read>(reader: R) -> Result<MPCParameters<E>, VerificationError> {
+        let params = MPCParameters::read(reader, true)?;
+        params.check_no_infinity()?;
 
-        // Evaluate for auxillary variables.
-        eval(
-            coeffs_g1.clone(),
-            coeffs_g2.clone(),
-            alpha_coeffs_g1.clone(),
-            beta_coeffs_g1.clone(),
-            &assembly.at_aux,
-            &assembly.bt_aux,
-            &assembly.ct_aux,
-            &mut a_g1[assembly.num_inputs..],
-            &mut b_g1[assembly.num_inputs..],
-            &mut b_g2[assembly.num_inputs..],
-            &mut l,
-            &worker,
-        );
+        Ok(params)
+    }
 
-        // Don't allow any elements be unconstrained, so that
-        // the L query is always fully dense.
-        for e in l.iter() {
-            if Into::<bool>::into(e.is_identity()) {
-                return Err(SynthesisError::UnconstrainedVariable);
-            }
-        }
+    /// Like `read_verified`, checking the points of each query on
+    /// the threads of `worker`, and reporting which element failed
+    /// if one isn't a valid group element.
+    pub fn read_checked_parallel<R: Read>(
+        reader: R,
+        worker: &Worker,
+    ) -> Result<MPCParameters<E>, VerificationError> {
+        let (header, reader) = header::read_header::<E, _>(reader)?;
+        let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+        let mut reader = BufReader::with_capacity(1024 * 1024, reader);
+        let reader = &mut reader;
+
+        let alpha_g1 = read_points_parallel(reader, "alpha_g1", 1, worker)?[0];
+        let beta_g1 = read_points_parallel(reader, "beta_g1", 1, worker)?[0];
+        let beta_g2 = read_points_parallel(reader, "beta_g2", 1, worker)?[0];
+        let gamma_g2 = read_points_parallel(reader, "gamma_g2", 1, worker)?[0];
+        let delta_g1 = read_points_parallel(reader, "delta_g1", 1, worker)?[0];
+        let delta_g2 = read_points_parallel(reader, "delta_g2", 1, worker)?[0];
+        let ic = read_query_parallel(reader, "ic", worker)?;
 
         let vk = VerifyingKey {
-            alpha_g1: alpha,
-            beta_g1: beta_g1,
-            beta_g2: beta_g2,
-            gamma_g2: bls12_381::G2Affine::generator(),
-            delta_g1: bls12_381::G1Affine::generator(),
-            delta_g2: bls12_381::G2Affine::generator(),
-            ic: ic.into_iter().map(|e| e.to_affine()).collect(),
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g1,
+            delta_g2,
+            ic,
         };
 
-        let params = Parameters {
-            vk: vk,
-            h: Arc::new(h),
-            l: Arc::new(l.into_iter().map(|e| e.to_affine()).collect()),
-
-            // Filter points at infinity away from A/B queries
-            a: Arc::new(
-                a_g1.into_iter()
-                    .filter(|e| !Into::<bool>::into(e.is_identity()))
-                    .map(|e| e.to_affine())
-                    .collect(),
-            ),
-            b_g1: Arc::new(
-                b_g1.into_iter()
-                    .filter(|e| !Into::<bool>::into(e.is_identity()))
-                    .map(|e| e.to_affine())
-                    .collect(),
-            ),
-            b_g2: Arc::new(
-                b_g2.into_iter()
-                    .filter(|e| !Into::<bool>::into(e.is_identity()))
-                    .map(|e| e.to_affine())
-                    .collect(),
-            ),
-        };
+        let h = read_query_parallel(reader, "h", worker)?;
+        let l = read_query_parallel(reader, "l", worker)?;
+        let a = read_query_parallel(reader, "a", worker)?;
+        let b_g1 = read_query_parallel(reader, "b_g1", worker)?;
+        let b_g2 = read_query_parallel(reader, "b_g2", worker)?;
 
-        let h = {
-            let sink = io::sink();
-            let mut sink = HashWriter::new(sink);
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&cs_hash)?;
+        }
 
-            params.write(&mut sink).unwrap();
+        let (circuit_fingerprint, contributions_len) = read_contributions_len(reader, version)?;
+        let mut contributions = vec![];
+        for _ in 0..contributions_len {
+            contributions.push(PublicKey::read(&mut *reader)?);
+        }
 
-            sink.into_hash()
+        let transcript_scheme = header::scheme_of(&header)?;
+        let params = MPCParameters {
+            params: Parameters {
+                vk,
+                h: Arc::new(h),
+                l: Arc::new(l),
+                a: Arc::new(a),
+                b_g1: Arc::new(b_g1),
+                b_g2: Arc::new(b_g2),
+            },
+            cs_hash,
+            contributions,
+            circuit_fingerprint,
+            transcript_scheme,
+            #[cfg(feature = "attest")]
+            verifier_attestations: vec![],
         };
+        params.check_no_infinity()?;
 
-        let mut cs_hash = [0; 64];
-        cs_hash.copy_from_slice(h.as_ref());
-
-        Ok(MPCParameters {
-            params: params,
-            cs_hash: cs_hash,
-            contributions: vec![],
-        })
+        Ok(params)
     }
 
-    /// Get the underlying Groth16 `Parameters`
-    pub fn get_params(&self) -> &Parameters<Bls12> {
-        &self.params
-    }
+    /// Serialize these parameters for a prover, and separately just
+    /// their verifying key for whoever only checks proofs.
+    pub fn split_keys(&self) -> (ProvingKeyBytes, VerifyingKeyBytes) {
+        let mut pk = vec![];
+        self.write_legacy(&mut pk).unwrap();
 
-    /// Contributes some randomness to the parameters. Only one
-    /// contributor needs to be honest for the parameters to be
-    /// secure.
-    ///
-    /// This function returns a "hash" that is bound to the
-    /// contribution. Contributors can use this hash to make
-    /// sure their contribution is in the final parameters, by
-    /// checking to see if it appears in the output of
-    /// `MPCParameters::verify`.
-    pub fn contribute<R: Rng>(&mut self, rng: &mut R) -> [u8; 64] {
-        // Generate a keypair
-        let (pubkey, privkey) = keypair(rng, self);
+        let mut vk = vec![];
+        self.params.vk.write(&mut vk).unwrap();
 
-        fn batch_exp(bases: &mut [bls12_381::G1Affine], coeff: bls12_381::Scalar) {
-            let mut projective = vec![bls12_381::G1Projective::identity(); bases.len()];
-            let cpus = num_cpus::get();
-            let chunk_size = if bases.len() < cpus {
-                1
-            } else {
-                bases.len() / cpus
-            };
+        (ProvingKeyBytes(pk), VerifyingKeyBytes(vk))
+    }
 
-            // Perform wNAF over multiple cores, placing results into `projective`.
-            crossbeam::scope(|scope| {
-                for (bases, projective) in bases
-                    .chunks_mut(chunk_size)
-                    .zip(projective.chunks_mut(chunk_size))
-                {
-                    scope.spawn(move || {
-                        let mut wnaf = Wnaf::new();
+    /// Take the final keys out of these parameters, dropping the
+    /// contributions: the Groth16 parameters for provers, their
+    /// verifying key for verifiers, and a summary of the transcript
+    /// they came from.
+    pub fn into_keys(self) -> (Parameters<E>, VerifyingKey<E>, TranscriptSummary) {
+        let summary = TranscriptSummary {
+            cs_hash: self.cs_hash,
+            contribution_hashes: self.contributions.iter().map(|p| p.hash()).collect(),
+            chain_commitment: self.chain_commitment(),
+        };
+        let vk = self.params.vk.clone();
 
-                        for (base, projective) in bases.iter_mut().zip(projective.iter_mut()) {
-                            *projective = wnaf.base(base.to_curve(), 1).scalar(&coeff);
-                        }
-                    });
-                }
-            });
+        (self.params, vk, summary)
+    }
 
-            // Perform batch normalization
-            crossbeam::scope(|scope| {
-                for projective in projective.chunks_mut(chunk_size) {
-                    scope.spawn(move || {
-                        batch_normalization(projective);
-                    });
-                }
-            });
+    /// Serialize the Groth16 parameters for provers, as bellman's
+    /// `Parameters::write` does, without the `cs_hash` and the
+    /// contributions that `write` appends. Bellman reads the result
+    /// with `Parameters::read`.
+    pub fn write_final_params<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.params.write(writer)
+    }
 
-            // Turn it all back into affine points
-            for (projective, affine) in projective.iter().zip(bases.iter_mut()) {
-                *affine = projective.to_affine();
-            }
-        }
+    /// Serialize the verifying key for verifiers, as bellman's
+    /// `VerifyingKey::write` does.
+    pub fn write_final_vk<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.params.vk.write(writer)
+    }
 
-        let delta_inv = privkey.delta.invert().expect("nonzero");
-        let mut l = (&self.params.l[..]).to_vec();
-        let mut h = (&self.params.h[..]).to_vec();
-        batch_exp(&mut l, delta_inv);
-        batch_exp(&mut h, delta_inv);
-        self.params.l = Arc::new(l);
-        self.params.h = Arc::new(h);
+    /// Deserialize the parameters from the output of `split_keys`,
+    /// performing all curve validity and group order checks, and
+    /// making sure the verifying key is the one they contain.
+    pub fn recombine_keys(
+        pk: &ProvingKeyBytes,
+        vk: &VerifyingKeyBytes,
+    ) -> io::Result<MPCParameters<E>> {
+        let params = MPCParameters::read(&pk.0[..], true)?;
 
-        self.params.vk.delta_g1 = self.params.vk.delta_g1.mul(privkey.delta).to_affine();
-        self.params.vk.delta_g2 = self.params.vk.delta_g2.mul(privkey.delta).to_affine();
+        if params.params.vk != vk.verifying_key()? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "verifying key doesn't match the parameters",
+            ));
+        }
 
-        self.contributions.push(pubkey.clone());
+        Ok(params)
+    }
 
-        // Calculate the hash of the public key and return it
-        {
-            let sink = io::sink();
-            let mut sink = HashWriter::new(sink);
-            pubkey.write(&mut sink).unwrap();
-            let h = sink.into_hash();
-            let mut response = [0u8; 64];
-            response.copy_from_slice(h.as_ref());
-            response
+    /// Check that, if there are any contributions, delta is no
+    /// longer the generator, which would mean the contributions
+    /// canceled each other out.
+    pub fn check_net_delta(&self) -> Result<(), VerificationError> {
+        if !self.contributions.is_empty() && self.params.vk.delta_g1 == E::G1Affine::generator() {
+            return Err(VerificationError::NetDeltaIsIdentity);
         }
-    }
 
-    /// Verify the correctness of the parameters, given a circuit
-    /// instance. This will return all of the hashes that
-    /// contributors obtained when they ran
-    /// `MPCParameters::contribute`, for ensuring that contributions
-    /// exist in the final parameters.
-    pub fn verify<C: Circuit<bls12_381::Scalar>>(&self, circuit: C) -> Result<Vec<[u8; 64]>, ()> {
-        let initial_params = MPCParameters::new(circuit).map_err(|_| ())?;
+        Ok(())
+    }
 
-        // H/L will change, but should have same length
-        if initial_params.params.h.len() != self.params.h.len() {
-            return Err(());
-        }
-        if initial_params.params.l.len() != self.params.l.len() {
-            return Err(());
+    /// Check that delta in G1 is the delta of the last contribution
+    /// (or the generator, if there are none) and that delta in G2 is
+    /// consistent with it.
+    pub fn check_delta_consistency(&self) -> Result<(), VerificationError> {
+        let expected = self
+            .contributions
+            .last()
+            .map_or(E::G1Affine::generator(), |pubkey| pubkey.delta_after);
+
+        if self.params.vk.delta_g1 != expected {
+            return Err(VerificationError::DeltaG1Mismatch);
         }
 
-        // A/B_G1/B_G2 doesn't change at all
-        if initial_params.params.a != self.params.a {
-            return Err(());
-        }
-        if initial_params.params.b_g1 != self.params.b_g1 {
-            return Err(());
-        }
-        if initial_params.params.b_g2 != self.params.b_g2 {
-            return Err(());
+        if !same_ratio(
+            (E::G1Affine::generator(), self.params.vk.delta_g1),
+            (E::G2Affine::generator(), self.params.vk.delta_g2),
+        ) {
+            return Err(VerificationError::DeltaG2Mismatch);
         }
 
-        // alpha/beta/gamma don't change
-        if initial_params.params.vk.alpha_g1 != self.params.vk.alpha_g1 {
-            return Err(());
-        }
-        if initial_params.params.vk.beta_g1 != self.params.vk.beta_g1 {
-            return Err(());
-        }
-        if initial_params.params.vk.beta_g2 != self.params.vk.beta_g2 {
-            return Err(());
-        }
-        if initial_params.params.vk.gamma_g2 != self.params.vk.gamma_g2 {
-            return Err(());
-        }
+        Ok(())
+    }
 
-        // IC shouldn't change, as gamma doesn't change
-        if initial_params.params.vk.ic != self.params.vk.ic {
-            return Err(());
+    /// Check that the IC query has an element for the implicit "one"
+    /// input that every circuit has.
+    pub fn check_ic_arity(&self) -> Result<(), VerificationError> {
+        if self.params.vk.ic.is_empty() {
+            return Err(VerificationError::IcEmpty);
         }
 
-        // cs_hash should be the same
-        if &initial_params.cs_hash[..] != &self.cs_hash[..] {
-            return Err(());
+        Ok(())
+    }
+
+    /// Run all of the checks a prover should make before using these
+    /// parameters: that someone contributed to them, that no query
+    /// contains the point at infinity (bellman never filters the L
+    /// query, so this also means it's dense), that delta is
+    /// consistent, and that the IC and H queries have a valid size.
+    ///
+    /// Unlike `verify`, this doesn't need the circuit, so it can't
+    /// tell whether the parameters are for the right one.
+    pub fn is_proving_ready(&self) -> Result<(), Phase2Error> {
+        if self.contributions.is_empty() {
+            return Err(Phase2Error::NoContributions);
         }
 
-        let sink = io::sink();
-        let mut sink = HashWriter::new(sink);
-        sink.write_all(&initial_params.cs_hash[..]).unwrap();
+        self.check_no_infinity()?;
+        self.check_delta_consistency()?;
+        self.check_ic_arity()?;
+        self.check_h_arity()?;
 
-        let mut current_delta = bls12_381::G1Affine::generator();
-        let mut result = vec![];
+        Ok(())
+    }
 
-        for pubkey in &self.contributions {
-            let mut our_sink = sink.clone();
-            our_sink
-                .write_all(pubkey.s.to_uncompressed().as_ref())
-                .unwrap();
-            our_sink
-                .write_all(pubkey.s_delta.to_uncompressed().as_ref())
-                .unwrap();
+    /// Check that the H query has `m - 1` elements, where `m` is the
+    /// size of the evaluation domain. That's a power of two, and the
+    /// domain holds a constraint for each element of the IC query
+    /// (one for each public input, and one for one), so it has at
+    /// least `ic.len()` elements, and never fewer than two.
+    pub fn check_h_arity(&self) -> Result<(), VerificationError> {
+        let len = self.params.h.len();
+        let m = len + 1;
+        let min_domain = self.params.vk.ic.len().next_power_of_two().max(2);
+
+        if !m.is_power_of_two() || m < min_domain {
+            return Err(VerificationError::HQueryArity { len, min_domain });
+        }
 
-            pubkey.write(&mut sink).unwrap();
+        Ok(())
+    }
 
-            let h = our_sink.into_hash();
+    /// Check that none of the elements of the verifying key or the
+    /// queries are the point at infinity, which would cause proving
+    /// to fail. Returns the first offending element.
+    pub fn check_no_infinity(&self) -> Result<(), VerificationError> {
+        let vk = &self.params.vk;
+        check_no_infinity_in("alpha_g1", &[vk.alpha_g1])?;
+        check_no_infinity_in("beta_g1", &[vk.beta_g1])?;
+        check_no_infinity_in("beta_g2", &[vk.beta_g2])?;
+        check_no_infinity_in("gamma_g2", &[vk.gamma_g2])?;
+        check_no_infinity_in("delta_g1", &[vk.delta_g1])?;
+        check_no_infinity_in("delta_g2", &[vk.delta_g2])?;
+        check_no_infinity_in("ic", &vk.ic)?;
+        check_no_infinity_in("h", &self.params.h)?;
+        check_no_infinity_in("l", &self.params.l)?;
+        check_no_infinity_in("a", &self.params.a)?;
+        check_no_infinity_in("b_g1", &self.params.b_g1)?;
+        check_no_infinity_in("b_g2", &self.params.b_g2)?;
 
-            // The transcript must be consistent
-            if &pubkey.transcript[..] != h.as_ref() {
-                return Err(());
-            }
+        Ok(())
+    }
+}
 
-            let r = hash_to_g2(h.as_ref()).to_affine();
+/// Whether `my_contribution` is among `contributions`, as returned
+/// by `verify`. It can be the hash `contribute` returned, or its hex,
+/// possibly abbreviated to a prefix of at least `MIN_HEX_PREFIX`
+/// digits.
+pub fn contains_contribution<Q: ContributionQuery + ?Sized>(
+    contributions: &[ContributionHash],
+    my_contribution: &Q,
+) -> bool {
+    contributions
+        .iter()
+        .any(|hash| my_contribution.matches(hash))
+}
 
-            // Check the signature of knowledge
-            if !same_ratio((r, pubkey.r_delta), (pubkey.s, pubkey.s_delta)) {
-                return Err(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use crate::core::keypair_with;
+    #[cfg(feature = "fs")]
+    use crate::testing::{deterministic_ceremony, insecure_radix, PinnedScheme, SyntheticCircuit};
+    use bls12_381::{G1Affine, G1Projective, Scalar};
+    #[cfg(feature = "fs")]
+    use bls12_381::{G2Affine, G2Projective};
+    #[cfg(feature = "fs")]
+    use std::sync::Once;
+
+    /// The largest domain of the circuits below.
+    #[cfg(feature = "fs")]
+    const MAX_EXP: u32 = 4;
+
+    /// Writes insecure radix files for the circuits below to a
+    /// directory, and makes it the current one, so that `new` and
+    /// `verify` find them. Nothing else in the tests uses the current
+    /// directory.
+    #[cfg(feature = "fs")]
+    fn radix_in_cwd() {
+        static WRITE: Once = Once::new();
+
+        WRITE.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("phase2-tests-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            for exp in 0..=MAX_EXP {
+                let file = File::create(dir.join(format!("phase1radix2m{}", exp))).unwrap();
+                insecure_radix::<Bls12>(exp, [exp as u8; 32])
+                    .write(io::BufWriter::new(file))
+                    .unwrap();
             }
+            std::env::set_current_dir(&dir).unwrap();
+        });
+    }
 
-            // Check the change from the old delta is consistent
-            if !same_ratio((current_delta, pubkey.delta_after), (r, pubkey.r_delta)) {
-                return Err(());
-            }
+    fn random_points(n: usize, seed: u8) -> (Vec<G1Affine>, Vec<G1Affine>) {
+        let mut rng = ChaChaRng::from_seed([seed; 32]);
+        let x = Scalar::random(&mut rng);
+        let v1: Vec<G1Affine> = (0..n)
+            .map(|_| (G1Affine::generator() * Scalar::random(&mut rng)).to_affine())
+            .collect();
+        let v2 = v1.iter().map(|p| (*p * x).to_affine()).collect();
 
-            current_delta = pubkey.delta_after;
+        (v1, v2)
+    }
 
-            {
-                let sink = io::sink();
-                let mut sink = HashWriter::new(sink);
-                pubkey.write(&mut sink).unwrap();
-                let h = sink.into_hash();
-                let mut response = [0u8; 64];
-                response.copy_from_slice(h.as_ref());
-                result.push(response);
-            }
-        }
+    #[test]
+    fn seeded_merge_ignores_threads() {
+        let (v1, v2) = random_points(50, 1);
+        let merge = |threads| {
+            Config { threads }
+                .install(|| {
+                    let tracker = Tracker::new(Progress::new(&no_progress), Stage::Verify, 0);
+                    merge_pieces_on(
+                        &Worker::new(),
+                        &v1,
+                        &v2,
+                        3,
+                        &tracker,
+                        MergeScalars::Seeded([7; 32]),
+                    )
+                })
+                .unwrap()
+        };
 
-        // Current parameters should have consistent delta in G1
-        if current_delta != self.params.vk.delta_g1 {
-            return Err(());
+        // Piece `i` uses stream `i`, whoever merges it
+        let mut expected = (G1Projective::identity(), G1Projective::identity());
+        for (i, (v1, v2)) in v1.chunks(3).zip(v2.chunks(3)).enumerate() {
+            let mut rng = ChaChaRng::from_seed([7; 32]);
+            rng.set_stream(i as u64);
+            let (s, sx) = merge_chunk_with(&mut rng, v1, v2);
+            expected.0 += s;
+            expected.1 += sx;
         }
+        let expected = (expected.0.to_affine(), expected.1.to_affine());
 
-        // Current parameters should have consistent delta in G2
-        if !same_ratio(
-            (bls12_381::G1Affine::generator(), current_delta),
-            (bls12_381::G2Affine::generator(), self.params.vk.delta_g2),
-        ) {
-            return Err(());
+        assert_eq!(merge(1), expected);
+        assert_eq!(merge(3), expected);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn verify_with_rng_ignores_threads() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(MAX_EXP);
+        let mut params = MPCParameters::<Bls12>::new(circuit).unwrap();
+        params.contribute_with_seed([1; 32]);
+
+        let verify = |threads| {
+            Config { threads }
+                .install(|| params.verify_with_rng(circuit, &mut ChaChaRng::from_seed([2; 32])))
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(verify(1), verify(3));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn cancelling_contributions_are_rejected() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let mut params = deterministic_ceremony(circuit, &[[1; 32]]);
+
+        // The delta `contribute_with_seed` drew, which the next
+        // contribution undoes, with a valid proof of knowledge
+        let delta = Scalar::random(&mut ChaChaRng::from_seed(expand_seed(&[1; 32])));
+        let s = G1Projective::random(&mut ChaChaRng::from_seed([2; 32])).to_affine();
+        let (pubkey, privkey) = keypair_with(
+            &*params.transcript_scheme,
+            delta.invert().unwrap(),
+            s,
+            &params.cs_hash,
+            &params.contributions,
+            params.params.vk.delta_g1,
+        );
+        params.apply_keypair(pubkey, &privkey, Progress::new(&no_progress));
+        assert_eq!(params.params.vk.delta_g1, G1Affine::generator());
+
+        assert!(matches!(
+            params.check_net_delta(),
+            Err(VerificationError::NetDeltaIsIdentity)
+        ));
+        assert!(matches!(
+            params.verify(circuit),
+            Err(VerificationError::NetDeltaIsIdentity)
+        ));
+    }
+
+    #[cfg(all(feature = "fs", feature = "rfc9380"))]
+    #[test]
+    fn transcript_scheme_round_trips() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let scheme = Rfc9380Scheme::new(b"PHASE2-TESTS".to_vec());
+        let mut before = MPCParameters::<Bls12>::new(circuit)
+            .unwrap()
+            .with_transcript_scheme(scheme.clone());
+        before.contribute_with_seed([1; 32]);
+        let mut before_bytes = vec![];
+        before.write(&mut before_bytes).unwrap();
+
+        let read = MPCParameters::<Bls12>::read(&before_bytes[..], true).unwrap();
+        assert_eq!(read.transcript_scheme.id(), scheme.id());
+        read.verify(circuit).unwrap();
+        assert!(
+            MPCParameters::<Bls12>::read_with_scheme(&before_bytes[..], true, LegacyScheme)
+                .is_err()
+        );
+
+        // Streaming contributes and checks with the scheme of the header
+        let mut after_bytes = vec![];
+        MPCParameters::<Bls12>::contribute_streaming(
+            &before_bytes[..],
+            &mut after_bytes,
+            &mut ChaChaRng::from_seed([2; 32]),
+        )
+        .unwrap();
+        let after = MPCParameters::<Bls12>::read(&after_bytes[..], true).unwrap();
+        assert_eq!(after.transcript_scheme.id(), scheme.id());
+        after.verify(circuit).unwrap();
+        verify_contribution_streamed::<Bls12, _, _>(&before_bytes[..], &after_bytes[..], 7)
+            .unwrap();
+        let token = after.verify_structure(circuit).unwrap();
+        assert!(validate_file(&after_bytes[..], &token).is_valid());
+
+        let mut digest = vec![];
+        after.digest().write(&mut digest).unwrap();
+        let digest = ParamsDigest::read(&digest[..]).unwrap();
+        quick_verify_contribution(&before.digest(), &digest).unwrap();
+
+        let mut challenge = vec![];
+        after.prepare_challenge().write(&mut challenge).unwrap();
+        let challenge = Challenge::<Bls12>::read(&challenge[..]).unwrap();
+        let mut next = after.clone();
+        next.apply_response(&respond(&challenge, [3; 32]).unwrap())
+            .unwrap();
+        next.verify(circuit).unwrap();
+
+        #[cfg(feature = "snarkjs")]
+        {
+            let mut zkey = vec![];
+            after.write_zkey(circuit, &mut zkey).unwrap();
+            let read = MPCParameters::read_zkey(&zkey[..]).unwrap();
+            assert_eq!(read.transcript_scheme.id(), scheme.id());
         }
+    }
 
-        // H and L queries should be updated with delta^-1
-        if !same_ratio(
-            merge_pairs(&initial_params.params.h, &self.params.h),
-            (self.params.vk.delta_g2, bls12_381::G2Affine::generator()), // reversed for inverse
-        ) {
-            return Err(());
+    #[cfg(feature = "fs")]
+    #[test]
+    fn verify_proof_rejects_empty_ic() {
+        radix_in_cwd();
+
+        let mut params = MPCParameters::<Bls12>::new(SyntheticCircuit::new(2)).unwrap();
+        params.params.vk.ic.clear();
+        let proof = Proof {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+
+        assert!(matches!(
+            params.verify_proof(&proof, &[]),
+            Err(Phase2Error::Verification(VerificationError::IcEmpty))
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn self_test_rejects_empty_ic() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let mut params = MPCParameters::<Bls12>::new(circuit).unwrap();
+        params.params.vk.ic.clear();
+
+        assert!(matches!(
+            params.self_test(circuit, &[]),
+            Err(SelfTestError::IcEmpty)
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn pinned_scheme_pins_contributions() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let mut params = MPCParameters::<Bls12>::new(circuit)
+            .unwrap()
+            .with_transcript_scheme(PinnedScheme(|_| G2Projective::generator()));
+        let initial = params.clone();
+        let hash = params.contribute_with_seed([1; 32]);
+        params.verify(circuit).unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        verify_transcript(&initial, [&bytes[..]]).unwrap();
+
+        // The delta `contribute_with_seed` draws, from the generators
+        // the initial parameters have
+        let delta = Scalar::random(&mut ChaChaRng::from_seed(expand_seed(&[1; 32])));
+        let pubkey = &params.contributions[0];
+        assert_eq!(
+            pubkey.delta_after,
+            (G1Affine::generator() * delta).to_affine()
+        );
+        assert_eq!(pubkey.r_delta, (G2Affine::generator() * delta).to_affine());
+        assert_eq!(
+            hex(&hash),
+            "bab843227d8a62527f61579ca4c5d53e2842a7e4c11706bcc9bff08324e5e353\
+             848a829959ffbdb016adc622761d13ba90a036b63adbf07525e5baf9210660d0"
+        );
+
+        // A custom scheme can't be read back with a challenge
+        let mut challenge = vec![];
+        params.prepare_challenge().write(&mut challenge).unwrap();
+        let challenge = Challenge::<Bls12>::read(&challenge[..]).unwrap();
+        assert!(respond(&challenge, [2; 32]).is_err());
+        let scheme = PinnedScheme(|_| G2Projective::generator());
+        let response = respond_with_scheme(&challenge, [2; 32], &scheme);
+        params.apply_response(&response).unwrap();
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn canonical_hash_hashes_canonical_bytes() {
+        radix_in_cwd();
+
+        let params = deterministic_ceremony(SyntheticCircuit::new(2), &[[1; 32]]);
+
+        let mut sink = HashWriter::new(io::sink());
+        sink.write_all(b"phase2 canonical\x01").unwrap();
+        sink.write_all(&params.canonical_bytes()).unwrap();
+        assert_eq!(params.canonical_hash(), sink.into_hash());
+        // As before the hash was defined over `canonical_bytes`
+        assert_eq!(
+            hex(&params.canonical_hash()),
+            "5d2002097916b8d654c5e64601ba1173c4ed3bf7b38d933154271d5d11d3c95c\
+             e1cf7ec838013e0ebfa80f3008789e90c3228500d7cb14e41542e9b46b347b49"
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn pubkey_checks_agree() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let params = deterministic_ceremony(circuit, &[[1; 32], [2; 32]]);
+        let check = |tamper: fn(&mut PublicKey<Bls12>)| {
+            let mut params = params.clone();
+            tamper(&mut params.contributions[1]);
+            let by_id = params.verify_chain_by_id(params.cs_hash, params.structural_digest());
+            let previous = &params.contributions[..1];
+            let single = check_pubkey(
+                &LegacyScheme,
+                &params.cs_hash,
+                previous,
+                previous[0].delta_after,
+                &params.contributions[1],
+            );
+
+            (
+                params.verify(circuit).unwrap_err(),
+                params.verify_aggregate(circuit).unwrap_err(),
+                by_id.unwrap_err(),
+                single.unwrap_err(),
+            )
+        };
+
+        assert!(matches!(
+            check(|pubkey| pubkey.transcript[0] ^= 1),
+            (
+                VerificationError::InvalidTranscript { index: 1 },
+                VerificationError::InvalidTranscript { index: 1 },
+                VerificationError::InvalidTranscript { index: 1 },
+                VerificationError::InvalidTranscript { index: 1 },
+            )
+        ));
+        assert!(matches!(
+            check(|pubkey| pubkey.r_delta = G2Affine::generator()),
+            (
+                VerificationError::InvalidSignature { index: 1 },
+                // Only the product of the pairings is checked
+                VerificationError::AggregateCheckFailed,
+                VerificationError::InvalidSignature { index: 1 },
+                VerificationError::InvalidSignature { index: 1 },
+            )
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn transcript_chunks_can_split_public_keys() {
+        radix_in_cwd();
+
+        let params = deterministic_ceremony(SyntheticCircuit::new(2), &[[1; 32], [2; 32], [3; 32]]);
+        let mut transcript = vec![];
+        params
+            .write_transcript_chunk(0..3, &mut transcript)
+            .unwrap();
+
+        // Chunks that split every public key, and an empty one
+        let mut chunks: Vec<&[u8]> = transcript.chunks(100).collect();
+        chunks.insert(2, &[]);
+        let hashes = verify_transcript_chunked::<Bls12, _, _>(params.cs_hash, chunks).unwrap();
+        let expected: Vec<_> = params.contributions.iter().map(|p| p.hash()).collect();
+        assert_eq!(hashes, expected);
+
+        // A public key cut short is still an error
+        let cut = &transcript[..transcript.len() - 1];
+        assert!(verify_transcript_chunked::<Bls12, _, _>(params.cs_hash, [cut]).is_err());
+
+        // Ranges past the last contribution, or reversed, are refused
+        for range in [2..4, Range { start: 3, end: 2 }, 4..4] {
+            let err = params.write_transcript_chunk(range, vec![]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
         }
+    }
 
-        if !same_ratio(
-            merge_pairs(&initial_params.params.l, &self.params.l),
-            (self.params.vk.delta_g2, bls12_381::G2Affine::generator()), // reversed for inverse
-        ) {
-            return Err(());
+    #[test]
+    fn given_scalars_merge_in_order() {
+        let (v1, v2) = random_points(50, 3);
+        let scalars: Vec<Scalar> = (1..=50u64).map(Scalar::from).collect();
+
+        let mut expected = (G1Projective::identity(), G1Projective::identity());
+        for ((v1, v2), rho) in v1.iter().zip(&v2).zip(&scalars) {
+            expected.0 += v1 * rho;
+            expected.1 += v2 * rho;
         }
+        let expected = (expected.0.to_affine(), expected.1.to_affine());
+
+        assert_eq!(merge_pairs_with_scalars(&v1, &v2, &scalars), expected);
+        let tracker = Tracker::new(Progress::new(&no_progress), Stage::Verify, 0);
+        let pieces = Config { threads: 3 }
+            .install(|| {
+                merge_pieces_on(
+                    &Worker::new(),
+                    &v1,
+                    &v2,
+                    7,
+                    &tracker,
+                    MergeScalars::Given(&scalars),
+                )
+            })
+            .unwrap();
+        assert_eq!(pieces, expected);
+    }
 
-        Ok(result)
+    #[test]
+    fn private_keys_zeroize() {
+        let mut privkey = PrivateKey::<Bls12> {
+            delta: Scalar::from(5),
+        };
+        privkey.zeroize();
+        assert_eq!(privkey.delta, Scalar::ZERO);
     }
 
-    /// Serialize these parameters. The serialized parameters
-    /// can be read by bellman as Groth16 `Parameters`.
-    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        self.params.write(&mut writer)?;
-        writer.write_all(&self.cs_hash)?;
+    #[cfg(all(feature = "fs", feature = "shamir"))]
+    #[test]
+    fn shares_contribute_as_their_seed() {
+        radix_in_cwd();
 
-        writer.write_u32::<BigEndian>(self.contributions.len() as u32)?;
-        for pubkey in &self.contributions {
-            pubkey.write(&mut writer)?;
-        }
+        let circuit = SyntheticCircuit::new(2);
+        let shares = shamir::split(&[1; 32], 2, 3, &mut ChaChaRng::from_seed([2; 32])).unwrap();
+        let mut params = MPCParameters::<Bls12>::new(circuit).unwrap();
+        let hash = params.contribute_from_shares(&shares[1..], 2).unwrap();
 
-        Ok(())
+        assert!(params == deterministic_ceremony(circuit, &[[1; 32]]));
+        assert_eq!(hash, <[u8; 64]>::from(params.contributions[0].hash()));
     }
 
-    /// Deserialize these parameters. If `checked` is false,
-    /// we won't perform curve validity and group order
-    /// checks.
-    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<MPCParameters> {
-        let params = Parameters::read(&mut reader, checked)?;
+    #[cfg(all(feature = "fs", feature = "rfc9380"))]
+    #[test]
+    fn contributions_cant_change_the_scheme() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let before = deterministic_ceremony(circuit, &[[1; 32]]);
+        let mut after = before
+            .clone()
+            .with_transcript_scheme(Rfc9380Scheme::default());
+        after.contribute_with_seed([2; 32]);
+
+        assert!(matches!(
+            verify_contribution(&before, &after),
+            Err(VerificationError::TranscriptSchemeMismatch)
+        ));
+        let (mut before_bytes, mut after_bytes) = (vec![], vec![]);
+        before.write(&mut before_bytes).unwrap();
+        after.write(&mut after_bytes).unwrap();
+        assert!(matches!(
+            verify_contribution_bytes::<Bls12>(&before_bytes, &after_bytes, true),
+            Err(VerificationError::TranscriptSchemeMismatch)
+        ));
+        assert!(matches!(
+            verify_contribution_streamed::<Bls12, _, _>(&before_bytes[..], &after_bytes[..], 7),
+            Err(VerificationError::TranscriptSchemeMismatch)
+        ));
+        assert!(matches!(
+            verify_transcript(&before, [&after_bytes[..]]),
+            Err(VerificationError::TranscriptSchemeMismatch)
+        ));
+    }
 
-        let mut cs_hash = [0u8; 64];
-        reader.read_exact(&mut cs_hash)?;
+    #[cfg(feature = "fs")]
+    #[test]
+    fn streaming_accepts_a_header_after_none() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let before = deterministic_ceremony(circuit, &[[1; 32]]);
+        let mut after = before.clone();
+        let contributed = ContributionHash::from(after.contribute_with_seed([2; 32]));
+
+        let (mut before_bytes, mut after_bytes) = (vec![], vec![]);
+        before.write_legacy(&mut before_bytes).unwrap();
+        after.write(&mut after_bytes).unwrap();
+        let hash =
+            verify_contribution_streamed::<Bls12, _, _>(&before_bytes[..], &after_bytes[..], 7)
+                .unwrap();
 
-        let contributions_len = reader.read_u32::<BigEndian>()? as usize;
+        // Every verifier returns the same hash, comparable in constant time
+        assert!(bool::from(hash.ct_eq(&contributed)));
+        assert!(bool::from(
+            verify_contribution(&before, &after).unwrap().ct_eq(&hash)
+        ));
+        assert!(!bool::from(hash.ct_eq(&before.contributions[0].hash())));
+    }
 
-        let mut contributions = vec![];
-        for _ in 0..contributions_len {
-            contributions.push(PublicKey::read(&mut reader)?);
-        }
+    #[cfg(feature = "fs")]
+    #[test]
+    fn chain_by_id_checks_the_identity() {
+        radix_in_cwd();
+
+        let params = deterministic_ceremony(SyntheticCircuit::new(2), &[[1; 32]]);
+        let digest = params.structural_digest();
+        let hashes = params.verify_chain_by_id(params.cs_hash, digest).unwrap();
+        assert_eq!(hashes, vec![params.contributions[0].hash()]);
+
+        let mut cs_hash = params.cs_hash;
+        cs_hash[0] ^= 1;
+        assert!(matches!(
+            params.verify_chain_by_id(cs_hash, digest),
+            Err(VerificationError::CsHashMismatch)
+        ));
+        let other = deterministic_ceremony(SyntheticCircuit::new(3), &[]);
+        assert!(matches!(
+            params.verify_chain_by_id(params.cs_hash, other.structural_digest()),
+            Err(VerificationError::StructuralDigestMismatch)
+        ));
+    }
 
-        Ok(MPCParameters {
-            params,
-            cs_hash,
-            contributions,
-        })
+    #[cfg(feature = "fs")]
+    #[test]
+    fn every_reader_follows_the_format_version() {
+        radix_in_cwd();
+
+        let circuit = SyntheticCircuit::new(2);
+        let params = deterministic_ceremony(circuit, &[[1; 32]]);
+        let token = params.verify_structure(circuit).unwrap();
+        let hashes = vec![params.contributions[0].hash()];
+
+        for version in [FormatVersion::V1, FormatVersion::V2, FormatVersion::V3] {
+            let mut bytes = vec![];
+            params.write_with_version(&mut bytes, version).unwrap();
+
+            let read = MPCParameters::<Bls12>::read(&bytes[..], true).unwrap();
+            assert!(read == params);
+            if version == FormatVersion::V3 {
+                assert_eq!(read.circuit_fingerprint, params.circuit_fingerprint);
+            }
+            let read =
+                MPCParameters::<Bls12>::read_checked_parallel(&bytes[..], &Worker::new()).unwrap();
+            assert!(read == params);
+
+            let report = validate_file(&bytes[..], &token);
+            assert!(report.first_error.is_none());
+            assert_eq!(report.contribution_hashes, hashes);
+
+            let digest = crate::digest::ParamsDigest::<Bls12>::compute(&bytes[..]).unwrap();
+            assert_eq!(digest.contribution_count(), 1);
+
+            let mut after = vec![];
+            let mut rng = ChaChaRng::from_seed([3; 32]);
+            MPCParameters::<Bls12>::contribute_streaming(&bytes[..], &mut after, &mut rng).unwrap();
+            verify_contribution_streamed::<Bls12, _, _>(&bytes[..], &after[..], 7).unwrap();
+            let after =
+                MPCParameters::<Bls12>::read_with_version(&after[..], true, version).unwrap();
+            assert_eq!(after.contributions.len(), 2);
+
+            #[cfg(feature = "mmap")]
+            {
+                let path = std::env::current_dir()
+                    .unwrap()
+                    .join(format!("mapped-{:?}", version));
+                std::fs::write(&path, &bytes).unwrap();
+                let mut file = crate::mmap::MPCParametersFile::<Bls12>::open(&path).unwrap();
+                file.contribute(&mut rng).unwrap();
+                let after = MPCParameters::<Bls12>::read(File::open(&path).unwrap(), true).unwrap();
+                verify_contribution(&params, &after).unwrap();
+            }
+        }
     }
-}
 
-/// This is a cheap helper utility that exists purely
-/// because Rust still doesn't have type-level integers
-/// and so doesn't implement `PartialEq` for `[T; 64]`
-pub fn contains_contribution(contributions: &[[u8; 64]], my_contribution: &[u8; 64]) -> bool {
-    for contrib in contributions {
-        if &contrib[..] == &my_contribution[..] {
-            return true;
+    #[cfg(all(feature = "client", feature = "coordinator"))]
+    #[test]
+    fn downloads_recover_from_a_partial_file_past_the_end() {
+        use std::time::Duration;
+
+        radix_in_cwd();
+
+        let dir = std::env::current_dir().unwrap().join("client-416");
+        std::fs::create_dir_all(dir.join("client")).unwrap();
+        let params = deterministic_ceremony(SyntheticCircuit::new(2), &[[1; 32]]);
+        let params_path = dir.join("params");
+        params.write(File::create(&params_path).unwrap()).unwrap();
+        let bytes = std::fs::read(&params_path).unwrap();
+
+        let coordinator = coordinator::Coordinator::<Bls12>::open(
+            params_path,
+            dir.join("transcript"),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        std::thread::spawn(move || coordinator.serve(addr));
+        while std::net::TcpStream::connect(addr).is_err() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let client = client::Client::new(&format!("http://{}", addr), dir.join("client"));
+        let partial = dir.join("client").join("params.part");
+
+        // Complete, as if we stopped before renaming it, and then left
+        // over from longer parameters
+        for stale in [bytes.clone(), [&bytes[..], &[0; 10]].concat()] {
+            std::fs::write(&partial, stale).unwrap();
+            let path = client.download().unwrap();
+            assert_eq!(std::fs::read(path).unwrap(), bytes);
         }
     }
 
-    return false;
+    #[cfg(feature = "fs")]
+    #[test]
+    fn h_arity_is_checked_against_the_inputs() {
+        radix_in_cwd();
+
+        let mut params = deterministic_ceremony(SyntheticCircuit::new(2), &[]);
+        params.check_h_arity().unwrap();
+
+        params.params.h = Arc::new(vec![]);
+        assert!(matches!(
+            params.check_h_arity(),
+            Err(VerificationError::HQueryArity {
+                len: 0,
+                min_domain: 2
+            })
+        ));
+
+        // A domain of 4 can't hold 5 input constraints
+        params.params.h = Arc::new(vec![G1Affine::generator(); 3]);
+        params.params.vk.ic = vec![G1Affine::generator(); 5];
+        assert!(matches!(
+            params.check_h_arity(),
+            Err(VerificationError::HQueryArity {
+                len: 3,
+                min_domain: 8
+            })
+        ));
+    }
 }
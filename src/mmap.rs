@@ -0,0 +1,330 @@
+//! Parameters that stay on disk, enabled by the `mmap` feature.
+
+use bellman::groth16::VerifyingKey;
+use bellman::Circuit;
+use bls12_381::Bls12;
+use byteorder::{BigEndian, ByteOrder};
+use ff::Field;
+use memmap2::MmapMut;
+use pairing::group::{Curve, UncompressedEncoding};
+use pairing::Engine;
+use rand::Rng;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::ops::{Mul, Range};
+use std::path::Path;
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+use crate::header;
+use crate::{
+    batch_exp, check_no_infinity_in, keypair, no_progress, read_contributions_len, read_point,
+    validate_file, write_contributions_len, ContributionHash, FormatVersion, MPCEngine,
+    MPCParameters, Progress, PublicKey, Stage, StructureToken, Tracker, TranscriptScheme,
+    VerificationError, CONTRIBUTE_CHUNK,
+};
+
+fn uncompressed_len<G: UncompressedEncoding>() -> usize {
+    G::Uncompressed::default().as_ref().len()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Serialized parameters (as written by `MPCParameters::write`) that
+/// are memory-mapped instead of read into memory. Contributing
+/// streams the H and L queries through memory in chunks and updates
+/// the file in place, so it needs little more memory than a chunk.
+///
+/// A contribution that's interrupted leaves the file corrupted, so
+/// contribute to a copy of the parameters you received.
+pub struct MPCParametersFile<E: Engine = Bls12> {
+    file: File,
+    mmap: MmapMut,
+    /// Past the header, if the file has one.
+    vk_offset: usize,
+    h: Range<usize>,
+    l: Range<usize>,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    cs_hash: [u8; 64],
+    /// The layout of the contributions, from the header.
+    version: FormatVersion,
+    circuit_fingerprint: Option<[u8; 32]>,
+    /// Where the circuit fingerprint or the number of contributions
+    /// starts.
+    contributions_offset: usize,
+    contributions: Vec<PublicKey<E>>,
+    /// From the header, or `LegacyScheme` without one.
+    transcript_scheme: Arc<dyn TranscriptScheme<E>>,
+}
+
+impl<E: MPCEngine> MPCParametersFile<E> {
+    /// Open the parameters at `path` for reading and writing. Only
+    /// the layout of the file and the contributions are read; the
+    /// queries are checked as they're used.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        // Safety: the mapping is only sound as long as nothing else
+        // modifies the file, which is up to the caller, as with any
+        // file the parameters are read from.
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        let g1 = uncompressed_len::<E::G1Affine>();
+        let g2 = uncompressed_len::<E::G2Affine>();
+
+        let (header, _) = header::read_header::<E, _>(&mmap[..])?;
+        let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+        let vk_offset = header.as_ref().map_or(0, |h| h.len());
+        let transcript_scheme = header::scheme_of(&header)?;
+
+        let mut rest = &mmap[vk_offset..];
+        let vk = VerifyingKey::<E>::read(&mut rest)?;
+        let mut offset = mmap.len() - rest.len();
+
+        // Finds the range of the points of the query at `offset`
+        let mut query = |size: usize| -> io::Result<Range<usize>> {
+            let len = mmap
+                .get(offset..offset + 4)
+                .map(BigEndian::read_u32)
+                .ok_or_else(|| invalid("truncated parameters"))?;
+            let range = offset + 4..offset + 4 + len as usize * size;
+            if range.end > mmap.len() {
+                return Err(invalid("truncated parameters"));
+            }
+
+            offset = range.end;
+            Ok(range)
+        };
+
+        let h = query(g1)?;
+        let l = query(g1)?;
+        query(g1)?;
+        query(g1)?;
+        query(g2)?;
+
+        let mut rest = &mmap[offset..];
+        let mut cs_hash = [0u8; 64];
+        io::Read::read_exact(&mut rest, &mut cs_hash)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&cs_hash)?;
+        }
+
+        let contributions_offset = offset + 64;
+        let (circuit_fingerprint, count) = read_contributions_len(&mut rest, version)?;
+        let contributions = (0..count)
+            .map(|_| PublicKey::read(&mut rest))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if !rest.is_empty() {
+            return Err(invalid("trailing data after the parameters"));
+        }
+
+        Ok(MPCParametersFile {
+            file,
+            mmap,
+            vk_offset,
+            h,
+            l,
+            delta_g1: vk.delta_g1,
+            delta_g2: vk.delta_g2,
+            cs_hash,
+            version,
+            circuit_fingerprint,
+            contributions_offset,
+            contributions,
+            transcript_scheme,
+        })
+    }
+
+    /// Like `MPCParameters::contribute`, writing the contribution to
+    /// the file.
+    pub fn contribute<R: Rng>(&mut self, rng: &mut R) -> io::Result<[u8; 64]> {
+        let (pubkey, privkey) = keypair(
+            rng,
+            &*self.transcript_scheme,
+            &self.cs_hash,
+            &self.contributions,
+            self.delta_g1,
+        );
+
+        let g1 = uncompressed_len::<E::G1Affine>();
+        let g2 = uncompressed_len::<E::G2Affine>();
+
+        // As `check_no_infinity` would after the contribution, refuse
+        // before anything is written back: a point at infinity stays
+        // one whatever delta is, and the file would be left half done.
+        let delta_g1_after = self.delta_g1.mul(privkey.delta).to_affine();
+        let delta_g2_after = self.delta_g2.mul(privkey.delta).to_affine();
+        self.check_no_infinity(delta_g1_after, delta_g2_after)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        let mut delta_inv = privkey.delta.invert().expect("nonzero");
+        let tracker = Tracker::new(Progress::new(&no_progress), Stage::Contribute, 0);
+
+        for query in [self.h.clone(), self.l.clone()] {
+            for chunk in self.mmap[query].chunks_mut(CONTRIBUTE_CHUNK * g1) {
+                let mut points = chunk
+                    .chunks(g1)
+                    .map(|mut p| read_point::<E::G1Affine, _>(&mut p))
+                    .collect::<io::Result<Vec<_>>>()?;
+
+                batch_exp::<E>(&mut points, delta_inv, &tracker);
+
+                for (p, bytes) in points.iter().zip(chunk.chunks_mut(g1)) {
+                    bytes.copy_from_slice(p.to_uncompressed().as_ref());
+                }
+            }
+        }
+        delta_inv.zeroize();
+
+        // Delta follows alpha and beta in G1, and beta and gamma in G2
+        self.delta_g1 = delta_g1_after;
+        self.delta_g2 = delta_g2_after;
+        let delta_g1 = self.vk_offset + 2 * g1 + 2 * g2;
+        let delta_g2 = delta_g1 + g1;
+        self.mmap[delta_g1..delta_g2].copy_from_slice(self.delta_g1.to_uncompressed().as_ref());
+        self.mmap[delta_g2..delta_g2 + g2]
+            .copy_from_slice(self.delta_g2.to_uncompressed().as_ref());
+        self.mmap.flush()?;
+
+        // The contributions are at the end. A varint count can grow,
+        // so they're all written again after it, which only ever
+        // makes the file longer.
+        self.contributions.push(pubkey.clone());
+        self.file
+            .seek(SeekFrom::Start(self.contributions_offset as u64))?;
+        let mut tail = vec![];
+        write_contributions_len(
+            &mut tail,
+            self.version,
+            self.circuit_fingerprint.as_ref(),
+            self.contributions.len(),
+        )?;
+        for contribution in &self.contributions {
+            contribution.write(&mut tail)?;
+        }
+        self.file.write_all(&tail)?;
+        self.file.flush()?;
+
+        // Safety: as in `open`
+        self.mmap = unsafe { MmapMut::map_mut(&self.file)? };
+
+        Ok(pubkey.hash().into())
+    }
+
+    /// `MPCParameters::check_no_infinity` for the H and L queries in
+    /// the file and delta after a contribution, the only parts of the
+    /// parameters it changes.
+    fn check_no_infinity(
+        &self,
+        delta_g1: E::G1Affine,
+        delta_g2: E::G2Affine,
+    ) -> Result<(), VerificationError> {
+        check_no_infinity_in("delta_g1", &[delta_g1])?;
+        check_no_infinity_in("delta_g2", &[delta_g2])?;
+
+        let g1 = uncompressed_len::<E::G1Affine>();
+        for (name, query) in [("h", self.h.clone()), ("l", self.l.clone())] {
+            for (i, chunk) in self.mmap[query].chunks(CONTRIBUTE_CHUNK * g1).enumerate() {
+                // Decoding without checks is enough to spot the point
+                // at infinity; `contribute` checks the points anyway
+                let points: Vec<E::G1Affine> = chunk
+                    .chunks(g1)
+                    .map(|bytes| {
+                        let mut repr =
+                            <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
+                        repr.as_mut().copy_from_slice(bytes);
+                        Option::from(E::G1Affine::from_uncompressed_unchecked(&repr))
+                            .ok_or_else(|| invalid("invalid point"))
+                    })
+                    .collect::<io::Result<_>>()?;
+
+                check_no_infinity_in(name, &points).map_err(|e| match e {
+                    VerificationError::PointAtInfinity { query, index } => {
+                        VerificationError::PointAtInfinity {
+                            query,
+                            index: i * CONTRIBUTE_CHUNK + index,
+                        }
+                    }
+                    e => e,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like `MPCParameters::verify`, streaming the parameters from
+    /// the file. The initial parameters of the circuit are still
+    /// held in memory.
+    pub fn verify<C: Circuit<E::Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<Vec<ContributionHash>, VerificationError> {
+        let initial = MPCParameters::<E>::new(circuit)?;
+        let token = StructureToken {
+            params: initial.params,
+            cs_hash: initial.cs_hash,
+        };
+
+        let report = validate_file(&self.mmap[..], &token);
+        match report.first_error {
+            Some(e) => Err(e),
+            None => Ok(report.contribution_hashes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::{insecure_radix, RandomCircuit};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn contributions_refuse_a_point_at_infinity() {
+        let circuit = RandomCircuit {
+            constraints: 6,
+            inputs: 1,
+            aux: 4,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(3, [3; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::<Bls12>::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        Arc::make_mut(&mut params.params.l)[2] = bls12_381::G1Affine::identity();
+
+        let path = std::env::temp_dir().join(format!(
+            "phase2-mmap-infinity-{}.params",
+            std::process::id()
+        ));
+        params.write(File::create(&path).unwrap()).unwrap();
+        let before = std::fs::read(&path).unwrap();
+
+        let mut file = MPCParametersFile::<Bls12>::open(&path).unwrap();
+        let err = file
+            .contribute(&mut ChaChaRng::from_seed([1; 32]))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            VerificationError::PointAtInfinity {
+                query: "l",
+                index: 2
+            }
+            .to_string()
+        );
+        drop(file);
+
+        // Nothing was written
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,97 @@
+//! Scoped threads for splitting work across CPUs, with a fallback
+//! that runs everything on the current thread when the `multicore`
+//! feature is disabled (e.g. on `wasm32-unknown-unknown`, which has
+//! no threads).
+//!
+//! Work runs on the current rayon thread pool, as bellman's does, so
+//! running the crate's functions within a pool's `install` (or with
+//! a `Config`) limits every parallel part of them to that pool.
+
+use std::io;
+
+/// How many threads the crate may use, for running on a machine it
+/// shares. Functions run with `Config::install` split their work
+/// over a pool of `threads` threads, including the parts bellman
+/// does; `0` uses one per CPU, as functions run outside of `install`
+/// do. To share a rayon pool of your own instead, call the crate's
+/// functions from within its `install`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The number of threads, or `0` for one per CPU.
+    pub threads: usize,
+}
+
+impl Config {
+    /// Run `f` with the parallelism of this configuration. Fails if
+    /// the threads can't be spawned. Without the `multicore`
+    /// feature, `f` runs on the current thread.
+    pub fn install<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        #[cfg(feature = "multicore")]
+        {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .build()
+                .map_err(io::Error::other)?;
+
+            Ok(pool.install(f))
+        }
+
+        #[cfg(not(feature = "multicore"))]
+        Ok(f())
+    }
+}
+
+#[cfg(feature = "multicore")]
+mod implementation {
+    /// The number of threads work is split over: those of the
+    /// current rayon pool.
+    pub fn num_threads() -> usize {
+        rayon::current_num_threads()
+    }
+
+    /// Spawns closures onto the current rayon pool.
+    pub struct Scope<'a, 'scope>(&'a rayon::Scope<'scope>);
+
+    impl<'scope> Scope<'_, 'scope> {
+        pub fn spawn<F: FnOnce() + Send + 'scope>(&self, f: F) {
+            self.0.spawn(move |_| f());
+        }
+    }
+
+    pub fn scope<'scope, F, R>(f: F) -> R
+    where
+        F: for<'a> FnOnce(&Scope<'a, 'scope>) -> R + Send,
+        R: Send,
+    {
+        rayon::scope(|scope| f(&Scope(scope)))
+    }
+}
+
+#[cfg(not(feature = "multicore"))]
+mod implementation {
+    pub fn num_threads() -> usize {
+        1
+    }
+
+    /// Runs closures "spawned" on it immediately, one after another.
+    pub struct Scope;
+
+    impl Scope {
+        pub fn spawn<F: FnOnce()>(&self, f: F) {
+            f();
+        }
+    }
+
+    pub fn scope<F, R>(f: F) -> R
+    where
+        F: FnOnce(&Scope) -> R,
+    {
+        f(&Scope)
+    }
+}
+
+pub(crate) use self::implementation::*;
@@ -0,0 +1,201 @@
+//! Contributing in two steps.
+//!
+//! The keypair of a contribution only depends on the `cs_hash`, the
+//! previous contributions and the current delta, which together are
+//! small enough to carry around as a `Challenge`. `respond` computes
+//! the keypair from it, and the `Response` is then applied to the
+//! parameters with `MPCParameters::apply_response`, which does the
+//! expensive update of the H and L queries.
+//!
+//! Rescaling the queries needs the contribution's secret delta, so
+//! the response holds it. That's why a response can't be
+//! serialized: it must be applied by the contributor, in the process
+//! that made it, and it's wiped when dropped, like the randomness
+//! used by `contribute`. Handing it to a coordinator would hand them
+//! the toxic waste.
+
+use bls12_381::Bls12;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::prime::PrimeCurveAffine;
+use pairing::group::{Curve, UncompressedEncoding};
+use pairing::Engine;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::io::{self, Read, Write};
+use std::ops::Mul;
+use std::sync::Arc;
+
+use crate::core::transcript_hash;
+use crate::{
+    expand_seed, keypair, no_progress, read_point, MPCEngine, MPCParameters, PrivateKey, Progress,
+    PublicKey, SchemeId, TranscriptScheme, VerificationError,
+};
+
+/// Everything needed to compute the keypair of the next contribution
+/// to some parameters, made with `MPCParameters::prepare_challenge`.
+#[derive(Clone)]
+pub struct Challenge<E: Engine = Bls12> {
+    pub(crate) cs_hash: [u8; 64],
+    pub(crate) contributions: Vec<PublicKey<E>>,
+    pub(crate) delta_g1: E::G1Affine,
+    pub(crate) scheme_id: SchemeId,
+    /// `None` for a custom scheme that was read back.
+    pub(crate) scheme: Option<Arc<dyn TranscriptScheme<E>>>,
+}
+
+impl<E: MPCEngine> Challenge<E> {
+    /// Serialize this challenge.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.cs_hash)?;
+        writer.write_u32::<BigEndian>(self.contributions.len() as u32)?;
+        for pubkey in &self.contributions {
+            pubkey.write(&mut writer)?;
+        }
+        writer.write_all(self.delta_g1.to_uncompressed().as_ref())?;
+
+        // Last, so that challenges from before it was written can be
+        // read
+        self.scheme_id.write(writer)
+    }
+
+    /// Deserialize a challenge, performing all curve validity and
+    /// group order checks.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Challenge<E>> {
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
+
+        let count = reader.read_u32::<BigEndian>()?;
+        let contributions = (0..count)
+            .map(|_| PublicKey::read(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let delta_g1: E::G1Affine = read_point(&mut reader)?;
+        if delta_g1.is_identity().into() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "point at infinity",
+            ));
+        }
+
+        let scheme_id = match SchemeId::read(&mut reader) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => SchemeId::Legacy,
+            id => id?,
+        };
+        let scheme = match scheme_id {
+            SchemeId::Custom(_) => None,
+            _ => Some(scheme_id.scheme()?),
+        };
+
+        Ok(Challenge {
+            cs_hash,
+            contributions,
+            delta_g1,
+            scheme_id,
+            scheme,
+        })
+    }
+}
+
+/// The keypair of a contribution, computed by `respond`. This holds
+/// the contribution's secret, so it can't be serialized.
+pub struct Response<E: MPCEngine = Bls12> {
+    pubkey: PublicKey<E>,
+    privkey: PrivateKey<E>,
+}
+
+impl<E: MPCEngine> Response<E> {
+    /// The hash the contribution will have, as `contribute` returns.
+    pub fn hash(&self) -> [u8; 64] {
+        self.pubkey.hash().into()
+    }
+}
+
+/// Computes the keypair of a contribution to the parameters that
+/// `challenge` was made from, with their transcript scheme. The
+/// randomness is derived from `entropy` as in
+/// `MPCParameters::contribute_with_seed`, and applying the response
+/// gives the same parameters as contributing with that seed would.
+///
+/// Fails if the challenge was read back for a custom scheme, which
+/// has to be passed to `respond_with_scheme`.
+pub fn respond<E: MPCEngine>(
+    challenge: &Challenge<E>,
+    entropy: [u8; 32],
+) -> io::Result<Response<E>> {
+    let scheme = challenge.scheme.as_deref().ok_or(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "the challenge is for a custom transcript scheme, see respond_with_scheme",
+    ))?;
+
+    Ok(respond_with_scheme(challenge, entropy, scheme))
+}
+
+/// Like `respond`, with `scheme` instead of the scheme recorded in the
+/// challenge, e.g. for a custom scheme, which can't be read back.
+pub fn respond_with_scheme<E: MPCEngine>(
+    challenge: &Challenge<E>,
+    entropy: [u8; 32],
+    scheme: &dyn TranscriptScheme<E>,
+) -> Response<E> {
+    let mut rng = ChaChaRng::from_seed(expand_seed(&entropy));
+    let (pubkey, privkey) = keypair(
+        &mut rng,
+        scheme,
+        &challenge.cs_hash,
+        &challenge.contributions,
+        challenge.delta_g1,
+    );
+
+    Response { pubkey, privkey }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Make the challenge for the next contribution to these
+    /// parameters, to be answered with `respond`.
+    pub fn prepare_challenge(&self) -> Challenge<E> {
+        Challenge {
+            cs_hash: self.cs_hash,
+            contributions: self.contributions.clone(),
+            delta_g1: self.params.vk.delta_g1,
+            scheme_id: self.transcript_scheme.id(),
+            scheme: Some(self.transcript_scheme.clone()),
+        }
+    }
+
+    /// Apply a response to the challenge of these parameters, as if
+    /// `contribute` had been called. The response is checked against
+    /// these parameters first, so one that was made for different
+    /// parameters is rejected. Returns the hash of the contribution.
+    ///
+    /// Only the contributor can do this, as the response holds their
+    /// secret (see the module documentation).
+    pub fn apply_response(
+        &mut self,
+        response: &Response<E>,
+    ) -> Result<[u8; 64], VerificationError> {
+        let Response { pubkey, privkey } = response;
+        let index = self.contributions.len();
+
+        let transcript =
+            transcript_hash(&self.cs_hash, &self.contributions, pubkey.s, pubkey.s_delta);
+        if pubkey.transcript != transcript {
+            return Err(VerificationError::InvalidTranscript { index });
+        }
+
+        // Knowing delta, the public key can be checked without pairings
+        let r = self
+            .transcript_scheme
+            .hash_to_g2(&pubkey.transcript)
+            .to_affine();
+        if pubkey.s.mul(privkey.delta).to_affine() != pubkey.s_delta
+            || r.mul(privkey.delta).to_affine() != pubkey.r_delta
+        {
+            return Err(VerificationError::InvalidSignature { index });
+        }
+        if self.params.vk.delta_g1.mul(privkey.delta).to_affine() != pubkey.delta_after {
+            return Err(VerificationError::InvalidDeltaRatio { index });
+        }
+
+        Ok(self.apply_keypair(pubkey.clone(), privkey, Progress::new(&no_progress)))
+    }
+}
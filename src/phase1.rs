@@ -0,0 +1,672 @@
+//! Utilities for the Powers of Tau (phase 1) radix files that
+//! `MPCParameters::new` reads.
+
+use bellman::SynthesisError;
+use ff::{Field, PrimeField};
+use group::prime::PrimeCurveAffine;
+use group::{Curve, Group, UncompressedEncoding, Wnaf, WnafGroup};
+#[cfg(feature = "fs")]
+use rand::Rng;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{self, Read, Write};
+#[cfg(feature = "fs")]
+use std::io::{BufReader, Seek, SeekFrom};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use crate::{merge_pairs, multicore, same_ratio, MPCEngine, Phase2Error};
+
+pub mod powersoftau;
+
+/// The largest exponent the Powers of Tau ceremony produced a radix
+/// file for.
+pub(crate) const MAX_EXP: u32 = 21;
+
+/// Where `MPCParameters::new_with_opts` finds the radix files, and
+/// how large they go.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug)]
+pub struct Phase1Options {
+    /// The largest exponent there's a radix file for. The default is
+    /// 21, as produced by the original Powers of Tau ceremony; larger
+    /// transcripts go up to 2^28.
+    pub max_exp: u32,
+    /// The path of the radix files, with `{exp}` standing for the
+    /// exponent. The default is `phase1radix2m{exp}`, in the current
+    /// directory.
+    pub path_template: String,
+}
+
+#[cfg(feature = "fs")]
+impl Default for Phase1Options {
+    fn default() -> Self {
+        Phase1Options {
+            max_exp: MAX_EXP,
+            path_template: "phase1radix2m{exp}".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Phase1Options {
+    /// The path of the radix file for a domain of size `2^exp`.
+    pub fn path(&self, exp: u32) -> PathBuf {
+        PathBuf::from(self.path_template.replace("{exp}", &exp.to_string()))
+    }
+}
+
+/// Returns the exponents `exp` for which `phase1radix2m{exp}` is
+/// present in `dir`, in ascending order. A circuit with `n`
+/// constraints needs the file for the smallest `exp` with `2^exp >= n`.
+#[cfg(feature = "fs")]
+pub fn available_radix(dir: &Path) -> Vec<u32> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut exps: Vec<u32> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_prefix("phase1radix2m")?.parse().ok()
+        })
+        .collect();
+    exps.sort_unstable();
+    exps
+}
+
+/// Returns the exponent `exp` of the radix file `new` needs for a
+/// circuit with `num_constraints` constraints, i.e. the smallest one
+/// with `2^exp >= num_constraints`. Fails if that's larger than any
+/// radix file the Powers of Tau ceremony produced.
+pub fn required_exp(num_constraints: usize) -> Result<u32, Phase2Error> {
+    required_exp_up_to(num_constraints, MAX_EXP)
+}
+
+/// Like `required_exp`, for radix files up to `2^max_exp`.
+pub(crate) fn required_exp_up_to(num_constraints: usize, max_exp: u32) -> Result<u32, Phase2Error> {
+    let mut m = 1;
+    let mut exp = 0;
+    while m < num_constraints {
+        m *= 2;
+        exp += 1;
+
+        // There's no radix file for larger domains
+        if exp > max_exp {
+            return Err(SynthesisError::PolynomialDegreeTooLarge.into());
+        }
+    }
+
+    Ok(exp)
+}
+
+/// Where `MPCParameters::new_from_source` reads the radix file from.
+pub enum RadixSource<'a> {
+    /// The directory holding `phase1radix2m{exp}`, which is picked
+    /// for the size of the circuit. `new` uses the current directory.
+    #[cfg(feature = "fs")]
+    Dir(PathBuf),
+    /// The radix file itself, which must be the one for the size of
+    /// the circuit.
+    #[cfg(feature = "fs")]
+    File(PathBuf),
+    /// A reader positioned at the start of the radix file.
+    Reader(Box<dyn Read + 'a>),
+    /// The contents of the radix file, e.g. a file that was
+    /// memory-mapped.
+    Bytes(&'a [u8]),
+}
+
+impl<'a> RadixSource<'a> {
+    /// Opens the radix file for a domain of size `2^exp`.
+    #[cfg_attr(not(feature = "fs"), allow(unused_variables))]
+    pub(crate) fn open(self, exp: u32) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            #[cfg(feature = "fs")]
+            RadixSource::Dir(dir) => {
+                Box::new(File::open(dir.join(format!("phase1radix2m{}", exp)))?)
+            }
+            #[cfg(feature = "fs")]
+            RadixSource::File(path) => Box::new(File::open(path)?),
+            RadixSource::Reader(reader) => reader,
+            RadixSource::Bytes(bytes) => Box::new(bytes),
+        })
+    }
+}
+
+/// Why a radix file failed `RadixFile::verify_structure`, or
+/// couldn't be checked against a Powers of Tau transcript.
+#[derive(Debug)]
+pub enum RadixError {
+    /// The file couldn't be read, or has invalid points.
+    Io(io::Error),
+    /// Beta in G1 and in G2 differ.
+    BetaMismatch,
+    /// The coefficients in G1 and in G2 differ.
+    CoeffsMismatch,
+    /// The coefficients aren't the Lagrange basis of the domain,
+    /// evaluated at the same tau as `h`.
+    NotLagrangeBasis,
+    /// The alpha coefficients aren't alpha times the coefficients.
+    AlphaCoeffsMismatch,
+    /// The beta coefficients aren't beta times the coefficients.
+    BetaCoeffsMismatch,
+    /// `h` isn't the powers of tau times the domain's vanishing
+    /// polynomial.
+    HMismatch,
+    /// The response file doesn't have the published hash.
+    ResponseHashMismatch,
+    /// The radix file isn't the one the response file produces.
+    NotFromResponse,
+    /// The file is `got` bytes long, but the radix file for its
+    /// domain is `expected` bytes long.
+    WrongLength { expected: u64, got: u64 },
+    /// A point of this vector isn't valid.
+    InvalidPoint(&'static str),
+}
+
+impl fmt::Display for RadixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RadixError::Io(e) => write!(f, "I/O error: {}", e),
+            RadixError::BetaMismatch => write!(f, "beta differs in G1 and G2"),
+            RadixError::CoeffsMismatch => write!(f, "the coefficients differ in G1 and G2"),
+            RadixError::NotLagrangeBasis => {
+                write!(f, "the coefficients aren't a Lagrange basis at tau")
+            }
+            RadixError::AlphaCoeffsMismatch => write!(f, "invalid alpha coefficients"),
+            RadixError::BetaCoeffsMismatch => write!(f, "invalid beta coefficients"),
+            RadixError::HMismatch => write!(f, "invalid h query"),
+            RadixError::ResponseHashMismatch => {
+                write!(f, "the response file doesn't have the published hash")
+            }
+            RadixError::NotFromResponse => {
+                write!(f, "the radix file wasn't produced from the response file")
+            }
+            RadixError::WrongLength { expected, got } if got < expected => {
+                write!(f, "truncated: expected {} bytes, got {}", expected, got)
+            }
+            RadixError::WrongLength { expected, got } => {
+                write!(f, "too long: expected {} bytes, got {}", expected, got)
+            }
+            RadixError::InvalidPoint(vector) => write!(f, "invalid point in {}", vector),
+        }
+    }
+}
+
+impl std::error::Error for RadixError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RadixError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RadixError {
+    fn from(e: io::Error) -> RadixError {
+        RadixError::Io(e)
+    }
+}
+
+/// Decodes `bytes` as consecutive points of `size` bytes each, in
+/// parallel. Fails if any of them is invalid.
+fn decode_points<G, F>(bytes: &[u8], size: usize, decode: F) -> io::Result<Vec<G>>
+where
+    G: PrimeCurveAffine,
+    F: Fn(&[u8]) -> Option<G> + Sync,
+{
+    let len = bytes.len() / size;
+    let chunk = len / multicore::num_threads() + 1;
+
+    let mut points = vec![G::identity(); len];
+    let mut valid = vec![true; len.div_ceil(chunk)];
+
+    multicore::scope(|scope| {
+        for ((points, bytes), valid) in points
+            .chunks_mut(chunk)
+            .zip(bytes.chunks(chunk * size))
+            .zip(valid.iter_mut())
+        {
+            let decode = &decode;
+
+            scope.spawn(move || {
+                for (p, bytes) in points.iter_mut().zip(bytes.chunks(size)) {
+                    match decode(bytes) {
+                        Some(point) => *p = point,
+                        None => {
+                            *valid = false;
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if valid.contains(&false) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid point"));
+    }
+
+    Ok(points)
+}
+
+/// Decodes an uncompressed point, if it's valid.
+fn decode_point<G: UncompressedEncoding>(bytes: &[u8]) -> Option<G> {
+    let mut repr = G::Uncompressed::default();
+    repr.as_mut().copy_from_slice(bytes);
+    Option::from(G::from_uncompressed(&repr))
+}
+
+fn point_size<G: UncompressedEncoding>() -> usize {
+    G::Uncompressed::default().as_ref().len()
+}
+
+/// Reads `len` uncompressed points, checking that they're valid.
+fn read_points<G, R>(reader: &mut R, len: usize) -> io::Result<Vec<G>>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    let size = point_size::<G>();
+
+    let mut bytes = vec![0u8; len * size];
+    reader.read_exact(&mut bytes)?;
+
+    decode_points(&bytes, size, decode_point)
+}
+
+fn write_points<G: UncompressedEncoding, W: Write>(writer: &mut W, points: &[G]) -> io::Result<()> {
+    for p in points {
+        writer.write_all(p.to_uncompressed().as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Computes `sum(scalars[i] * points[i])`, in parallel.
+fn lincomb<G>(points: &[G], scalars: &[G::Scalar]) -> G
+where
+    G: PrimeCurveAffine,
+    G::Curve: WnafGroup,
+{
+    let chunk = points.len() / multicore::num_threads() + 1;
+    let sum = std::sync::Mutex::new(G::Curve::identity());
+
+    multicore::scope(|scope| {
+        for (points, scalars) in points.chunks(chunk).zip(scalars.chunks(chunk)) {
+            let sum = &sum;
+
+            scope.spawn(move || {
+                let mut wnaf = Wnaf::new();
+                let mut local = G::Curve::identity();
+                for (p, scalar) in points.iter().zip(scalars) {
+                    local += wnaf.scalar(scalar).base(p.to_curve());
+                }
+
+                *sum.lock().unwrap() += local;
+            });
+        }
+    });
+
+    sum.into_inner().unwrap().to_affine()
+}
+
+/// The contents of a radix file `phase1radix2m{exp}`: the parts of
+/// the Powers of Tau output that phase 2 needs for a domain of size
+/// `m = 2^exp`, with the coefficients in the Lagrange basis.
+#[derive(Clone)]
+pub struct RadixFile<E: MPCEngine> {
+    /// Alpha in G1.
+    pub alpha_g1: E::G1Affine,
+    /// Beta in G1.
+    pub beta_g1: E::G1Affine,
+    /// Beta in G2.
+    pub beta_g2: E::G2Affine,
+    /// `L_i(tau)` in G1, for the `m` Lagrange polynomials `L_i`.
+    pub coeffs_g1: Vec<E::G1Affine>,
+    /// `L_i(tau)` in G2.
+    pub coeffs_g2: Vec<E::G2Affine>,
+    /// `alpha * L_i(tau)` in G1.
+    pub alpha_coeffs_g1: Vec<E::G1Affine>,
+    /// `beta * L_i(tau)` in G1.
+    pub beta_coeffs_g1: Vec<E::G1Affine>,
+    /// `tau^i * (tau^m - 1)` in G1, for `i < m - 1`.
+    pub h: Vec<E::G1Affine>,
+}
+
+/// What `RadixFile::open` found out about a radix file.
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RadixInfo {
+    /// The exponent of the domain.
+    pub exp: u32,
+    /// The size of the domain, `2^exp`, which is the degree of the
+    /// QAP and the most constraints a circuit using the file can have.
+    pub degree: usize,
+    /// The number of points in G1.
+    pub g1_points: usize,
+    /// The number of points in G2.
+    pub g2_points: usize,
+    /// The length of the file, in bytes.
+    pub len: u64,
+    /// How many of the points were decoded.
+    pub checked: usize,
+}
+
+/// The vectors of the radix file for a domain of size `m`, in the
+/// order they're written, with their length and whether they're in
+/// G2.
+#[cfg(feature = "fs")]
+fn radix_layout(m: usize) -> [(&'static str, usize, bool); 8] {
+    [
+        ("alpha_g1", 1, false),
+        ("beta_g1", 1, false),
+        ("beta_g2", 1, true),
+        ("coeffs_g1", m, false),
+        ("coeffs_g2", m, true),
+        ("alpha_coeffs_g1", m, false),
+        ("beta_coeffs_g1", m, false),
+        ("h", m - 1, false),
+    ]
+}
+
+/// The number of points `check_points` decodes at a time.
+#[cfg(feature = "fs")]
+const CHECK_CHUNK: usize = 1 << 16;
+
+/// Checks the `len` points of `vector` at `offset`: all of them, or
+/// the first, the last and `samples` more at random. Returns how many
+/// were decoded.
+#[cfg(feature = "fs")]
+fn check_points<G, R>(
+    reader: &mut R,
+    vector: &'static str,
+    offset: u64,
+    len: usize,
+    samples: Option<usize>,
+) -> Result<usize, RadixError>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read + Seek,
+{
+    let size = point_size::<G>();
+
+    let samples = match samples {
+        Some(samples) if samples + 2 < len => samples,
+        _ => {
+            reader.seek(SeekFrom::Start(offset))?;
+            let mut bytes = vec![0u8; CHECK_CHUNK.min(len) * size];
+            let mut left = len;
+            while left > 0 {
+                let bytes = &mut bytes[..CHECK_CHUNK.min(left) * size];
+                reader.read_exact(bytes)?;
+                decode_points::<G, _>(bytes, size, decode_point)
+                    .map_err(|_| RadixError::InvalidPoint(vector))?;
+                left -= bytes.len() / size;
+            }
+
+            return Ok(len);
+        }
+    };
+
+    let rng = &mut rand::thread_rng();
+    let mut indices: Vec<usize> = (0..samples).map(|_| rng.gen_range(0..len)).collect();
+    indices.extend([0, len - 1]);
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut bytes = vec![0u8; size];
+    for &i in &indices {
+        reader.seek(SeekFrom::Start(offset + (i * size) as u64))?;
+        reader.read_exact(&mut bytes)?;
+        if decode_point::<G>(&bytes).is_none() {
+            return Err(RadixError::InvalidPoint(vector));
+        }
+    }
+
+    Ok(indices.len())
+}
+
+/// Checks the radix file at `path` for `RadixFile::open` and
+/// `RadixFile::open_sampled`.
+#[cfg(feature = "fs")]
+fn check_radix<E: MPCEngine>(
+    path: &Path,
+    exp: u32,
+    samples: Option<usize>,
+) -> Result<RadixInfo, RadixError> {
+    if exp > E::Fr::S || exp >= usize::BITS {
+        return Err(RadixError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the field has no domain of that size",
+        )));
+    }
+
+    let m = 1 << exp;
+    let layout = radix_layout(m);
+    let g1 = point_size::<E::G1Affine>();
+    let g2 = point_size::<E::G2Affine>();
+
+    let count = |in_g2: bool| -> usize {
+        layout
+            .iter()
+            .filter(|&&(_, _, g)| g == in_g2)
+            .map(|&(_, len, _)| len)
+            .sum()
+    };
+    let (g1_points, g2_points) = (count(false), count(true));
+    let expected = (g1_points * g1 + g2_points * g2) as u64;
+
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len != expected {
+        return Err(RadixError::WrongLength { expected, got: len });
+    }
+
+    let reader = &mut BufReader::with_capacity(1024 * 1024, file);
+    let mut offset = 0;
+    let mut checked = 0;
+    for (vector, len, in_g2) in layout {
+        checked += if in_g2 {
+            check_points::<E::G2Affine, _>(reader, vector, offset, len, samples)?
+        } else {
+            check_points::<E::G1Affine, _>(reader, vector, offset, len, samples)?
+        };
+        offset += (len * if in_g2 { g2 } else { g1 }) as u64;
+    }
+
+    Ok(RadixInfo {
+        exp,
+        degree: m,
+        g1_points,
+        g2_points,
+        len,
+        checked,
+    })
+}
+
+impl<E: MPCEngine> PartialEq for RadixFile<E> {
+    fn eq(&self, other: &RadixFile<E>) -> bool {
+        self.alpha_g1 == other.alpha_g1
+            && self.beta_g1 == other.beta_g1
+            && self.beta_g2 == other.beta_g2
+            && self.coeffs_g1 == other.coeffs_g1
+            && self.coeffs_g2 == other.coeffs_g2
+            && self.alpha_coeffs_g1 == other.alpha_coeffs_g1
+            && self.beta_coeffs_g1 == other.beta_coeffs_g1
+            && self.h == other.h
+    }
+}
+
+impl<E: MPCEngine> RadixFile<E> {
+    /// Check the radix file at `path` for a domain of size `2^exp`
+    /// without building anything from it: that it has the right
+    /// length and that every point is valid. A truncated or corrupted
+    /// file is reported here, instead of partway through
+    /// `MPCParameters::new`. This doesn't check that the points are
+    /// consistent; see `verify_structure` for that.
+    #[cfg(feature = "fs")]
+    pub fn open<P: AsRef<Path>>(path: P, exp: u32) -> Result<RadixInfo, RadixError> {
+        check_radix::<E>(path.as_ref(), exp, None)
+    }
+
+    /// Like `open`, but only decodes the first and last points of
+    /// each vector and `samples` more at random, which takes seconds
+    /// even for the largest files. A wrong length is still always
+    /// caught, but a corrupted point only with some probability.
+    #[cfg(feature = "fs")]
+    pub fn open_sampled<P: AsRef<Path>>(
+        path: P,
+        exp: u32,
+        samples: usize,
+    ) -> Result<RadixInfo, RadixError> {
+        check_radix::<E>(path.as_ref(), exp, Some(samples))
+    }
+
+    /// Read the radix file for a domain of size `2^exp`, checking
+    /// that every point is valid.
+    pub fn read<R: Read>(mut reader: R, exp: u32) -> io::Result<RadixFile<E>> {
+        let m = 1 << exp;
+
+        let alpha_g1 = crate::read_point(&mut reader)?;
+        let beta_g1 = crate::read_point(&mut reader)?;
+        let beta_g2 = crate::read_point(&mut reader)?;
+
+        Ok(RadixFile {
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            coeffs_g1: read_points(&mut reader, m)?,
+            coeffs_g2: read_points(&mut reader, m)?,
+            alpha_coeffs_g1: read_points(&mut reader, m)?,
+            beta_coeffs_g1: read_points(&mut reader, m)?,
+            h: read_points(&mut reader, m - 1)?,
+        })
+    }
+
+    /// Write the radix file, as `MPCParameters::new` reads it.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.alpha_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.beta_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.beta_g2.to_uncompressed().as_ref())?;
+        write_points(&mut writer, &self.coeffs_g1)?;
+        write_points(&mut writer, &self.coeffs_g2)?;
+        write_points(&mut writer, &self.alpha_coeffs_g1)?;
+        write_points(&mut writer, &self.beta_coeffs_g1)?;
+        write_points(&mut writer, &self.h)?;
+
+        Ok(())
+    }
+
+    /// Check with pairings that the file is internally consistent:
+    /// that its coefficients are the Lagrange basis of the domain
+    /// evaluated at some tau, in both groups, that the alpha and beta
+    /// coefficients and `h` are derived from the same tau, and that
+    /// beta agrees in both groups. This doesn't show that tau is the
+    /// one from the Powers of Tau ceremony; see `powersoftau` for
+    /// that.
+    ///
+    /// Each vector is checked with a random linear combination, so a
+    /// bad file is caught with overwhelming probability.
+    pub fn verify_structure(&self) -> Result<(), RadixError> {
+        let m = self.coeffs_g1.len();
+        let g1 = E::G1Affine::generator();
+        let g2 = E::G2Affine::generator();
+
+        if self.coeffs_g2.len() != m
+            || self.alpha_coeffs_g1.len() != m
+            || self.beta_coeffs_g1.len() != m
+            || self.h.len() != m.saturating_sub(1)
+            || !m.is_power_of_two()
+        {
+            return Err(RadixError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "inconsistent lengths",
+            )));
+        }
+
+        if !same_ratio((g1, self.beta_g1), (g2, self.beta_g2)) {
+            return Err(RadixError::BetaMismatch);
+        }
+
+        // With one point, the only Lagrange polynomial is 1
+        if m == 1 {
+            if self.coeffs_g1[0] != g1 || self.coeffs_g2[0] != g2 {
+                return Err(RadixError::NotLagrangeBasis);
+            }
+            if self.alpha_coeffs_g1[0] != self.alpha_g1 {
+                return Err(RadixError::AlphaCoeffsMismatch);
+            }
+            if self.beta_coeffs_g1[0] != self.beta_g1 {
+                return Err(RadixError::BetaCoeffsMismatch);
+            }
+
+            return Ok(());
+        }
+
+        // The domain's root of unity, as bellman computes it
+        let exp = m.trailing_zeros();
+        let mut omega = E::Fr::ROOT_OF_UNITY;
+        for _ in exp..E::Fr::S {
+            omega = omega.square();
+        }
+
+        // We do not need to be overly cautious of the RNG
+        // used for this check.
+        let rng = &mut rand::thread_rng();
+        let r: Vec<E::Fr> = (0..m).map(|_| E::Fr::random(&mut *rng)).collect();
+
+        let mut powers = Vec::with_capacity(m);
+        let mut r_powers = Vec::with_capacity(m);
+        let mut power = E::Fr::ONE;
+        for r in &r {
+            powers.push(power);
+            r_powers.push(*r * power);
+            power *= omega;
+        }
+
+        let coeffs = lincomb(&self.coeffs_g1, &r);
+        let coeffs_g2 = lincomb(&self.coeffs_g2, &r);
+        if !same_ratio((g1, coeffs), (g2, coeffs_g2)) {
+            return Err(RadixError::CoeffsMismatch);
+        }
+
+        if !same_ratio(
+            (self.alpha_g1, lincomb(&self.alpha_coeffs_g1, &r)),
+            (g2, coeffs_g2),
+        ) {
+            return Err(RadixError::AlphaCoeffsMismatch);
+        }
+        if !same_ratio(
+            (self.beta_g1, lincomb(&self.beta_coeffs_g1, &r)),
+            (g2, coeffs_g2),
+        ) {
+            return Err(RadixError::BetaCoeffsMismatch);
+        }
+
+        // x is the sum of omega^i L_i(x), so this is tau in G2
+        let tau_g2 = lincomb(&self.coeffs_g2, &powers);
+
+        // L_i(tau) (tau - omega^i) = omega^i (tau^m - 1) / m, and
+        // h[0] is tau^m - 1
+        let m_inv = E::Fr::from(m as u64).invert().unwrap();
+        let shifted = lincomb(&self.coeffs_g1, &r_powers).to_curve()
+            + self.h[0] * (r_powers.iter().sum::<E::Fr>() * m_inv);
+        if !same_ratio((coeffs, shifted.to_affine()), (g2, tau_g2)) {
+            return Err(RadixError::NotLagrangeBasis);
+        }
+
+        // Each element of h is tau times the one before it
+        let (s, sx) = merge_pairs(&self.h[..m - 2], &self.h[1..]);
+        if !same_ratio((s, sx), (g2, tau_g2)) {
+            return Err(RadixError::HMismatch);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+//! Checking radix files against the transcript of the Powers of Tau
+//! ceremony they came from.
+//!
+//! Each participant of that ceremony published the BLAKE2b hash of
+//! their response file, which starts with the hash of the challenge
+//! it responds to, followed by the accumulator of the powers of tau
+//! with compressed points. The radix files were derived from the
+//! last response, so anyone with that file can derive them again and
+//! compare; `verify_radix` does just that.
+
+use bellman::multicore::Worker;
+use bellman::{domain, SynthesisError};
+use group::prime::PrimeCurveAffine;
+use group::{Curve, UncompressedEncoding};
+use std::io::{self, Read};
+
+use super::{decode_points, RadixError, RadixFile};
+use crate::{HashReader, MPCEngine};
+
+/// Get the BLAKE2b hash of a challenge or response file, as the
+/// Powers of Tau transcript publishes it.
+pub fn file_hash<R: Read>(reader: R) -> io::Result<[u8; 64]> {
+    let mut reader = HashReader::new(reader);
+    io::copy(&mut reader, &mut io::sink())?;
+
+    Ok(reader.into_hash())
+}
+
+/// A point of the accumulator, for bellman's FFT.
+#[derive(Clone, Copy)]
+pub(crate) struct Projective<G>(pub(crate) G);
+
+impl<G: group::Group> domain::Group<G::Scalar> for Projective<G>
+where
+    G::Scalar: ff::PrimeField,
+{
+    fn group_zero() -> Self {
+        Projective(G::identity())
+    }
+    fn group_mul_assign(&mut self, by: &G::Scalar) {
+        self.0 *= *by;
+    }
+    fn group_add_assign(&mut self, other: &Self) {
+        self.0 += other.0;
+    }
+    fn group_sub_assign(&mut self, other: &Self) {
+        self.0 -= other.0;
+    }
+}
+
+/// Reads `len` points, compressed or not, checking that they're
+/// valid.
+fn read_points<G, R>(reader: &mut R, len: usize, compressed: bool) -> io::Result<Vec<G>>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    if !compressed {
+        return super::read_points(reader, len);
+    }
+
+    let size = G::Repr::default().as_ref().len();
+
+    let mut bytes = vec![0u8; len * size];
+    reader.read_exact(&mut bytes)?;
+
+    decode_points(&bytes, size, |bytes| {
+        let mut repr = G::Repr::default();
+        repr.as_mut().copy_from_slice(bytes);
+        Option::from(G::from_bytes(&repr))
+    })
+}
+
+/// Reads the first `keep` of `len` points, skipping the others.
+fn read_prefix<G, R>(
+    reader: &mut R,
+    len: usize,
+    keep: usize,
+    compressed: bool,
+) -> io::Result<Vec<G>>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    R: Read,
+{
+    let points = read_points(reader, keep, compressed)?;
+
+    let size = if compressed {
+        G::Repr::default().as_ref().len()
+    } else {
+        G::Uncompressed::default().as_ref().len()
+    };
+    let skip = ((len - keep) * size) as u64;
+    if io::copy(&mut reader.take(skip), &mut io::sink())? != skip {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(points)
+}
+
+/// Turns the powers of tau `tau^0 .. tau^(m - 1)` into the Lagrange
+/// basis of the domain of size `m`, evaluated at tau.
+fn lagrange<G: PrimeCurveAffine>(powers: &[G], worker: &Worker) -> Vec<G> {
+    let powers = powers.iter().map(|p| Projective(p.to_curve())).collect();
+
+    // The domain is exactly as large as the powers, so this can't fail
+    let mut domain = domain::EvaluationDomain::from_coeffs(powers).unwrap();
+    domain.ifft(worker);
+
+    let coeffs: Vec<G::Curve> = domain.into_coeffs().into_iter().map(|p| p.0).collect();
+    let mut affine = vec![G::identity(); coeffs.len()];
+    G::Curve::batch_normalize(&coeffs, &mut affine);
+    affine
+}
+
+/// The accumulator of a Powers of Tau ceremony of size `2^power`.
+pub struct Accumulator<E: MPCEngine> {
+    /// `tau^i` in G1, for `i < 2^(power + 1) - 1`.
+    pub tau_powers_g1: Vec<E::G1Affine>,
+    /// `tau^i` in G2, for `i < 2^power`.
+    pub tau_powers_g2: Vec<E::G2Affine>,
+    /// `alpha * tau^i` in G1, for `i < 2^power`.
+    pub alpha_tau_powers_g1: Vec<E::G1Affine>,
+    /// `beta * tau^i` in G1, for `i < 2^power`.
+    pub beta_tau_powers_g1: Vec<E::G1Affine>,
+    /// Beta in G2.
+    pub beta_g2: E::G2Affine,
+}
+
+impl<E: MPCEngine> Accumulator<E> {
+    /// Read the accumulator of a ceremony of size `2^power`, which
+    /// must be at the start of `reader`: a response file (with
+    /// compressed points) or a challenge file (without) must be past
+    /// its leading hash. The original ceremony had a `power` of 21.
+    pub fn read<R: Read>(reader: R, power: u32, compressed: bool) -> io::Result<Self> {
+        Accumulator::read_prefix(reader, power, compressed, 1 << power)
+    }
+
+    /// Like `read`, keeping only the powers needed for radix files
+    /// of domains up to size `m`. The rest are skipped over without
+    /// being decoded, which is far quicker for small domains.
+    pub fn read_prefix<R: Read>(
+        mut reader: R,
+        power: u32,
+        compressed: bool,
+        m: usize,
+    ) -> io::Result<Self> {
+        let len = 1 << power;
+        let m = m.min(len);
+
+        Ok(Accumulator {
+            tau_powers_g1: read_prefix(&mut reader, 2 * len - 1, 2 * m - 1, compressed)?,
+            tau_powers_g2: read_prefix(&mut reader, len, m, compressed)?,
+            alpha_tau_powers_g1: read_prefix(&mut reader, len, m, compressed)?,
+            beta_tau_powers_g1: read_prefix(&mut reader, len, m, compressed)?,
+            beta_g2: read_points(&mut reader, 1, compressed)?[0],
+        })
+    }
+
+    /// Derive the radix file for a domain of size `2^exp`, as the
+    /// ceremony's `prepare_phase2` did. Fails if the accumulator
+    /// doesn't have enough powers of tau for it.
+    pub fn radix(&self, exp: u32) -> Result<RadixFile<E>, SynthesisError> {
+        let m = 1 << exp;
+        if m > self.tau_powers_g2.len() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        let worker = Worker::new();
+
+        // tau^i * (tau^m - 1) = tau^(i + m) - tau^i
+        let h: Vec<E::G1> = (0..m - 1)
+            .map(|i| self.tau_powers_g1[i + m].to_curve() - self.tau_powers_g1[i])
+            .collect();
+        let mut h_affine = vec![E::G1Affine::identity(); h.len()];
+        E::G1::batch_normalize(&h, &mut h_affine);
+
+        Ok(RadixFile {
+            alpha_g1: self.alpha_tau_powers_g1[0],
+            beta_g1: self.beta_tau_powers_g1[0],
+            beta_g2: self.beta_g2,
+            coeffs_g1: lagrange(&self.tau_powers_g1[..m], &worker),
+            coeffs_g2: lagrange(&self.tau_powers_g2[..m], &worker),
+            alpha_coeffs_g1: lagrange(&self.alpha_tau_powers_g1[..m], &worker),
+            beta_coeffs_g1: lagrange(&self.beta_tau_powers_g1[..m], &worker),
+            h: h_affine,
+        })
+    }
+}
+
+/// Check that `radix` was derived from the response file in
+/// `response`, of a ceremony of size `2^power`, and that the response
+/// file has the hash `response_hash`, as published in the ceremony's
+/// transcript. The response file is read once, hashing it on the
+/// way.
+pub fn verify_radix<E: MPCEngine, R: Read>(
+    response: R,
+    power: u32,
+    response_hash: &[u8; 64],
+    radix: &RadixFile<E>,
+) -> Result<(), RadixError> {
+    let mut reader = HashReader::new(response);
+
+    let mut challenge_hash = [0u8; 64];
+    reader.read_exact(&mut challenge_hash)?;
+    let m = radix.coeffs_g1.len();
+    let accumulator = Accumulator::<E>::read_prefix(&mut reader, power, true, m)?;
+
+    // The participant's public key follows, which is hashed too
+    io::copy(&mut reader, &mut io::sink())?;
+    if reader.into_hash() != *response_hash {
+        return Err(RadixError::ResponseHashMismatch);
+    }
+
+    let exp = m.trailing_zeros();
+    match accumulator.radix(exp) {
+        Ok(expected) if expected == *radix => Ok(()),
+        _ => Err(RadixError::NotFromResponse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{insecure_accumulator, insecure_radix};
+    use bls12_381::Bls12;
+
+    #[test]
+    fn radix_matches_a_known_tau() {
+        for exp in 0..5 {
+            let accumulator = insecure_accumulator::<Bls12>(exp, [exp as u8; 32]);
+            let radix = accumulator.radix(exp).unwrap();
+            assert!(radix == insecure_radix(exp, [exp as u8; 32]));
+        }
+    }
+}
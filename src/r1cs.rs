@@ -0,0 +1,319 @@
+//! Reading circuits from the circom `.r1cs` format, enabled by the
+//! `snarkjs` feature.
+//!
+//! Like a zkey file, an r1cs file is a sequence of sections, each
+//! with a little-endian id and length. Field elements are written
+//! little-endian, in normal (not Montgomery) form. Wire 0 is the
+//! constant one, followed by the public outputs, the public inputs
+//! and then every private wire.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+use byteorder::{LittleEndian, ReadBytesExt};
+use ff::PrimeField;
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use crate::{phase1, MPCEngine, MPCParameters, Phase2Error};
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// The modulus of `F`, as little-endian bytes.
+fn modulus<F: PrimeField>() -> Vec<u8> {
+    let hex = F::MODULUS.trim_start_matches("0x");
+    let mut bytes: Vec<u8> = (0..hex.len())
+        .rev()
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i.saturating_sub(1)..=i], 16).unwrap())
+        .collect();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    bytes
+}
+
+/// Reads a little-endian field element of `n8` bytes.
+fn read_fr<F: PrimeField, R: Read>(reader: &mut R, n8: usize) -> io::Result<F> {
+    let mut bytes = vec![0u8; n8];
+    reader.read_exact(&mut bytes)?;
+
+    // Horner's rule over 64-bit limbs, from the most significant
+    let shift = F::from(u64::MAX) + F::ONE;
+    let mut fr = F::ZERO;
+    for limb in bytes.chunks(8).rev() {
+        let mut le = [0u8; 8];
+        le[..limb.len()].copy_from_slice(limb);
+        fr = fr * shift + F::from(u64::from_le_bytes(le));
+    }
+
+    Ok(fr)
+}
+
+type Lc<F> = Vec<(usize, F)>;
+
+/// The constraints of an r1cs file, as a circuit.
+struct R1cs<F: PrimeField> {
+    num_wires: usize,
+    /// The public wires, excluding the constant one.
+    num_public: usize,
+    constraints: Vec<(Lc<F>, Lc<F>, Lc<F>)>,
+}
+
+impl<F: PrimeField> R1cs<F> {
+    fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != b"r1cs" {
+            return Err(invalid("not an r1cs file"));
+        }
+        reader.read_u32::<LittleEndian>()?;
+        let num_sections = reader.read_u32::<LittleEndian>()?;
+
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let id = reader.read_u32::<LittleEndian>()?;
+            let size = reader.read_u64::<LittleEndian>()?;
+
+            let mut section = vec![];
+            (&mut reader).take(size).read_to_end(&mut section)?;
+            if section.len() as u64 != size {
+                return Err(invalid("truncated section"));
+            }
+            sections.insert(id, section);
+        }
+        let mut section = |id| sections.remove(&id).ok_or(invalid("missing section"));
+
+        let header = section(1)?;
+        let mut h = &header[..];
+        let n8 = h.read_u32::<LittleEndian>()? as usize;
+        let mut prime = vec![0u8; n8];
+        h.read_exact(&mut prime)?;
+        while prime.last() == Some(&0) {
+            prime.pop();
+        }
+        if prime != modulus::<F>() {
+            return Err(invalid("the r1cs file is over a different field"));
+        }
+
+        let num_wires = h.read_u32::<LittleEndian>()? as usize;
+        let num_outputs = h.read_u32::<LittleEndian>()? as usize;
+        let num_public_inputs = h.read_u32::<LittleEndian>()? as usize;
+        let _num_private_inputs = h.read_u32::<LittleEndian>()?;
+        let _num_labels = h.read_u64::<LittleEndian>()?;
+        let num_constraints = h.read_u32::<LittleEndian>()?;
+
+        let num_public = num_outputs + num_public_inputs;
+        if num_wires == 0 || num_public >= num_wires {
+            return Err(invalid("inconsistent wire counts"));
+        }
+
+        let body = section(2)?;
+        let mut c = &body[..];
+        let mut read_lc = || -> io::Result<Lc<F>> {
+            let terms = c.read_u32::<LittleEndian>()?;
+            (0..terms)
+                .map(|_| {
+                    let wire = c.read_u32::<LittleEndian>()? as usize;
+                    if wire >= num_wires {
+                        return Err(invalid("unknown wire"));
+                    }
+
+                    Ok((wire, read_fr(&mut c, n8)?))
+                })
+                .collect()
+        };
+
+        // We don't trust the count to preallocate
+        let mut constraints = vec![];
+        for _ in 0..num_constraints {
+            constraints.push((read_lc()?, read_lc()?, read_lc()?));
+        }
+        if !c.is_empty() {
+            return Err(invalid("trailing data after the constraints"));
+        }
+
+        Ok(R1cs {
+            num_wires,
+            num_public,
+            constraints,
+        })
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for R1cs<F> {
+    fn synthesize<CS: ConstraintSystem<F>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut wires = vec![CS::one()];
+        for _ in 0..self.num_public {
+            wires.push(cs.alloc_input(|| "", || Err(SynthesisError::AssignmentMissing))?);
+        }
+        for _ in self.num_public + 1..self.num_wires {
+            wires.push(cs.alloc(|| "", || Err(SynthesisError::AssignmentMissing))?);
+        }
+
+        let wires = &wires;
+        let lc = |terms: Lc<F>| {
+            move |lc: LinearCombination<F>| {
+                terms
+                    .into_iter()
+                    .fold(lc, |lc, (wire, coeff)| lc + (coeff, wires[wire]))
+            }
+        };
+        for (a, b, c) in self.constraints {
+            cs.enforce(|| "", lc(a), lc(b), lc(c));
+        }
+
+        Ok(())
+    }
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Like `new_from_source`, for a circuit read from a circom
+    /// `.r1cs` file instead of a bellman `Circuit`. The file must be
+    /// over the scalar field of `E`.
+    pub fn new_from_r1cs<R: Read>(
+        reader: R,
+        radix: phase1::RadixSource,
+    ) -> Result<MPCParameters<E>, Phase2Error> {
+        let circuit = R1cs::<E::Fr>::read(reader)?;
+        MPCParameters::new_from_source(circuit, radix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::insecure_radix;
+    use bls12_381::{Bls12, Scalar};
+    use byteorder::WriteBytesExt;
+
+    /// The modulus of the BLS12-381 scalar field, little-endian.
+    const MODULUS: [u8; 32] = [
+        0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0x02, 0xa4, 0xbd,
+        0x53, 0x05, 0xd8, 0xa1, 0x09, 0x08, 0xd8, 0x39, 0x33, 0x48, 0x7d, 0x9d, 0x29, 0x53, 0xa7,
+        0xed, 0x73,
+    ];
+
+    /// -1, little-endian.
+    fn minus_one() -> [u8; 32] {
+        let mut bytes = MODULUS;
+        bytes[0] -= 1;
+        bytes
+    }
+
+    fn small(n: u8) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        bytes[0] = n;
+        bytes
+    }
+
+    fn write_lc(out: &mut Vec<u8>, terms: &[(u32, [u8; 32])]) {
+        out.write_u32::<LittleEndian>(terms.len() as u32).unwrap();
+        for (wire, coeff) in terms {
+            out.write_u32::<LittleEndian>(*wire).unwrap();
+            out.extend_from_slice(coeff);
+        }
+    }
+
+    /// An r1cs file written out by hand: wire 1 is a public output,
+    /// wire 2 a private input and wire 3 an intermediate, with
+    ///
+    /// ```text
+    /// x * x = t
+    /// (x - 1) * x = t - x
+    /// (t + 2x) * 1 = out
+    /// ```
+    fn fixture() -> Vec<u8> {
+        let mut header = vec![];
+        header.write_u32::<LittleEndian>(32).unwrap();
+        header.extend_from_slice(&MODULUS);
+        header.write_u32::<LittleEndian>(4).unwrap(); // wires
+        header.write_u32::<LittleEndian>(1).unwrap(); // public outputs
+        header.write_u32::<LittleEndian>(0).unwrap(); // public inputs
+        header.write_u32::<LittleEndian>(1).unwrap(); // private inputs
+        header.write_u64::<LittleEndian>(4).unwrap(); // labels
+        header.write_u32::<LittleEndian>(3).unwrap(); // constraints
+
+        let mut constraints = vec![];
+        write_lc(&mut constraints, &[(2, small(1))]);
+        write_lc(&mut constraints, &[(2, small(1))]);
+        write_lc(&mut constraints, &[(3, small(1))]);
+
+        write_lc(&mut constraints, &[(2, small(1)), (0, minus_one())]);
+        write_lc(&mut constraints, &[(2, small(1))]);
+        write_lc(&mut constraints, &[(3, small(1)), (2, minus_one())]);
+
+        write_lc(&mut constraints, &[(3, small(1)), (2, small(2))]);
+        write_lc(&mut constraints, &[(0, small(1))]);
+        write_lc(&mut constraints, &[(1, small(1))]);
+
+        // Wire to label map, which we ignore
+        let mut labels = vec![];
+        for label in 0..4 {
+            labels.write_u64::<LittleEndian>(label).unwrap();
+        }
+
+        let mut file = b"r1cs".to_vec();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u32::<LittleEndian>(3).unwrap();
+        for (id, section) in [(1, header), (2, constraints), (3, labels)] {
+            file.write_u32::<LittleEndian>(id).unwrap();
+            file.write_u64::<LittleEndian>(section.len() as u64)
+                .unwrap();
+            file.extend_from_slice(&section);
+        }
+        file
+    }
+
+    /// The circuit of `fixture`, written for bellman.
+    struct Fixture;
+
+    impl Circuit<Scalar> for Fixture {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let out = cs.alloc_input(|| "out", || Err(SynthesisError::AssignmentMissing))?;
+            let x = cs.alloc(|| "x", || Err(SynthesisError::AssignmentMissing))?;
+            let t = cs.alloc(|| "t", || Err(SynthesisError::AssignmentMissing))?;
+
+            cs.enforce(|| "x * x", |lc| lc + x, |lc| lc + x, |lc| lc + t);
+            cs.enforce(
+                || "(x - 1) * x",
+                |lc| lc + x - CS::one(),
+                |lc| lc + x,
+                |lc| lc + t - x,
+            );
+            cs.enforce(
+                || "t + 2x",
+                |lc| lc + t + (Scalar::from(2), x),
+                |lc| lc + CS::one(),
+                |lc| lc + out,
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn r1cs_matches_bellman() {
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(3, [5; 32])
+            .write(&mut radix)
+            .unwrap();
+
+        let from_r1cs =
+            MPCParameters::<Bls12>::new_from_r1cs(&fixture()[..], RadixSource::Bytes(&radix))
+                .unwrap();
+        let from_bellman =
+            MPCParameters::<Bls12>::new_from_source(Fixture, RadixSource::Bytes(&radix)).unwrap();
+        assert_eq!(from_r1cs.cs_hash, from_bellman.cs_hash);
+
+        // A circuit over another field is refused
+        let mut other = fixture();
+        other[29] ^= 1;
+        let err = R1cs::<Scalar>::read(&other[..]).err().unwrap();
+        assert_eq!(err.to_string(), "the r1cs file is over a different field");
+    }
+}
@@ -0,0 +1,46 @@
+//! Generating the `phase1radix2m{exp}` files that `MPCParameters::new`
+//! reads, from the output of the Powers of Tau ceremony, without the
+//! ceremony's own `prepare_phase2` tool.
+
+use bls12_381::Bls12;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use crate::phase1::powersoftau::Accumulator;
+use crate::{phase1, MPCEngine, Phase2Error};
+
+/// Write the radix file for a domain of size `2^exp` to `out`, given
+/// the final response file of the original BLS12-381 Powers of Tau
+/// ceremony (of size 2^21) in `accumulator`. Only the powers the
+/// domain needs are decoded.
+pub fn prepare<R: Read, W: Write>(accumulator: R, exp: u32, out: W) -> Result<(), Phase2Error> {
+    prepare_with_power::<Bls12, R, W>(accumulator, phase1::MAX_EXP, exp, out)
+}
+
+/// Like `prepare`, for the response file of a ceremony of size
+/// `2^power` over `E`.
+pub fn prepare_with_power<E, R, W>(
+    accumulator: R,
+    power: u32,
+    exp: u32,
+    out: W,
+) -> Result<(), Phase2Error>
+where
+    E: MPCEngine,
+    R: Read,
+    W: Write,
+{
+    let mut reader = BufReader::with_capacity(1024 * 1024, accumulator);
+
+    // The hash of the challenge the response was made for
+    let mut challenge_hash = [0u8; 64];
+    reader.read_exact(&mut challenge_hash)?;
+
+    let accumulator = Accumulator::<E>::read_prefix(reader, power, true, 1 << exp)?;
+    let radix = accumulator.radix(exp)?;
+
+    let mut writer = BufWriter::with_capacity(1024 * 1024, out);
+    radix.write(&mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! How the transcript of contributions is mapped into G2.
+//!
+//! Each contribution proves knowledge of its delta with a signature
+//! over a point `r` in G2 derived from the hash of the transcript up
+//! to it. Every participant and verifier of a ceremony has to derive
+//! `r` the same way, so the scheme is chosen when the ceremony starts
+//! and set on the parameters with
+//! `MPCParameters::with_transcript_scheme`. The transcript itself is
+//! always hashed with BLAKE2b-512, which is what contributions store.
+//!
+//! The `SchemeId` of the scheme is written in the header of the
+//! parameters (see `MPCParameters::write`), so that whoever reads
+//! them, contributes to them or verifies them uses the same one.
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use pairing::group::Group;
+use pairing::Engine;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::MPCEngine;
+
+/// Identifies a `TranscriptScheme` in serialized parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemeId {
+    /// `LegacyScheme`.
+    Legacy,
+    /// `Rfc9380Scheme` with the domain separation tag `dst`.
+    Rfc9380 {
+        /// The domain separation tag, of at most 255 bytes.
+        dst: Vec<u8>,
+    },
+    /// A scheme of your own, named by up to 255 bytes of your
+    /// choosing. Parameters that use one can only be read with
+    /// `MPCParameters::read_with_scheme`.
+    Custom(Vec<u8>),
+}
+
+impl SchemeId {
+    /// Serialize this id: a byte for the kind of scheme, then the
+    /// length and bytes of the tag or name, if it has one.
+    pub(crate) fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let (kind, bytes) = match self {
+            SchemeId::Legacy => return writer.write_u8(0),
+            SchemeId::Rfc9380 { dst } => (1, dst),
+            SchemeId::Custom(name) => (2, name),
+        };
+        let len = u8::try_from(bytes.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the transcript scheme's id is longer than 255 bytes",
+            )
+        })?;
+
+        writer.write_u8(kind)?;
+        writer.write_u8(len)?;
+        writer.write_all(bytes)
+    }
+
+    /// Deserialize an id.
+    pub(crate) fn read<R: Read>(mut reader: R) -> io::Result<SchemeId> {
+        let kind = reader.read_u8()?;
+        if kind == 0 {
+            return Ok(SchemeId::Legacy);
+        }
+
+        let mut bytes = vec![0u8; reader.read_u8()? as usize];
+        reader.read_exact(&mut bytes)?;
+        match kind {
+            1 => Ok(SchemeId::Rfc9380 { dst: bytes }),
+            2 => Ok(SchemeId::Custom(bytes)),
+            k => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown transcript scheme {}", k),
+            )),
+        }
+    }
+
+    /// The length of the serialized id, in bytes.
+    #[cfg(any(feature = "mmap", feature = "snarkjs"))]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            SchemeId::Legacy => 1,
+            SchemeId::Rfc9380 { dst: bytes } | SchemeId::Custom(bytes) => 2 + bytes.len(),
+        }
+    }
+
+    /// The scheme this identifies, over `E`. Fails for custom schemes,
+    /// which only their users can make, and for schemes that aren't
+    /// defined over `E` or not compiled in.
+    pub(crate) fn scheme<E: MPCEngine>(&self) -> io::Result<Arc<dyn TranscriptScheme<E>>> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        match self {
+            SchemeId::Legacy => Ok(Arc::new(LegacyScheme)),
+            #[cfg(feature = "rfc9380")]
+            SchemeId::Rfc9380 { dst } => {
+                // Only defined over BLS12-381, which `E` has to be
+                let scheme: Arc<dyn TranscriptScheme<bls12_381::Bls12>> =
+                    Arc::new(Rfc9380Scheme::new(dst.clone()));
+                (Box::new(scheme) as Box<dyn std::any::Any>)
+                    .downcast::<Arc<dyn TranscriptScheme<E>>>()
+                    .map(|scheme| *scheme)
+                    .map_err(|_| invalid("RFC 9380 hashing is only defined for BLS12-381"))
+            }
+            #[cfg(not(feature = "rfc9380"))]
+            SchemeId::Rfc9380 { .. } => Err(invalid(
+                "the parameters use RFC 9380 hashing, which needs the rfc9380 feature",
+            )),
+            SchemeId::Custom(_) => Err(invalid(
+                "the parameters use a custom transcript scheme, see read_with_scheme",
+            )),
+        }
+    }
+}
+
+/// Maps the BLAKE2b-512 hash of a transcript to a point in G2.
+///
+/// What works on `MPCParameters` uses the scheme set on them, and
+/// what works on serialized parameters uses the one in their header.
+/// Transcripts on their own don't record it, so the
+/// `verify_transcript_*_with_scheme` functions take it.
+pub trait TranscriptScheme<E: Engine>: Send + Sync {
+    /// The point `r` for the transcript hash `transcript`.
+    fn hash_to_g2(&self, transcript: &[u8; 64]) -> E::G2;
+
+    /// What identifies this scheme in serialized parameters. Schemes
+    /// of your own should return a `SchemeId::Custom` that names
+    /// them, which this does with an empty name.
+    fn id(&self) -> SchemeId {
+        SchemeId::Custom(vec![])
+    }
+}
+
+/// The scheme of every ceremony before schemes could be chosen, and
+/// the default: the point is sampled with `group`'s `random` from a
+/// ChaCha RNG seeded with the first 32 bytes of the transcript hash.
+/// It's only specified by these implementations, so it's hard to
+/// reproduce elsewhere; it's kept to verify existing transcripts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LegacyScheme;
+
+impl<E: Engine> TranscriptScheme<E> for LegacyScheme {
+    fn hash_to_g2(&self, transcript: &[u8; 64]) -> E::G2 {
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&transcript[..32]);
+        E::G2::random(&mut ChaChaRng::from_seed(seed))
+    }
+
+    fn id(&self) -> SchemeId {
+        SchemeId::Legacy
+    }
+}
+
+/// Hashing to G2 of BLS12-381 as specified by RFC 9380, with the
+/// `BLS12381G2_XMD:SHA-256_SSWU_RO_` suite and a domain separation
+/// tag of the ceremony's choosing, so that `r` can be recomputed by
+/// any implementation of the standard.
+#[cfg(feature = "rfc9380")]
+#[derive(Clone, Debug)]
+pub struct Rfc9380Scheme {
+    dst: Vec<u8>,
+}
+
+#[cfg(feature = "rfc9380")]
+impl Rfc9380Scheme {
+    /// The domain separation tag of `default()`.
+    pub const DEFAULT_DST: &'static [u8] = b"PHASE2-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+    /// The scheme with the domain separation tag `dst`, which should
+    /// name the ceremony (see RFC 9380, section 3.1).
+    pub fn new(dst: impl Into<Vec<u8>>) -> Self {
+        Rfc9380Scheme { dst: dst.into() }
+    }
+
+    /// The domain separation tag.
+    pub fn dst(&self) -> &[u8] {
+        &self.dst
+    }
+}
+
+#[cfg(feature = "rfc9380")]
+impl Default for Rfc9380Scheme {
+    fn default() -> Self {
+        Rfc9380Scheme::new(Self::DEFAULT_DST)
+    }
+}
+
+#[cfg(feature = "rfc9380")]
+impl TranscriptScheme<bls12_381::Bls12> for Rfc9380Scheme {
+    fn hash_to_g2(&self, transcript: &[u8; 64]) -> bls12_381::G2Projective {
+        use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+
+        <bls12_381::G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(
+            transcript, &self.dst,
+        )
+    }
+
+    fn id(&self) -> SchemeId {
+        SchemeId::Rfc9380 {
+            dst: self.dst.clone(),
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Hashes of each section of serialized parameters, so that a
+//! coordinator receiving a large contribution in parts can tell which
+//! part was corrupted instead of rejecting the whole file.
+//!
+//! The participant sends a `SectionManifest` along with the
+//! parameters, and the coordinator checks what it received against
+//! it with `MPCParameters::verify_sections`. The manifest records the
+//! length of each section as well as its hash, so a corrupted length
+//! prefix doesn't throw off the sections after it.
+
+use blake2_rfc::blake2b::Blake2b;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use pairing::group::UncompressedEncoding;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::{header, write_contributions_len, FormatVersion, MPCEngine, MPCParameters};
+
+/// A section of serialized parameters, in the order they're written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Section {
+    /// The header, naming the curve, layout and transcript scheme.
+    Header,
+    /// The verifying key.
+    Vk,
+    /// The H query.
+    H,
+    /// The L query.
+    L,
+    /// The A query.
+    A,
+    /// The B query, in G1.
+    BG1,
+    /// The B query, in G2.
+    BG2,
+    /// The `cs_hash`, the circuit fingerprint in the layouts that
+    /// have one, and the contributions.
+    Contributions,
+}
+
+impl Section {
+    /// Every section, in the order they're written.
+    pub const ALL: [Section; 8] = [
+        Section::Header,
+        Section::Vk,
+        Section::H,
+        Section::L,
+        Section::A,
+        Section::BG1,
+        Section::BG2,
+        Section::Contributions,
+    ];
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Section::Header => "header",
+            Section::Vk => "vk",
+            Section::H => "h",
+            Section::L => "l",
+            Section::A => "a",
+            Section::BG1 => "b_g1",
+            Section::BG2 => "b_g2",
+            Section::Contributions => "contributions",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Why `MPCParameters::verify_sections` failed.
+#[derive(Debug)]
+pub enum SectionError {
+    /// An I/O error.
+    Io(io::Error),
+    /// These sections don't match the manifest, or were cut short.
+    Corrupted(Vec<Section>),
+}
+
+impl fmt::Display for SectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SectionError::Io(e) => write!(f, "I/O error: {}", e),
+            SectionError::Corrupted(sections) => {
+                let names: Vec<String> = sections.iter().map(|s| s.to_string()).collect();
+                write!(f, "corrupted sections: {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SectionError::Io(e) => Some(e),
+            SectionError::Corrupted(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SectionError {
+    fn from(e: io::Error) -> SectionError {
+        SectionError::Io(e)
+    }
+}
+
+/// Hashes a section with BLAKE2b, counting its bytes.
+struct SectionHasher {
+    hasher: Blake2b,
+    len: u64,
+}
+
+impl SectionHasher {
+    fn new() -> Self {
+        SectionHasher {
+            hasher: Blake2b::new(64),
+            len: 0,
+        }
+    }
+
+    fn finish(self) -> (u64, [u8; 64]) {
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(self.hasher.finalize().as_ref());
+        (self.len, hash)
+    }
+}
+
+impl Write for SectionHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hasher.update(buf);
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The length and BLAKE2b hash of each section of serialized
+/// parameters, as `MPCParameters::hash_sections` computes them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionManifest {
+    sections: [(u64, [u8; 64]); 8],
+}
+
+impl SectionManifest {
+    /// The length of `section`, in bytes.
+    pub fn len(&self, section: Section) -> u64 {
+        self.sections[section as usize].0
+    }
+
+    /// The hash of `section`.
+    pub fn hash(&self, section: Section) -> &[u8; 64] {
+        &self.sections[section as usize].1
+    }
+
+    /// The length of the parameters, in bytes.
+    pub fn total_len(&self) -> u64 {
+        self.sections.iter().map(|(len, _)| len).sum()
+    }
+
+    /// Serialize this manifest.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (len, hash) in &self.sections {
+            writer.write_u64::<BigEndian>(*len)?;
+            writer.write_all(hash)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a manifest.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<SectionManifest> {
+        let mut sections = [(0, [0u8; 64]); 8];
+        for (len, hash) in sections.iter_mut() {
+            *len = reader.read_u64::<BigEndian>()?;
+            reader.read_exact(hash)?;
+        }
+
+        Ok(SectionManifest { sections })
+    }
+}
+
+fn write_query<G: UncompressedEncoding, W: Write>(writer: &mut W, query: &[G]) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(query.len() as u32)?;
+    for p in query {
+        writer.write_all(p.to_uncompressed().as_ref())?;
+    }
+
+    Ok(())
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Hash each section of these parameters as `write` serializes
+    /// them, for the coordinator to check an upload against with
+    /// `verify_sections`.
+    pub fn hash_sections(&self) -> SectionManifest {
+        self.hash_sections_with_version(FormatVersion::V1)
+            .expect("the V1 layout doesn't need the circuit fingerprint")
+    }
+
+    /// Like `hash_sections`, for parameters serialized with
+    /// `write_with_version`. Fails if `version` needs the circuit
+    /// fingerprint and it isn't known, as `write_with_version` does.
+    pub fn hash_sections_with_version(
+        &self,
+        version: FormatVersion,
+    ) -> io::Result<SectionManifest> {
+        let mut sections = [(0, [0u8; 64]); 8];
+        for (section, entry) in Section::ALL.iter().zip(sections.iter_mut()) {
+            let mut w = SectionHasher::new();
+            self.write_section(*section, version, &mut w)?;
+            *entry = w.finish();
+        }
+
+        Ok(SectionManifest { sections })
+    }
+
+    /// Writes one section, as `write_with_version` does.
+    fn write_section<W: Write>(
+        &self,
+        section: Section,
+        version: FormatVersion,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let params = &self.params;
+        match section {
+            Section::Header => header::write_header::<E, _>(
+                writer,
+                version,
+                &self.cs_hash,
+                self.transcript_scheme.id(),
+            ),
+            Section::Vk => params.vk.write(writer),
+            Section::H => write_query(writer, &params.h),
+            Section::L => write_query(writer, &params.l),
+            Section::A => write_query(writer, &params.a),
+            Section::BG1 => write_query(writer, &params.b_g1),
+            Section::BG2 => write_query(writer, &params.b_g2),
+            Section::Contributions => {
+                writer.write_all(&self.cs_hash)?;
+                write_contributions_len(
+                    writer,
+                    version,
+                    self.circuit_fingerprint.as_ref(),
+                    self.contributions.len(),
+                )?;
+                for pubkey in &self.contributions {
+                    pubkey.write(&mut *writer)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Check serialized parameters, e.g. an upload reassembled from
+    /// its parts, against the `manifest` of the parameters that were
+    /// sent. The sections are located by the lengths in the manifest,
+    /// so each is checked on its own; the error lists every section
+    /// that doesn't match, including those that were cut short.
+    /// Anything after the last section is ignored.
+    ///
+    /// This only shows that the parameters are the ones that were
+    /// sent, not that they're valid.
+    pub fn verify_sections<R: Read>(
+        mut reader: R,
+        manifest: &SectionManifest,
+    ) -> Result<(), SectionError> {
+        let mut corrupted = vec![];
+        for section in Section::ALL {
+            let mut w = SectionHasher::new();
+            let len = manifest.len(section);
+            io::copy(&mut (&mut reader).take(len), &mut w)?;
+
+            if w.finish() != (len, *manifest.hash(section)) {
+                corrupted.push(section);
+            }
+        }
+
+        if corrupted.is_empty() {
+            Ok(())
+        } else {
+            Err(SectionError::Corrupted(corrupted))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::{insecure_radix, RandomCircuit};
+    use bls12_381::Bls12;
+
+    fn params() -> MPCParameters<Bls12> {
+        let circuit = RandomCircuit {
+            constraints: 6,
+            inputs: 1,
+            aux: 4,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(3, [3; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+        params
+    }
+
+    #[test]
+    fn written_parameters_match_their_sections() {
+        let params = params();
+        let mut file = vec![];
+        params.write(&mut file).unwrap();
+
+        let manifest = params.hash_sections();
+        assert_eq!(manifest.total_len(), file.len() as u64);
+        MPCParameters::<Bls12>::verify_sections(&file[..], &manifest).unwrap();
+
+        for version in [FormatVersion::V2, FormatVersion::V3] {
+            let mut file = vec![];
+            params.write_with_version(&mut file, version).unwrap();
+
+            let manifest = params.hash_sections_with_version(version).unwrap();
+            assert_eq!(manifest.total_len(), file.len() as u64);
+            MPCParameters::<Bls12>::verify_sections(&file[..], &manifest).unwrap();
+        }
+    }
+
+    #[test]
+    fn corrupted_sections_are_listed() {
+        let params = params();
+        let manifest = params.hash_sections();
+        let mut file = vec![];
+        params.write(&mut file).unwrap();
+
+        // The start of the L query, past its length
+        let l: u64 = [Section::Header, Section::Vk, Section::H]
+            .iter()
+            .map(|s| manifest.len(*s))
+            .sum();
+        let mut flipped = file.clone();
+        flipped[l as usize + 10] ^= 1;
+        assert!(matches!(
+            MPCParameters::<Bls12>::verify_sections(&flipped[..], &manifest),
+            Err(SectionError::Corrupted(sections)) if sections == vec![Section::L]
+        ));
+
+        // Cut off in the middle of the A query
+        let a = l + manifest.len(Section::L);
+        let cut = &file[..(a + manifest.len(Section::A) / 2) as usize];
+        assert!(matches!(
+            MPCParameters::<Bls12>::verify_sections(cut, &manifest),
+            Err(SectionError::Corrupted(sections))
+                if sections == vec![Section::A, Section::BG1, Section::BG2, Section::Contributions]
+        ));
+    }
+}
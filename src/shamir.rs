@@ -0,0 +1,131 @@
+//! Shamir secret sharing of contribution seeds, enabled by the
+//! `shamir` feature, so that no single person holds the entropy of a
+//! contribution.
+//!
+//! Each byte of the seed is shared independently over GF(2^8), with
+//! the AES reduction polynomial.
+
+use rand::Rng;
+use zeroize::Zeroizing;
+
+use crate::{MPCEngine, MPCParameters, Phase2Error};
+
+/// One share of a 32-byte seed. Shares are evaluations of random
+/// polynomials at `x`, which must be nonzero and distinct across the
+/// shares of one seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Share {
+    pub x: u8,
+    pub y: [u8; 32],
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        // Branchless, as the shares are secret
+        product ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1b & (a >> 7).wrapping_neg());
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_inv(a: u8) -> u8 {
+    // a^254 = a^-1, as the multiplicative group has order 255
+    let mut result = 1;
+    let mut power = a;
+    for bit in 0..8 {
+        if (254 >> bit) & 1 == 1 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+    }
+    result
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which
+/// recover it with `combine`.
+pub fn split<R: Rng>(
+    secret: &[u8; 32],
+    threshold: usize,
+    count: usize,
+    rng: &mut R,
+) -> Result<Vec<Share>, Phase2Error> {
+    if threshold == 0 || threshold > count || count > 255 {
+        return Err(Phase2Error::InvalidShares);
+    }
+
+    // The coefficients of each byte's polynomial, constant term
+    // first. Any of them give the secret away with the shares.
+    let coeffs: Zeroizing<Vec<[u8; 32]>> = Zeroizing::new(
+        (0..threshold)
+            .map(|i| if i == 0 { *secret } else { rng.gen() })
+            .collect(),
+    );
+
+    let shares = (1..=count as u8)
+        .map(|x| {
+            let mut y = [0u8; 32];
+            for (i, y) in y.iter_mut().enumerate() {
+                // Horner's rule
+                for c in coeffs.iter().rev() {
+                    *y = gf_mul(*y, x) ^ c[i];
+                }
+            }
+            Share { x, y }
+        })
+        .collect();
+
+    Ok(shares)
+}
+
+/// Recover a secret from at least `threshold` of its shares. Only
+/// the first `threshold` shares are used.
+pub fn combine(shares: &[Share], threshold: usize) -> Result<[u8; 32], Phase2Error> {
+    if threshold == 0 || shares.len() < threshold {
+        return Err(Phase2Error::NotEnoughShares {
+            threshold,
+            got: shares.len(),
+        });
+    }
+
+    let shares = &shares[..threshold];
+
+    for (i, a) in shares.iter().enumerate() {
+        if a.x == 0 || shares[..i].iter().any(|b| b.x == a.x) {
+            return Err(Phase2Error::InvalidShares);
+        }
+    }
+
+    // Lagrange interpolation at zero, where subtraction is xor
+    let mut secret = [0u8; 32];
+    for (i, share) in shares.iter().enumerate() {
+        let mut basis = 1;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_mul(other.x, gf_inv(other.x ^ share.x)));
+            }
+        }
+
+        for (s, y) in secret.iter_mut().zip(share.y.iter()) {
+            *s ^= gf_mul(basis, *y);
+        }
+    }
+
+    Ok(secret)
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Contributes with the seed recovered from `threshold` of the
+    /// `shares` (see `combine`), as `contribute_with_seed` would. The
+    /// seed is wiped once it's used, as by `contribute_and_wipe`.
+    pub fn contribute_from_shares(
+        &mut self,
+        shares: &[Share],
+        threshold: usize,
+    ) -> Result<[u8; 64], Phase2Error> {
+        let mut seed = Zeroizing::new(combine(shares, threshold)?);
+
+        Ok(self.contribute_and_wipe(&mut seed))
+    }
+}
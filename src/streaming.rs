@@ -0,0 +1,390 @@
+//! Contributing to serialized parameters as they're streamed from a
+//! reader to a writer, and verifying a contribution to parameters as
+//! they're streamed from two readers, without holding them in memory.
+
+use bellman::groth16::VerifyingKey;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::Field;
+use group::prime::PrimeCurveAffine;
+use pairing::group::{Curve, Group, UncompressedEncoding};
+use rand::Rng;
+use std::io::{self, Read, Write};
+use std::ops::Mul;
+use zeroize::Zeroize;
+
+use crate::{
+    batch_exp, check_merged_update, header, keypair_with, merge_chunk, no_progress,
+    read_contributions_len, read_point, write_contributions_len, ContributionHash, FormatVersion,
+    MPCEngine, MPCParameters, PrivateKey, Progress, PublicKey, Stage, Tracker, UpdateBase,
+    VerificationError, CONTRIBUTE_CHUNK,
+};
+
+/// Copies a query that a contribution doesn't change, prefixed by its
+/// length.
+fn copy_query<G, R, W>(reader: &mut R, writer: &mut W) -> io::Result<()>
+where
+    G: UncompressedEncoding,
+    R: Read,
+    W: Write,
+{
+    let len = reader.read_u32::<BigEndian>()?;
+    writer.write_u32::<BigEndian>(len)?;
+
+    let size = G::Uncompressed::default().as_ref().len() as u64;
+    let bytes = u64::from(len) * size;
+    if io::copy(&mut reader.take(bytes), writer)? != bytes {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
+/// Exponentiates a query by `coeff` a chunk at a time, as it's
+/// copied. Every point is checked, and must not be the point at
+/// infinity.
+fn exp_query<E, R, W>(reader: &mut R, writer: &mut W, coeff: E::Fr) -> io::Result<()>
+where
+    E: MPCEngine,
+    R: Read,
+    W: Write,
+{
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    writer.write_u32::<BigEndian>(len as u32)?;
+
+    let tracker = Tracker::new(Progress::new(&no_progress), Stage::Contribute, len);
+    let mut points = Vec::with_capacity(len.min(CONTRIBUTE_CHUNK));
+    let mut remaining = len;
+    while remaining > 0 {
+        points.clear();
+        for _ in 0..remaining.min(CONTRIBUTE_CHUNK) {
+            let p: E::G1Affine = read_point(reader)?;
+            if p.is_identity().into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+            points.push(p);
+        }
+        remaining -= points.len();
+
+        batch_exp::<E>(&mut points, coeff, &tracker);
+        for p in &points {
+            writer.write_all(p.to_uncompressed().as_ref())?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<E: MPCEngine> MPCParameters<E> {
+    /// Like `contribute`, for serialized parameters (as written by
+    /// `write`) that are read from `reader`, with the new parameters
+    /// written to `writer` as they're computed. Only a chunk of the
+    /// H and L queries is in memory at a time, so this suits
+    /// parameters too large to read. Given the same `rng`, the
+    /// contribution is the same as `contribute` would make.
+    ///
+    /// The H and L queries are checked as they're read; the other
+    /// queries, which a contribution doesn't change, are copied
+    /// as they are.
+    pub fn contribute_streaming<I, O, R>(
+        reader: I,
+        mut writer: O,
+        rng: &mut R,
+    ) -> io::Result<[u8; 64]>
+    where
+        I: Read,
+        O: Write,
+        R: Rng,
+    {
+        // The keypair can only be finished once the previous
+        // contributions at the end have been read, but its delta is
+        // needed right away. It's kept in a `PrivateKey`, so that it's
+        // wiped however this returns.
+        let privkey = PrivateKey::<E> {
+            delta: E::Fr::random(&mut *rng),
+        };
+        let s = E::G1::random(rng).to_affine();
+        let mut delta_inv = privkey.delta.invert().expect("nonzero");
+
+        // A header is passed on as it is, as the contribution doesn't
+        // change the cs_hash it has the start of, nor the scheme
+        let (header, mut reader) = header::read_header::<E, _>(reader)?;
+        let scheme = header::scheme_of(&header)?;
+        let version = header.as_ref().map_or(FormatVersion::V1, |h| h.version);
+        if let Some(header) = &header {
+            header.write::<E, _>(&mut writer)?;
+        }
+
+        let mut vk = VerifyingKey::<E>::read(&mut reader)?;
+        let delta_before = vk.delta_g1;
+        vk.delta_g1 = vk.delta_g1.mul(privkey.delta).to_affine();
+        vk.delta_g2 = vk.delta_g2.mul(privkey.delta).to_affine();
+        vk.write(&mut writer)?;
+
+        exp_query::<E, _, _>(&mut reader, &mut writer, delta_inv)?;
+        exp_query::<E, _, _>(&mut reader, &mut writer, delta_inv)?;
+        delta_inv.zeroize();
+        copy_query::<E::G1Affine, _, _>(&mut reader, &mut writer)?;
+        copy_query::<E::G1Affine, _, _>(&mut reader, &mut writer)?;
+        copy_query::<E::G2Affine, _, _>(&mut reader, &mut writer)?;
+
+        let mut cs_hash = [0u8; 64];
+        reader.read_exact(&mut cs_hash)?;
+        if let Some(header) = &header {
+            header.check_cs_hash(&cs_hash)?;
+        }
+        writer.write_all(&cs_hash)?;
+
+        // We don't trust the count to preallocate
+        let (circuit_fingerprint, count) = read_contributions_len(&mut reader, version)?;
+        let mut contributions = vec![];
+        for _ in 0..count {
+            contributions.push(PublicKey::<E>::read(&mut reader)?);
+        }
+
+        let (pubkey, _) = keypair_with(
+            &*scheme,
+            privkey.delta,
+            s,
+            &cs_hash,
+            &contributions,
+            delta_before,
+        );
+        contributions.push(pubkey.clone());
+
+        write_contributions_len(
+            &mut writer,
+            version,
+            circuit_fingerprint.as_ref(),
+            contributions.len(),
+        )?;
+        for contribution in &contributions {
+            contribution.write(&mut writer)?;
+        }
+        writer.flush()?;
+
+        Ok(pubkey.hash().into())
+    }
+}
+
+/// Reads the lengths of a query from `before` and `after`, which
+/// are `None` unless they're equal.
+fn query_len<R1: Read, R2: Read>(before: &mut R1, after: &mut R2) -> io::Result<Option<usize>> {
+    let len = before.read_u32::<BigEndian>()?;
+    let after_len = after.read_u32::<BigEndian>()?;
+
+    Ok(Some(len as usize).filter(|_| len == after_len))
+}
+
+/// Merges a query of `before` with the same query of `after`, as
+/// `merge_pairs` does, reading `chunk_size` points of each at a time.
+fn merge_query<E, R1, R2>(
+    before: &mut R1,
+    after: &mut R2,
+    len: usize,
+    chunk_size: usize,
+) -> io::Result<(E::G1Affine, E::G1Affine)>
+where
+    E: MPCEngine,
+    R1: Read,
+    R2: Read,
+{
+    let mut s = E::G1::identity();
+    let mut sx = E::G1::identity();
+    let mut v1 = Vec::with_capacity(len.min(chunk_size));
+    let mut v2 = Vec::with_capacity(len.min(chunk_size));
+
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(chunk_size);
+        v1.clear();
+        v2.clear();
+        for _ in 0..n {
+            v1.push(read_point::<E::G1Affine, _>(before)?);
+            v2.push(read_point::<E::G1Affine, _>(after)?);
+        }
+        remaining -= n;
+
+        let (local_s, local_sx) = merge_chunk(&v1, &v2);
+        s += local_s;
+        sx += local_sx;
+    }
+
+    Ok((s.to_affine(), sx.to_affine()))
+}
+
+/// Compares a query that a contribution doesn't change, byte for
+/// byte, `chunk_size` points at a time.
+fn compare_query<G, R1, R2>(
+    before: &mut R1,
+    after: &mut R2,
+    chunk_size: usize,
+    mismatch: VerificationError,
+) -> Result<(), VerificationError>
+where
+    G: UncompressedEncoding,
+    R1: Read,
+    R2: Read,
+{
+    let size = G::Uncompressed::default().as_ref().len();
+    let Some(len) = query_len(before, after)? else {
+        return Err(mismatch);
+    };
+
+    let mut v1 = vec![0u8; len.min(chunk_size) * size];
+    let mut v2 = vec![0u8; len.min(chunk_size) * size];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(chunk_size) * size;
+        before.read_exact(&mut v1[..n])?;
+        after.read_exact(&mut v2[..n])?;
+        if v1[..n] != v2[..n] {
+            return Err(mismatch);
+        }
+        remaining -= n / size;
+    }
+
+    Ok(())
+}
+
+/// Like `verify_contribution`, for serialized parameters (as written
+/// by `write`) that are read from `before` and `after` side by side.
+/// Only `chunk_size` points of each are in memory at a time, so two
+/// files larger than memory can be checked. The random linear
+/// combinations of the H and L queries are accumulated a chunk at a
+/// time, and the queries a contribution doesn't change are compared
+/// byte for byte rather than decoded.
+pub fn verify_contribution_streamed<E, R1, R2>(
+    before: R1,
+    after: R2,
+    chunk_size: usize,
+) -> Result<ContributionHash, VerificationError>
+where
+    E: MPCEngine,
+    R1: Read,
+    R2: Read,
+{
+    let chunk_size = chunk_size.max(1);
+
+    let (before_header, mut before) = header::read_header::<E, _>(before)?;
+    let (after_header, mut after) = header::read_header::<E, _>(after)?;
+    let before_version = before_header
+        .as_ref()
+        .map_or(FormatVersion::V1, |h| h.version);
+    let after_version = after_header
+        .as_ref()
+        .map_or(FormatVersion::V1, |h| h.version);
+
+    // The contribution is checked with the scheme of `before`, which
+    // it can't change
+    let scheme = header::scheme_of::<E>(&before_header)?;
+    if header::scheme_of::<E>(&after_header)?.id() != scheme.id() {
+        return Err(VerificationError::TranscriptSchemeMismatch);
+    }
+
+    let before_vk = VerifyingKey::<E>::read(&mut before)?;
+    let after_vk = VerifyingKey::<E>::read(&mut after)?;
+
+    // alpha/beta/gamma don't change
+    if before_vk.alpha_g1 != after_vk.alpha_g1 {
+        return Err(VerificationError::AlphaG1Mismatch);
+    }
+    if before_vk.beta_g1 != after_vk.beta_g1 {
+        return Err(VerificationError::BetaG1Mismatch);
+    }
+    if before_vk.beta_g2 != after_vk.beta_g2 {
+        return Err(VerificationError::BetaG2Mismatch);
+    }
+    if before_vk.gamma_g2 != after_vk.gamma_g2 {
+        return Err(VerificationError::GammaG2Mismatch);
+    }
+
+    // IC shouldn't change, as gamma doesn't change
+    if before_vk.ic != after_vk.ic {
+        return Err(VerificationError::IcMismatch);
+    }
+
+    // H/L will change, but should have same length
+    let h_len =
+        query_len(&mut before, &mut after)?.ok_or(VerificationError::HQueryLengthMismatch)?;
+    let h = merge_query::<E, _, _>(&mut before, &mut after, h_len, chunk_size)?;
+    let l_len =
+        query_len(&mut before, &mut after)?.ok_or(VerificationError::LQueryLengthMismatch)?;
+    let l = merge_query::<E, _, _>(&mut before, &mut after, l_len, chunk_size)?;
+
+    // A/B_G1/B_G2 doesn't change at all
+    compare_query::<E::G1Affine, _, _>(
+        &mut before,
+        &mut after,
+        chunk_size,
+        VerificationError::AQueryMismatch,
+    )?;
+    compare_query::<E::G1Affine, _, _>(
+        &mut before,
+        &mut after,
+        chunk_size,
+        VerificationError::BG1QueryMismatch,
+    )?;
+    compare_query::<E::G2Affine, _, _>(
+        &mut before,
+        &mut after,
+        chunk_size,
+        VerificationError::BG2QueryMismatch,
+    )?;
+
+    // cs_hash should be the same
+    let mut cs_hash = [0u8; 64];
+    before.read_exact(&mut cs_hash)?;
+    let mut after_cs_hash = [0u8; 64];
+    after.read_exact(&mut after_cs_hash)?;
+    if cs_hash[..] != after_cs_hash[..] {
+        return Err(VerificationError::CsHashMismatch);
+    }
+    for header in [&before_header, &after_header].into_iter().flatten() {
+        header.check_cs_hash(&cs_hash)?;
+    }
+
+    // We don't trust the counts to preallocate
+    let (before_fingerprint, count) = read_contributions_len(&mut before, before_version)?;
+    let mut contributions = vec![];
+    for _ in 0..count {
+        contributions.push(PublicKey::<E>::read(&mut before)?);
+    }
+    let (after_fingerprint, count) = read_contributions_len(&mut after, after_version)?;
+    let mut after_contributions = vec![];
+    for _ in 0..count {
+        after_contributions.push(PublicKey::<E>::read(&mut after)?);
+    }
+
+    // A contribution can't change the circuit the parameters are for
+    if before_fingerprint.is_some() && after_fingerprint != before_fingerprint {
+        return Err(VerificationError::CircuitFingerprintMismatch);
+    }
+
+    // Transformation involves a single new object
+    if after_contributions.len() != contributions.len() + 1 {
+        return Err(VerificationError::NotOneContribution);
+    }
+
+    // None of the previous transformations should change
+    if contributions[..] != after_contributions[..contributions.len()] {
+        return Err(VerificationError::PreviousContributionsChanged);
+    }
+
+    let base = UpdateBase {
+        scheme: &*scheme,
+        cs_hash: &cs_hash,
+        contributions: &contributions,
+        vk: &before_vk,
+    };
+    check_merged_update(
+        &base,
+        after_contributions.last().unwrap(),
+        after_vk.delta_g1,
+        after_vk.delta_g2,
+        || h,
+        || l,
+    )
+}
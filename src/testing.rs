@@ -0,0 +1,285 @@
+//! Helpers for tests and rehearsals of ceremonies built on this
+//! crate, enabled by the `test-utils` feature. Rehearsing a ceremony
+//! with `RandomCircuit` is a supported use of the feature, but the
+//! parameters made with anything in here are for rehearsals only:
+//! nothing in here is suitable for real ceremonies.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use bls12_381::{Bls12, Scalar};
+use ff::{Field, PrimeField};
+use group::prime::PrimeCurveAffine;
+use group::{Curve, Group, Wnaf, WnafGroup};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use crate::phase1::RadixFile;
+#[cfg(feature = "fs")]
+use crate::MPCParameters;
+use crate::{MPCEngine, SchemeId, TranscriptScheme};
+
+/// Create parameters for `circuit` and apply one contribution per
+/// seed, in order, using `MPCParameters::contribute_with_seed`.
+/// The result is fully deterministic, so its `fingerprint()` is
+/// stable and can be asserted in golden-file tests.
+///
+/// Panics if the circuit can't be synthesized.
+#[cfg(feature = "fs")]
+pub fn deterministic_ceremony<C>(circuit: C, seeds: &[[u8; 32]]) -> MPCParameters
+where
+    C: Circuit<bls12_381::Scalar>,
+{
+    let mut params = MPCParameters::new(circuit).expect("circuit should synthesize");
+
+    for seed in seeds {
+        params.contribute_with_seed(*seed);
+    }
+
+    params
+}
+
+/// A circuit whose evaluation domain has exactly `2^exp` elements,
+/// for measuring how the crate scales with the size of circuits. It
+/// squares a witness over and over, and exposes the last square as
+/// its one public input, so every query of its parameters is dense.
+#[derive(Clone, Copy, Debug)]
+pub struct SyntheticCircuit {
+    exp: u32,
+}
+
+impl SyntheticCircuit {
+    /// A circuit that needs `phase1radix2m{exp}`. Panics unless `exp`
+    /// is at least 2.
+    pub fn new(exp: u32) -> Self {
+        assert!(exp >= 2, "the circuit needs a domain of at least 4");
+        SyntheticCircuit { exp }
+    }
+
+    /// The exponent of the size of the domain.
+    pub fn exp(&self) -> u32 {
+        self.exp
+    }
+}
+
+impl<S: PrimeField> Circuit<S> for SyntheticCircuit {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        // One and the public input are each constrained on their own
+        let squarings = (1usize << self.exp) - 2;
+
+        let mut value = S::from(2);
+        let mut x = cs.alloc(|| "x", || Ok(value))?;
+        for i in 0..squarings {
+            value = value.square();
+            let square = if i + 1 == squarings {
+                cs.alloc_input(|| "out", || Ok(value))?
+            } else {
+                cs.alloc(|| "square", || Ok(value))?
+            };
+
+            cs.enforce(|| "squaring", |lc| lc + x, |lc| lc + x, |lc| lc + square);
+            x = square;
+        }
+
+        Ok(())
+    }
+}
+
+/// A random but satisfiable R1CS of any size, for rehearsing a
+/// ceremony at the scale of a circuit that isn't finished yet: how
+/// long each step takes, and how large the files get. The same sizes
+/// always give the same circuit, and the same witness.
+///
+/// Each constraint multiplies two short random combinations of the
+/// variables, and every variable appears in at least one of them, so
+/// `aux` must be zero if `constraints` is. The domain also holds a
+/// constraint for each input and for one, so the circuit needs
+/// `phase1radix2m{exp}` for the smallest `2^exp` that's at least
+/// `constraints + inputs + 1`.
+///
+/// Like the rest of this module, it needs the `test-utils` feature,
+/// which a crate rehearsing its ceremony can enable outside of its
+/// tests too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RandomCircuit {
+    /// The number of constraints.
+    pub constraints: usize,
+    /// The number of public inputs, besides one.
+    pub inputs: usize,
+    /// The number of auxiliary (private) variables.
+    pub aux: usize,
+}
+
+impl Circuit<Scalar> for RandomCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut seed = [0u8; 32];
+        seed[..8].copy_from_slice(&(self.constraints as u64).to_le_bytes());
+        seed[8..16].copy_from_slice(&(self.inputs as u64).to_le_bytes());
+        seed[16..24].copy_from_slice(&(self.aux as u64).to_le_bytes());
+        let mut rng = ChaChaRng::from_seed(seed);
+
+        let mut vars = vec![(CS::one(), Scalar::ONE)];
+        for _ in 0..self.inputs {
+            let value = Scalar::random(&mut rng);
+            vars.push((cs.alloc_input(|| "input", || Ok(value))?, value));
+        }
+        for _ in 0..self.aux {
+            let value = Scalar::random(&mut rng);
+            vars.push((cs.alloc(|| "aux", || Ok(value))?, value));
+        }
+
+        // A random combination of `terms` variables and `covered`,
+        // with its value
+        let mut combination = |terms: usize, covered: &[(Variable, Scalar)]| {
+            let mut lc = LinearCombination::zero();
+            let mut value = Scalar::ZERO;
+            let picked: Vec<_> = (0..terms)
+                .map(|_| vars[rng.gen_range(0..vars.len())])
+                .collect();
+            for (var, v) in picked.iter().chain(covered) {
+                let coeff = Scalar::random(&mut rng);
+                lc = lc + (coeff, *var);
+                value += coeff * v;
+            }
+
+            (lc, value)
+        };
+
+        // Each constraint covers the next few variables after one
+        let per_constraint = (vars.len() - 1).div_ceil(self.constraints.max(1));
+        for i in 0..self.constraints {
+            let start = (1 + i * per_constraint).min(vars.len());
+            let end = (start + per_constraint).min(vars.len());
+            let covered = vars[start..end].to_vec();
+
+            let (a, a_value) = combination(2, &covered);
+            let (b, b_value) = combination(2, &[]);
+
+            // The constant term of C makes the constraint hold
+            let (c, c_value) = combination(1, &[]);
+            let correction = a_value * b_value - c_value;
+
+            cs.enforce(|| "random", |_| a, |_| b, |_| c + (correction, CS::one()));
+        }
+
+        Ok(())
+    }
+}
+
+/// `base` times each of `scalars`
+fn exp_table<G>(base: G::Curve, scalars: &[G::Scalar]) -> Vec<G>
+where
+    G: PrimeCurveAffine,
+    G::Curve: WnafGroup,
+{
+    let mut wnaf = Wnaf::new();
+    let mut wnaf = wnaf.base(base, scalars.len());
+    let points: Vec<G::Curve> = scalars.iter().map(|s| wnaf.scalar(s)).collect();
+
+    let mut affine = vec![G::identity(); points.len()];
+    G::Curve::batch_normalize(&points, &mut affine);
+    affine
+}
+
+/// The tau, alpha and beta picked from `seed`.
+fn insecure_secrets<F: PrimeField>(seed: [u8; 32]) -> (F, F, F) {
+    let mut rng = ChaChaRng::from_seed(seed);
+    let tau = F::random(&mut rng);
+    let alpha = F::random(&mut rng);
+    let beta = F::random(&mut rng);
+    (tau, alpha, beta)
+}
+
+/// The first `n` powers of `tau`.
+fn powers<F: PrimeField>(tau: F, n: usize) -> Vec<F> {
+    (0..n)
+        .scan(F::ONE, |power, _| {
+            let current = *power;
+            *power *= tau;
+            Some(current)
+        })
+        .collect()
+}
+
+/// A radix file for a domain of size `2^exp` derived from a tau,
+/// alpha and beta picked from `seed`, which are then known to anyone
+/// with the seed. It stands in for the Powers of Tau in benchmarks
+/// and tests, through `phase1::RadixSource::Bytes`, and lets them run
+/// over any curve.
+///
+/// The Lagrange coefficients are evaluated at tau directly, rather
+/// than taken from an accumulator, so this also checks
+/// `Accumulator::radix` against `insecure_accumulator`.
+pub fn insecure_radix<E: MPCEngine>(exp: u32, seed: [u8; 32]) -> RadixFile<E> {
+    let (tau, alpha, beta) = insecure_secrets::<E::Fr>(seed);
+
+    // The domain's root of unity, as bellman computes it
+    let mut omega = E::Fr::ROOT_OF_UNITY;
+    for _ in exp..E::Fr::S {
+        omega = omega.square();
+    }
+
+    // L_i(tau) = omega^i (tau^m - 1) / (m (tau - omega^i))
+    let m = 1 << exp;
+    let z = tau.pow_vartime([m as u64]) - E::Fr::ONE;
+    let m_inv = E::Fr::from(m as u64).invert().unwrap();
+    let lagrange: Vec<E::Fr> = powers(omega, m)
+        .into_iter()
+        .map(|w| w * z * m_inv * (tau - w).invert().unwrap())
+        .collect();
+    let h: Vec<E::Fr> = powers(tau, m - 1).into_iter().map(|t| t * z).collect();
+
+    let g1 = E::G1::generator();
+    RadixFile {
+        alpha_g1: (g1 * alpha).to_affine(),
+        beta_g1: (g1 * beta).to_affine(),
+        beta_g2: (E::G2::generator() * beta).to_affine(),
+        coeffs_g1: exp_table(g1, &lagrange),
+        coeffs_g2: exp_table(E::G2::generator(), &lagrange),
+        alpha_coeffs_g1: exp_table(g1 * alpha, &lagrange),
+        beta_coeffs_g1: exp_table(g1 * beta, &lagrange),
+        h: exp_table(g1, &h),
+    }
+}
+
+/// The accumulator of a Powers of Tau ceremony just large enough for
+/// a domain of size `2^exp`, with the tau, alpha and beta that
+/// `insecure_radix` picks from `seed`.
+#[cfg(test)]
+pub(crate) fn insecure_accumulator<E: MPCEngine>(
+    exp: u32,
+    seed: [u8; 32],
+) -> crate::phase1::powersoftau::Accumulator<E> {
+    let (tau, alpha, beta) = insecure_secrets::<E::Fr>(seed);
+
+    let m = 1 << exp;
+    let powers = powers(tau, 2 * m - 1);
+
+    let g1 = E::G1::generator();
+    crate::phase1::powersoftau::Accumulator {
+        tau_powers_g1: exp_table(g1, &powers),
+        tau_powers_g2: exp_table(E::G2::generator(), &powers[..m]),
+        alpha_tau_powers_g1: exp_table(g1 * alpha, &powers[..m]),
+        beta_tau_powers_g1: exp_table(g1 * beta, &powers[..m]),
+        beta_g2: (E::G2::generator() * beta).to_affine(),
+    }
+}
+
+/// The hash to G2 of a `PinnedScheme`.
+pub type HashToG2 = fn(&[u8; 64]) -> bls12_381::G2Projective;
+
+/// A transcript scheme that hashes to G2 with a function of the
+/// test's choosing, so tests can pin the `r` point of every
+/// contribution to known values. Set it on parameters with
+/// `MPCParameters::with_transcript_scheme`; as it's a custom scheme,
+/// parameters that use it are read back with `read_with_scheme`.
+#[derive(Clone, Copy)]
+pub struct PinnedScheme(pub HashToG2);
+
+impl TranscriptScheme<Bls12> for PinnedScheme {
+    fn hash_to_g2(&self, transcript: &[u8; 64]) -> bls12_381::G2Projective {
+        (self.0)(transcript)
+    }
+
+    fn id(&self) -> SchemeId {
+        SchemeId::Custom(b"phase2-testing-pinned".to_vec())
+    }
+}
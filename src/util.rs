@@ -0,0 +1,174 @@
+//! The checks that verification is built from, for tooling that
+//! verifies ceremonies its own way. They're the same functions this
+//! crate verifies with.
+//!
+//! A contribution takes a query `v` to `v * delta^-1`, and publishes
+//! delta in G2. `merge_pairs` folds all of the pairs into one with a
+//! random linear combination, so that a single `same_ratio` checks
+//! them all:
+//!
+//! ```rust
+//! use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+//! use ff::Field;
+//! use group::{Curve, Group};
+//! use phase2::util::{merge_pairs, same_ratio};
+//!
+//! let rng = &mut rand::thread_rng();
+//! let delta = Scalar::random(&mut *rng);
+//! let delta_inv = delta.invert().unwrap();
+//!
+//! let before: Vec<G1Affine> = (0..10)
+//!     .map(|_| G1Projective::random(&mut *rng).to_affine())
+//!     .collect();
+//! let after: Vec<G1Affine> = before
+//!     .iter()
+//!     .map(|p| (p * delta_inv).to_affine())
+//!     .collect();
+//!
+//! let worker = bellman::multicore::Worker::new();
+//! let g2 = G2Affine::generator();
+//! let delta_g2 = (g2 * delta).to_affine();
+//!
+//! // (before, after) has the ratio of (delta, g2)
+//! let pair = merge_pairs(rng, &worker, &before, &after);
+//! assert!(same_ratio(pair, (delta_g2, g2)));
+//!
+//! // A single point out of place is caught
+//! let mut bad = after.clone();
+//! bad[3] = before[3];
+//! let pair = merge_pairs(rng, &worker, &before, &bad);
+//! assert!(!same_ratio(pair, (delta_g2, g2)));
+//! ```
+
+use bellman::multicore::Worker;
+use group::WnafGroup;
+use pairing::PairingCurveAffine;
+use rand::RngCore;
+
+use crate::{
+    merge_pairs_on, no_progress, MPCEngine, MergeScalars, Progress, Stage, Tracker,
+    TranscriptScheme,
+};
+
+/// Checks that `g1.0` is to `g1.1` as `g2.0` is to `g2.1`, i.e. that
+/// e(g1.0, g2.1) = e(g1.1, g2.0).
+pub fn same_ratio<G: PairingCurveAffine>(g1: (G, G), g2: (G::Pair, G::Pair)) -> bool {
+    crate::same_ratio(g1, g2)
+}
+
+/// Merges the pairs (v1[i], v2[i]) into one, (sum r_i * v1[i],
+/// sum r_i * v2[i]) for random r_i drawn from `rng`, on the threads
+/// of `worker`. If every pair has the same ratio, so does the
+/// result; if any doesn't, the result doesn't either, except with
+/// negligible probability. Panics unless `v1` and `v2` are as long.
+///
+/// The scalars are drawn from a ChaCha generator seeded from `rng`,
+/// a stream per fixed number of pairs, so a seeded `rng` gives the
+/// same result however many threads `worker` has.
+pub fn merge_pairs<G, R>(rng: &mut R, worker: &Worker, v1: &[G], v2: &[G]) -> (G, G)
+where
+    G: PairingCurveAffine,
+    G::Curve: WnafGroup,
+    R: RngCore,
+{
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+
+    let tracker = Tracker::new(Progress::new(&no_progress), Stage::Verify, 0);
+    merge_pairs_on(worker, v1, v2, &tracker, MergeScalars::Seeded(seed))
+}
+
+/// Hashes the transcript hash `digest` of a contribution to G2 with
+/// `scheme`, giving the `r` its public key proves knowledge of delta
+/// with.
+///
+/// ```rust
+/// use bls12_381::Bls12;
+/// use phase2::{util::hash_to_g2, LegacyScheme};
+///
+/// let r = hash_to_g2::<Bls12>(&LegacyScheme, &[7; 64]);
+/// assert_eq!(r, hash_to_g2::<Bls12>(&LegacyScheme, &[7; 64]));
+/// assert_ne!(r, hash_to_g2::<Bls12>(&LegacyScheme, &[8; 64]));
+/// ```
+pub fn hash_to_g2<E: MPCEngine>(scheme: &dyn TranscriptScheme<E>, digest: &[u8; 64]) -> E::G2 {
+    scheme.hash_to_g2(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hex, Config, LegacyScheme};
+    use bls12_381::{Bls12, G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+    use ff::Field;
+    use group::{Curve, GroupEncoding};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn same_ratio_compares_ratios() {
+        let x = Scalar::from(5);
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let pair = (g1, (g1 * x).to_affine());
+
+        assert!(same_ratio(pair, (g2, (g2 * x).to_affine())));
+        assert!(!same_ratio(
+            pair,
+            (g2, (g2 * (x + Scalar::ONE)).to_affine())
+        ));
+        assert!(!same_ratio(pair, ((g2 * x).to_affine(), g2)));
+    }
+
+    #[test]
+    fn merge_pairs_is_reproducible_and_catches_tampering() {
+        // Just over one piece of `merge_pairs_on`, so two streams of
+        // scalars are used
+        let x = Scalar::from(7);
+        let (g, gx) = (G1Projective::generator(), G1Projective::generator() * x);
+        let (mut v1, mut v2) = (
+            vec![G1Affine::identity(); 4100],
+            vec![G1Affine::identity(); 4100],
+        );
+        let (p1, p2): (Vec<_>, Vec<_>) = (1..=4100u64)
+            .scan((g, gx), |(p, px), _| {
+                let next = (*p, *px);
+                *p += g;
+                *px += gx;
+                Some(next)
+            })
+            .unzip();
+        G1Projective::batch_normalize(&p1, &mut v1);
+        G1Projective::batch_normalize(&p2, &mut v2);
+        let g2 = G2Affine::generator();
+        let ratio = (g2, (g2 * x).to_affine());
+
+        let merge = |threads, v2: &[G1Affine]| {
+            Config { threads }
+                .install(|| {
+                    let rng = &mut ChaChaRng::from_seed([2; 32]);
+                    merge_pairs(rng, &Worker::new(), &v1, v2)
+                })
+                .unwrap()
+        };
+
+        let pair = merge(1, &v2);
+        assert_eq!(pair, merge(3, &v2));
+        assert!(same_ratio(pair, ratio));
+
+        let mut tampered = v2.clone();
+        tampered[4099] = v1[4099];
+        assert!(!same_ratio(merge(3, &tampered), ratio));
+    }
+
+    #[test]
+    fn hash_to_g2_matches_a_fixed_vector() {
+        let r: G2Projective = hash_to_g2::<Bls12>(&LegacyScheme, &[7; 64]);
+
+        assert_eq!(
+            hex(r.to_affine().to_bytes().as_ref()),
+            "a6f4055e19363d477e9969a0cacdac85f6361f37af6eea6fa09c75b50d8b0747\
+             b5ab700a62007cddc9aff5663c3c76ff03d5bce172375600e1f0968380db2084\
+             a72201760906117a736538ebee0e0beb0333eb1ad544eff2c73e1a4721a7b09c"
+        );
+    }
+}
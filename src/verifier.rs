@@ -0,0 +1,275 @@
+//! Exporting the verifying key for verifiers outside of Rust: as a
+//! snarkjs `verification_key.json`, and as Solidity constants for
+//! on-chain verifiers.
+//!
+//! BLS12-381 base field elements don't fit in a `uint256`, so the
+//! Solidity export uses the encoding of the BLS12-381 precompiles of
+//! EIP-2537, which take each field element as 64 big-endian bytes
+//! (the 48 bytes of the element, left-padded with zeros), G1 points
+//! as `x | y` and G2 points as `x.c0 | x.c1 | y.c0 | y.c1`, with the
+//! point at infinity as zeros.
+
+use bls12_381::{Bls12, G1Affine, G2Affine};
+
+use crate::{hex, MPCParameters, Phase2Error};
+
+/// The size of a big-endian base field element.
+const FQ_SIZE: usize = 48;
+
+/// The size of a base field element as EIP-2537 encodes it.
+const EIP2537_FQ_SIZE: usize = 64;
+
+/// Splits the uncompressed encoding of a G1 point into its big-endian
+/// coordinates, `x` and `y`, or returns `None` for the point at
+/// infinity.
+fn g1_coords(p: &G1Affine) -> Option<[Vec<u8>; 2]> {
+    if p.is_identity().into() {
+        return None;
+    }
+
+    // Flags are in the top bits of the first byte
+    let mut repr = p.to_uncompressed();
+    repr[0] &= 0x1f;
+
+    Some([repr[..FQ_SIZE].to_vec(), repr[FQ_SIZE..].to_vec()])
+}
+
+/// Like `g1_coords`, for G2: `x.c0`, `x.c1`, `y.c0` and `y.c1`. The
+/// uncompressed encoding has `c1` before `c0`.
+fn g2_coords(p: &G2Affine) -> Option<[Vec<u8>; 4]> {
+    if p.is_identity().into() {
+        return None;
+    }
+
+    let mut repr = p.to_uncompressed();
+    repr[0] &= 0x1f;
+    let c = |i: usize| repr[i * FQ_SIZE..(i + 1) * FQ_SIZE].to_vec();
+
+    Some([c(1), c(0), c(3), c(2)])
+}
+
+/// Formats a big-endian integer in decimal.
+fn decimal(be: &[u8]) -> String {
+    let mut n = be.to_vec();
+    let mut digits = vec![];
+
+    // Long division by 10, most significant byte first
+    while n.iter().any(|&b| b != 0) {
+        let mut rem = 0u32;
+        for b in n.iter_mut() {
+            let cur = (rem << 8) | u32::from(*b);
+            *b = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// A G1 point in snarkjs' projective form: `[x, y, "1"]`.
+fn g1_json(p: &G1Affine) -> String {
+    match g1_coords(p) {
+        Some([x, y]) => format!("[\"{}\",\"{}\",\"1\"]", decimal(&x), decimal(&y)),
+        None => "[\"0\",\"1\",\"0\"]".to_string(),
+    }
+}
+
+/// A G2 point in snarkjs' projective form:
+/// `[[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]]`.
+fn g2_json(p: &G2Affine) -> String {
+    match g2_coords(p) {
+        Some([x0, x1, y0, y1]) => format!(
+            "[[\"{}\",\"{}\"],[\"{}\",\"{}\"],[\"1\",\"0\"]]",
+            decimal(&x0),
+            decimal(&x1),
+            decimal(&y0),
+            decimal(&y1)
+        ),
+        None => "[[\"0\",\"0\"],[\"1\",\"0\"],[\"0\",\"0\"]]".to_string(),
+    }
+}
+
+/// Pads each coordinate to 64 bytes, as EIP-2537 expects, and
+/// concatenates them as hex.
+fn eip2537(coords: &[Vec<u8>]) -> String {
+    coords
+        .iter()
+        .map(|c| hex(&[0u8; EIP2537_FQ_SIZE - FQ_SIZE]) + &hex(c))
+        .collect()
+}
+
+fn g1_eip2537(p: &G1Affine) -> String {
+    match g1_coords(p) {
+        Some(coords) => eip2537(&coords),
+        None => hex(&[0u8; 2 * EIP2537_FQ_SIZE]),
+    }
+}
+
+fn g2_eip2537(p: &G2Affine) -> String {
+    match g2_coords(p) {
+        Some(coords) => eip2537(&coords),
+        None => hex(&[0u8; 4 * EIP2537_FQ_SIZE]),
+    }
+}
+
+impl MPCParameters<Bls12> {
+    /// Export the verifying key in the format of snarkjs'
+    /// `verification_key.json`, which `snarkjs groth16 verify` reads.
+    /// Coordinates are decimal strings. The precomputed
+    /// `vk_alphabeta_12` is left out, as snarkjs' verifier doesn't
+    /// use it. Like `export_transcript_json`, there's no whitespace.
+    /// Fails for parameters whose IC query is empty (see
+    /// `check_ic_arity`).
+    pub fn export_vk_json(&self) -> Result<String, Phase2Error> {
+        self.check_ic_arity()?;
+        let vk = &self.params.vk;
+        let ic: Vec<String> = vk.ic.iter().map(g1_json).collect();
+
+        Ok(format!(
+            "{{\"protocol\":\"groth16\",\"curve\":\"bls12381\",\"nPublic\":{},\"vk_alpha_1\":{},\"vk_beta_2\":{},\"vk_gamma_2\":{},\"vk_delta_2\":{},\"IC\":[{}]}}",
+            vk.ic.len() - 1,
+            g1_json(&vk.alpha_g1),
+            g2_json(&vk.beta_g2),
+            g2_json(&vk.gamma_g2),
+            g2_json(&vk.delta_g2),
+            ic.join(",")
+        ))
+    }
+
+    /// Export the verifying key as a Solidity library of constants,
+    /// for a Groth16 verifier contract built on the EIP-2537
+    /// precompiles. Each point is a `bytes` constant in the encoding
+    /// the precompiles take (see the module docs), and `IC` holds
+    /// the points of the IC query back to back, ready to be paired
+    /// with the public inputs for the G1 multi-exponentiation. Fails
+    /// for parameters whose IC query is empty.
+    pub fn export_vk_solidity(&self) -> Result<String, Phase2Error> {
+        self.check_ic_arity()?;
+        let vk = &self.params.vk;
+        let ic: String = vk.ic.iter().map(g1_eip2537).collect();
+
+        Ok(format!(
+            "// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+/// The Groth16 verifying key of a phase2 ceremony over BLS12-381, in
+/// the encoding of the EIP-2537 precompiles.
+library Groth16VerifyingKey {{
+    uint256 internal constant N_PUBLIC = {};
+
+    bytes internal constant ALPHA_G1 = hex\"{}\";
+    bytes internal constant BETA_G2 = hex\"{}\";
+    bytes internal constant GAMMA_G2 = hex\"{}\";
+    bytes internal constant DELTA_G2 = hex\"{}\";
+
+    /// N_PUBLIC + 1 G1 points, the first for the constant input.
+    bytes internal constant IC = hex\"{}\";
+}}
+",
+            vk.ic.len() - 1,
+            g1_eip2537(&vk.alpha_g1),
+            g2_eip2537(&vk.beta_g2),
+            g2_eip2537(&vk.gamma_g2),
+            g2_eip2537(&vk.delta_g2),
+            ic
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::{insecure_radix, RandomCircuit};
+    use crate::VerificationError;
+
+    fn params() -> MPCParameters<Bls12> {
+        let circuit = RandomCircuit {
+            constraints: 2,
+            inputs: 1,
+            aux: 1,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(2, [2; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+        params
+    }
+
+    /// `p` as EIP-2537 encodes it, from its uncompressed encoding.
+    fn padded(p: &G1Affine) -> String {
+        let mut repr = p.to_uncompressed();
+        repr[0] &= 0x1f;
+        let pad = hex(&[0u8; EIP2537_FQ_SIZE - FQ_SIZE]);
+        format!("{}{}{}{}", pad, hex(&repr[..48]), pad, hex(&repr[48..]))
+    }
+
+    #[test]
+    fn json_has_an_ic_point_per_input_and_one() {
+        let params = params();
+        let json = params.export_vk_json().unwrap();
+
+        assert!(json.starts_with("{\"protocol\":\"groth16\",\"curve\":\"bls12381\",\"nPublic\":1,"));
+        let ic = json.split("\"IC\":").nth(1).unwrap();
+        assert!(ic.starts_with("[[\"") && ic.ends_with("\"]]}"));
+        assert_eq!(ic.matches(",\"1\"]").count(), 2);
+        assert_eq!(
+            ic,
+            format!(
+                "[{},{}]}}",
+                g1_json(&params.params.vk.ic[0]),
+                g1_json(&params.params.vk.ic[1])
+            )
+        );
+    }
+
+    #[test]
+    fn solidity_constants_are_eip2537_points() {
+        let params = params();
+        let vk = &params.params.vk;
+        let solidity = params.export_vk_solidity().unwrap();
+
+        assert!(solidity.contains("uint256 internal constant N_PUBLIC = 1;"));
+        assert!(solidity.contains(&format!(
+            "bytes internal constant ALPHA_G1 = hex\"{}\";",
+            padded(&vk.alpha_g1)
+        )));
+        assert!(solidity.contains(&format!(
+            "bytes internal constant IC = hex\"{}{}\";",
+            padded(&vk.ic[0]),
+            padded(&vk.ic[1])
+        )));
+
+        // Each G2 point is four padded coordinates
+        let delta = solidity
+            .split("DELTA_G2 = hex\"")
+            .nth(1)
+            .and_then(|s| s.split('"').next())
+            .unwrap();
+        assert_eq!(delta.len(), 2 * 4 * EIP2537_FQ_SIZE);
+    }
+
+    #[test]
+    fn an_empty_ic_query_cant_be_exported() {
+        let mut params = params();
+        params.params.vk.ic.clear();
+
+        assert!(matches!(
+            params.export_vk_json(),
+            Err(Phase2Error::Verification(VerificationError::IcEmpty))
+        ));
+        assert!(matches!(
+            params.export_vk_solidity(),
+            Err(Phase2Error::Verification(VerificationError::IcEmpty))
+        ));
+    }
+}
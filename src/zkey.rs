@@ -0,0 +1,927 @@
+//! Conversion of parameters to and from the snarkjs `.zkey` format,
+//! enabled by the `snarkjs` feature.
+//!
+//! A zkey file is a sequence of sections, each with a little-endian
+//! id and length. Field elements are written little-endian in
+//! Montgomery form, and curve points as their affine coordinates
+//! (with the point at infinity written as zeros).
+
+use bellman::domain::EvaluationDomain;
+use bellman::groth16::{Parameters, VerifyingKey};
+use bellman::multicore::Worker;
+use bellman::Circuit;
+use bls12_381::{Bls12, G1Affine, G1Projective, Scalar};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use group::prime::PrimeCurveAffine;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::phase1::powersoftau::Projective;
+use crate::{expand, KeypairAssembly, MPCParameters, PublicKey, SchemeId};
+
+/// The BLS12-381 base field modulus, as little-endian 64-bit limbs.
+const Q: [u64; 6] = [
+    0xb9fe_ffff_ffff_aaab,
+    0x1eab_fffe_b153_ffff,
+    0x6730_d2a0_f6b0_f624,
+    0x6477_4b84_f385_12bf,
+    0x4b1b_a7b6_434b_acd7,
+    0x1a01_11ea_397f_e69a,
+];
+
+/// The BLS12-381 scalar field modulus, as little-endian 64-bit limbs.
+const R: [u64; 4] = [
+    0xffff_ffff_0000_0001,
+    0x53bd_a402_fffe_5bfe,
+    0x3339_d808_09a1_d805,
+    0x73ed_a753_299d_7d48,
+];
+
+/// The id of the section `write_zkey` records the transcript scheme
+/// in, past those snarkjs knows.
+const SCHEME_SECTION: u32 = 100;
+
+const N8Q: usize = 48;
+const N8R: usize = 32;
+const G1_SIZE: usize = N8Q * 2;
+const G2_SIZE: usize = N8Q * 4;
+
+/// Converts a big-endian base field element into little-endian
+/// Montgomery form, i.e. `x * 2^384 mod q`.
+fn fq_to_montgomery(be: &[u8]) -> [u8; N8Q] {
+    let mut x = [0u64; 6];
+    for (i, limb) in x.iter_mut().enumerate() {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&be[N8Q - 8 * (i + 1)..N8Q - 8 * i]);
+        *limb = u64::from_be_bytes(bytes);
+    }
+
+    // Multiply by 2^384 with modular doublings. As x < q < 2^382,
+    // doubling never overflows the top limb.
+    for _ in 0..384 {
+        let mut carry = 0;
+        for limb in x.iter_mut() {
+            let next = *limb >> 63;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+
+        let mut geq = true;
+        for i in (0..6).rev() {
+            if x[i] != Q[i] {
+                geq = x[i] > Q[i];
+                break;
+            }
+        }
+
+        if geq {
+            let mut borrow = 0;
+            for i in 0..6 {
+                let (d, b1) = x[i].overflowing_sub(Q[i]);
+                let (d, b2) = d.overflowing_sub(borrow);
+                x[i] = d;
+                borrow = (b1 | b2) as u64;
+            }
+        }
+    }
+
+    let mut out = [0u8; N8Q];
+    for (i, limb) in x.iter().enumerate() {
+        out[8 * i..8 * (i + 1)].copy_from_slice(&limb.to_le_bytes());
+    }
+    out
+}
+
+/// Converts a little-endian base field element in Montgomery form
+/// back into big-endian, i.e. `x * 2^-384 mod q`.
+fn fq_from_montgomery(le: &[u8]) -> io::Result<[u8; N8Q]> {
+    let mut x = [0u64; 7];
+    for (i, limb) in x.iter_mut().take(6).enumerate() {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&le[8 * i..8 * (i + 1)]);
+        *limb = u64::from_le_bytes(bytes);
+    }
+
+    if (0..6)
+        .rev()
+        .find(|&i| x[i] != Q[i])
+        .is_none_or(|i| x[i] > Q[i])
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "field element out of range",
+        ));
+    }
+
+    // Divide by 2^384 with modular halvings: adding q to odd values
+    // first makes them even without changing them mod q.
+    for _ in 0..384 {
+        if x[0] & 1 == 1 {
+            let mut carry = 0;
+            for i in 0..6 {
+                let (s, c1) = x[i].overflowing_add(Q[i]);
+                let (s, c2) = s.overflowing_add(carry);
+                x[i] = s;
+                carry = (c1 | c2) as u64;
+            }
+            x[6] = carry;
+        }
+
+        for i in 0..6 {
+            x[i] = (x[i] >> 1) | (x[i + 1] << 63);
+        }
+        x[6] = 0;
+    }
+
+    let mut out = [0u8; N8Q];
+    for (i, limb) in x.iter().take(6).enumerate() {
+        out[N8Q - 8 * (i + 1)..N8Q - 8 * i].copy_from_slice(&limb.to_be_bytes());
+    }
+    Ok(out)
+}
+
+/// Writes a scalar as snarkjs stores R1CS coefficients: in
+/// Montgomery form, multiplied by the Montgomery factor once more.
+fn write_coeff<W: Write>(writer: &mut W, coeff: bls12_381::Scalar) -> io::Result<()> {
+    let r = bls12_381::Scalar::from(2).pow_vartime(&[256, 0, 0, 0]);
+    writer.write_all((coeff * r * r).to_repr().as_ref())
+}
+
+fn write_g1<W: Write>(writer: &mut W, p: &bls12_381::G1Affine) -> io::Result<()> {
+    if p.is_identity().into() {
+        return writer.write_all(&[0u8; G1_SIZE]);
+    }
+
+    // The uncompressed encoding is x | y, big-endian, with flags in
+    // the top bits of the first byte.
+    let mut repr = p.to_uncompressed();
+    repr.as_mut()[0] &= 0x1f;
+    let repr = repr.as_ref();
+
+    writer.write_all(&fq_to_montgomery(&repr[0..N8Q]))?;
+    writer.write_all(&fq_to_montgomery(&repr[N8Q..2 * N8Q]))
+}
+
+fn write_g2<W: Write>(writer: &mut W, p: &bls12_381::G2Affine) -> io::Result<()> {
+    if p.is_identity().into() {
+        return writer.write_all(&[0u8; G2_SIZE]);
+    }
+
+    // The uncompressed encoding is x.c1 | x.c0 | y.c1 | y.c0, but
+    // snarkjs expects x.c0 | x.c1 | y.c0 | y.c1.
+    let mut repr = p.to_uncompressed();
+    repr.as_mut()[0] &= 0x1f;
+    let repr = repr.as_ref();
+
+    writer.write_all(&fq_to_montgomery(&repr[N8Q..2 * N8Q]))?;
+    writer.write_all(&fq_to_montgomery(&repr[0..N8Q]))?;
+    writer.write_all(&fq_to_montgomery(&repr[3 * N8Q..4 * N8Q]))?;
+    writer.write_all(&fq_to_montgomery(&repr[2 * N8Q..3 * N8Q]))
+}
+
+fn read_g1(bytes: &[u8]) -> io::Result<bls12_381::G1Affine> {
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(bls12_381::G1Affine::identity());
+    }
+
+    let mut repr = [0u8; G1_SIZE];
+    repr[0..N8Q].copy_from_slice(&fq_from_montgomery(&bytes[0..N8Q])?);
+    repr[N8Q..].copy_from_slice(&fq_from_montgomery(&bytes[N8Q..2 * N8Q])?);
+
+    Option::from(bls12_381::G1Affine::from_uncompressed(&repr))
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "invalid point"))
+}
+
+fn read_g2(bytes: &[u8]) -> io::Result<bls12_381::G2Affine> {
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(bls12_381::G2Affine::identity());
+    }
+
+    // The reverse of the reordering in `write_g2`
+    let mut repr = [0u8; G2_SIZE];
+    repr[0..N8Q].copy_from_slice(&fq_from_montgomery(&bytes[N8Q..2 * N8Q])?);
+    repr[N8Q..2 * N8Q].copy_from_slice(&fq_from_montgomery(&bytes[0..N8Q])?);
+    repr[2 * N8Q..3 * N8Q].copy_from_slice(&fq_from_montgomery(&bytes[3 * N8Q..4 * N8Q])?);
+    repr[3 * N8Q..].copy_from_slice(&fq_from_montgomery(&bytes[2 * N8Q..3 * N8Q])?);
+
+    Option::from(bls12_381::G2Affine::from_uncompressed(&repr))
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "invalid point"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Reads the points of a section that holds nothing else.
+fn read_points<G>(
+    section: &[u8],
+    size: usize,
+    read: fn(&[u8]) -> io::Result<G>,
+) -> io::Result<Vec<G>> {
+    if !section.len().is_multiple_of(size) {
+        return Err(invalid("truncated section"));
+    }
+
+    section.chunks(size).map(read).collect()
+}
+
+/// The reverse of `expand`, filtering points at infinity away.
+fn nonzero<G: PrimeCurveAffine>(query: Vec<G>) -> Vec<G> {
+    query
+        .into_iter()
+        .filter(|p| !bool::from(p.is_identity()))
+        .collect()
+}
+
+/// The root of unity of order `2^exp` derived from `root`, one of
+/// order `2^S`.
+fn root_of_unity(root: Scalar, exp: u32) -> Scalar {
+    (exp..Scalar::S).fold(root, |w, _| w.square())
+}
+
+/// The root of unity of order `2^S` snarkjs uses. ffjavascript
+/// derives its roots from the smallest quadratic non-residue, 5,
+/// where bellman's are powers of the multiplicative generator, 7, so
+/// the two put the constraints of a circuit at different points.
+fn snarkjs_root_of_unity() -> Scalar {
+    // 5^t, for the odd t with r - 1 = t * 2^S
+    let repr = (-Scalar::ONE).to_repr();
+    let mut t = [0u64; 4];
+    for (limb, bytes) in t.iter_mut().zip(repr.as_ref().chunks(8)) {
+        *limb = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    for i in 0..4 {
+        t[i] = (t[i] >> Scalar::S) | t.get(i + 1).map_or(0, |next| next << (64 - Scalar::S));
+    }
+
+    Scalar::from(5).pow_vartime(&t)
+}
+
+/// The `e < 2^exp` with `base^e = x`, for `base` of order `2^exp`
+/// and `x` one of its powers, found a bit at a time.
+fn dlog(base: Scalar, x: Scalar, exp: u32) -> usize {
+    let base_inv = base.invert().unwrap();
+    let mut e = 0;
+
+    for bit in 0..exp {
+        // x / base^e has order at most 2^(exp - bit); it's exactly
+        // that unless the next bit of e is known to be 0
+        let y = x * base_inv.pow_vartime(&[e as u64, 0, 0, 0]);
+        if (bit + 1..exp).fold(y, |y, _| y.square()) != Scalar::ONE {
+            e |= 1 << bit;
+        }
+    }
+
+    e
+}
+
+/// How the domain of size `2^exp` of bellman relates to that of
+/// snarkjs.
+struct Domain {
+    exp: u32,
+    /// `ω_s = ω_b^to_snarkjs`, for the roots `ω_b` and `ω_s` of
+    /// bellman and snarkjs.
+    to_snarkjs: usize,
+    /// `ω_b = ω_s^from_bellman`: bellman's constraint `j` sits at
+    /// snarkjs' constraint `j * from_bellman`.
+    from_bellman: usize,
+    /// The root of unity of order `2^(exp + 1)` of snarkjs, which
+    /// shifts its domain to the odd coset it evaluates H on.
+    shift: Scalar,
+}
+
+impl Domain {
+    fn new(exp: u32) -> Self {
+        let bellman = root_of_unity(Scalar::ROOT_OF_UNITY, exp);
+        let snarkjs = root_of_unity(snarkjs_root_of_unity(), exp);
+
+        Domain {
+            exp,
+            to_snarkjs: dlog(bellman, snarkjs, exp),
+            from_bellman: dlog(snarkjs, bellman, exp),
+            shift: root_of_unity(snarkjs_root_of_unity(), exp + 1),
+        }
+    }
+
+    fn size(&self) -> usize {
+        1 << self.exp
+    }
+
+    /// Where snarkjs has bellman's constraint `j`.
+    fn constraint(&self, j: usize) -> usize {
+        (j * self.from_bellman) % self.size()
+    }
+
+    /// Converts bellman's H query, `tau^k * Z(tau) / delta` for
+    /// `k < n - 1`, into the one snarkjs' prover takes: the Lagrange
+    /// basis of the odd coset `shift * ω_s^i` of the domain of size
+    /// `2n`, over delta.
+    ///
+    /// Those Lagrange polynomials vanish on the domain, so they're
+    /// `Z(x) * q_i(x)`, and `q_i` is `-1/2` times the Lagrange
+    /// polynomial of the coset of size `n`: `Z` is `-2` on it. The
+    /// coefficients of `q_i` are `-1/(2n) * (shift * ω_s^i)^-k`, which
+    /// an inverse FFT gives. bellman's query lacks the `k = n - 1`
+    /// term, but the quotient a prover multiplies it with has no
+    /// such term either, so proofs come out the same.
+    fn h_to_snarkjs(&self, h: &[G1Affine]) -> Vec<G1Affine> {
+        let n = self.size();
+        let shift_inv = self.shift.invert().unwrap();
+        let minus_half = -Scalar::from(2).invert().unwrap();
+
+        let mut factor = minus_half;
+        let mut coeffs = Vec::with_capacity(n);
+        for k in 0..n {
+            let p = h.get(k).map_or(G1Projective::identity(), |p| p * factor);
+            coeffs.push(Projective(p));
+            factor *= shift_inv;
+        }
+
+        // The domain is exactly as large as the points, so this can't
+        // fail
+        let mut domain = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        domain.ifft(&Worker::new());
+        let evals = domain.into_coeffs();
+
+        // ω_s^-ik is ω_b^-(i * to_snarkjs)k
+        let points: Vec<G1Projective> =
+            (0..n).map(|i| evals[(i * self.to_snarkjs) % n].0).collect();
+        let mut affine = vec![G1Affine::identity(); n];
+        G1Projective::batch_normalize(&points, &mut affine);
+        affine
+    }
+
+    /// The reverse of `h_to_snarkjs`. Fails if the H query has a
+    /// `k = n - 1` term, which bellman's can't hold.
+    fn h_from_snarkjs(&self, h: &[G1Affine]) -> io::Result<Vec<G1Affine>> {
+        let n = self.size();
+        let minus_two = -Scalar::from(2);
+
+        let mut evals = vec![Projective(G1Projective::identity()); n];
+        for (i, p) in h.iter().enumerate() {
+            evals[(i * self.to_snarkjs) % n] = Projective(p * minus_two);
+        }
+
+        let mut domain = EvaluationDomain::from_coeffs(evals).unwrap();
+        domain.fft(&Worker::new());
+        let coeffs = domain.into_coeffs();
+
+        let mut factor = Scalar::ONE;
+        let mut points = Vec::with_capacity(n);
+        for p in coeffs {
+            points.push(p.0 * factor);
+            factor *= self.shift;
+        }
+        if !bool::from(points.pop().unwrap().is_identity()) {
+            return Err(invalid("H query isn't one bellman can have"));
+        }
+
+        let mut affine = vec![G1Affine::identity(); n - 1];
+        G1Projective::batch_normalize(&points, &mut affine);
+        Ok(affine)
+    }
+}
+
+/// Reads the sections of a zkey file, by id.
+fn read_sections<R: Read>(mut reader: R) -> io::Result<HashMap<u32, Vec<u8>>> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"zkey" {
+        return Err(invalid("not a zkey file"));
+    }
+    reader.read_u32::<LittleEndian>()?;
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let id = reader.read_u32::<LittleEndian>()?;
+        let size = reader.read_u64::<LittleEndian>()?;
+
+        let mut section = vec![];
+        (&mut reader).take(size).read_to_end(&mut section)?;
+        if section.len() as u64 != size {
+            return Err(invalid("truncated section"));
+        }
+        sections.insert(id, section);
+    }
+
+    Ok(sections)
+}
+
+fn write_section_header<W: Write>(writer: &mut W, id: u32, size: usize) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(id)?;
+    writer.write_u64::<LittleEndian>(size as u64)
+}
+
+impl MPCParameters<Bls12> {
+    /// Export these parameters in the snarkjs `.zkey` format, for
+    /// proving with snarkjs. The circuit the parameters were created
+    /// for must be supplied, as snarkjs needs its constraint
+    /// matrices, and A/B queries that still contain the points
+    /// bellman filters away.
+    ///
+    /// snarkjs puts the constraints at other points of the domain
+    /// than bellman does, so they're written in the order that puts
+    /// each where bellman's queries expect it, and the H query is
+    /// converted to the basis snarkjs' prover takes (the Lagrange
+    /// basis of the odd coset of a domain twice the size). The
+    /// witness snarkjs proves with must list the variables in the
+    /// order the circuit allocates them: one, the public inputs,
+    /// then the auxiliary variables. The transcript scheme is written
+    /// in a section of its own, which snarkjs ignores.
+    pub fn write_zkey<C, W>(&self, circuit: C, mut writer: W) -> io::Result<()>
+    where
+        C: Circuit<bls12_381::Scalar>,
+        W: Write,
+    {
+        let assembly = KeypairAssembly::synthesize(circuit)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let params = &self.params;
+        let num_vars = assembly.num_inputs + assembly.num_aux;
+        let num_public = assembly.num_inputs - 1;
+        let domain_size = params.h.len() + 1;
+
+        if params.vk.ic.len() != assembly.num_inputs
+            || params.l.len() != assembly.num_aux
+            || !domain_size.is_power_of_two()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "parameters don't match the circuit",
+            ));
+        }
+        let domain = Domain::new(domain_size.trailing_zeros());
+
+        let at = assembly.at_inputs.iter().chain(assembly.at_aux.iter());
+        let bt = assembly.bt_inputs.iter().chain(assembly.bt_aux.iter());
+        let a_density: Vec<bool> = at.clone().map(|t| !t.is_empty()).collect();
+        let b_density: Vec<bool> = bt.clone().map(|t| !t.is_empty()).collect();
+
+        let a = expand(&params.a, &a_density)?;
+        let b_g1 = expand(&params.b_g1, &b_density)?;
+        let b_g2 = expand(&params.b_g2, &b_density)?;
+
+        let num_coeffs: usize = at.clone().chain(bt.clone()).map(|t| t.len()).sum();
+
+        writer.write_all(b"zkey")?;
+        writer.write_u32::<LittleEndian>(1)?;
+        writer.write_u32::<LittleEndian>(11)?;
+
+        // Header: the protocol (1 is Groth16)
+        write_section_header(&mut writer, 1, 4)?;
+        writer.write_u32::<LittleEndian>(1)?;
+
+        // Groth16 header
+        write_section_header(
+            &mut writer,
+            2,
+            4 + N8Q + 4 + N8R + 12 + G1_SIZE * 3 + G2_SIZE * 3,
+        )?;
+        writer.write_u32::<LittleEndian>(N8Q as u32)?;
+        for limb in &Q {
+            writer.write_u64::<LittleEndian>(*limb)?;
+        }
+        writer.write_u32::<LittleEndian>(N8R as u32)?;
+        for limb in &R {
+            writer.write_u64::<LittleEndian>(*limb)?;
+        }
+        writer.write_u32::<LittleEndian>(num_vars as u32)?;
+        writer.write_u32::<LittleEndian>(num_public as u32)?;
+        writer.write_u32::<LittleEndian>(domain_size as u32)?;
+        write_g1(&mut writer, &params.vk.alpha_g1)?;
+        write_g1(&mut writer, &params.vk.beta_g1)?;
+        write_g2(&mut writer, &params.vk.beta_g2)?;
+        write_g2(&mut writer, &params.vk.gamma_g2)?;
+        write_g1(&mut writer, &params.vk.delta_g1)?;
+        write_g2(&mut writer, &params.vk.delta_g2)?;
+
+        // IC
+        write_section_header(&mut writer, 3, params.vk.ic.len() * G1_SIZE)?;
+        for p in &params.vk.ic {
+            write_g1(&mut writer, p)?;
+        }
+
+        // Coefficients of the A and B matrices
+        write_section_header(&mut writer, 4, 4 + num_coeffs * (12 + N8R))?;
+        writer.write_u32::<LittleEndian>(num_coeffs as u32)?;
+        for (matrix, polys) in [(0u32, at), (1u32, bt)] {
+            for (signal, poly) in polys.enumerate() {
+                for &(coeff, constraint) in poly {
+                    writer.write_u32::<LittleEndian>(matrix)?;
+                    writer.write_u32::<LittleEndian>(domain.constraint(constraint) as u32)?;
+                    writer.write_u32::<LittleEndian>(signal as u32)?;
+                    write_coeff(&mut writer, coeff)?;
+                }
+            }
+        }
+
+        // A, B1, B2 and C (bellman's L query)
+        write_section_header(&mut writer, 5, num_vars * G1_SIZE)?;
+        for p in &a {
+            write_g1(&mut writer, p)?;
+        }
+        write_section_header(&mut writer, 6, num_vars * G1_SIZE)?;
+        for p in &b_g1 {
+            write_g1(&mut writer, p)?;
+        }
+        write_section_header(&mut writer, 7, num_vars * G2_SIZE)?;
+        for p in &b_g2 {
+            write_g2(&mut writer, p)?;
+        }
+        write_section_header(&mut writer, 8, params.l.len() * G1_SIZE)?;
+        for p in params.l.iter() {
+            write_g1(&mut writer, p)?;
+        }
+
+        // H, in snarkjs' basis
+        write_section_header(&mut writer, 9, domain_size * G1_SIZE)?;
+        for p in &domain.h_to_snarkjs(&params.h) {
+            write_g1(&mut writer, p)?;
+        }
+
+        // Contributions
+        let contribution_size = G1_SIZE * 3 + G2_SIZE + 64 + 8;
+        write_section_header(
+            &mut writer,
+            10,
+            64 + 4 + self.contributions.len() * contribution_size,
+        )?;
+        writer.write_all(&self.cs_hash)?;
+        writer.write_u32::<LittleEndian>(self.contributions.len() as u32)?;
+        for pubkey in &self.contributions {
+            write_g1(&mut writer, &pubkey.delta_after)?;
+            write_g1(&mut writer, &pubkey.s)?;
+            write_g1(&mut writer, &pubkey.s_delta)?;
+            write_g2(&mut writer, &pubkey.r_delta)?;
+            writer.write_all(&pubkey.transcript)?;
+
+            // A regular contribution, with no parameters
+            writer.write_u32::<LittleEndian>(0)?;
+            writer.write_u32::<LittleEndian>(0)?;
+        }
+
+        let scheme = self.transcript_scheme.id();
+        write_section_header(&mut writer, SCHEME_SECTION, scheme.len())?;
+        scheme.write(&mut writer)
+    }
+
+    /// The name `write_zkey` had before `read_zkey` was added.
+    #[deprecated(note = "use `write_zkey`")]
+    pub fn export_zkey<C, W>(&self, circuit: C, writer: W) -> io::Result<()>
+    where
+        C: Circuit<bls12_381::Scalar>,
+        W: Write,
+    {
+        self.write_zkey(circuit, writer)
+    }
+    /// Import parameters that `write_zkey` exported, after they've
+    /// been round the snarkjs tooling. Every point is checked to be on
+    /// the curve and in the prime order subgroup, points at infinity
+    /// are filtered away from the A/B queries as bellman expects, and
+    /// the H query is converted back to bellman's basis.
+    ///
+    /// This isn't an import of zkeys snarkjs made: their constraints
+    /// sit at snarkjs' points of the domain rather than bellman's,
+    /// and their contributions derive `r` from the transcript with
+    /// snarkjs' own hash to G2, so they can't be verified here. Files
+    /// without the section `write_zkey` records the transcript
+    /// scheme in are rejected, and a snarkjs contribution to one
+    /// drops it, so ceremonies can't mix the two toolchains.
+    pub fn read_zkey<R: Read>(reader: R) -> io::Result<MPCParameters<Bls12>> {
+        let mut sections = read_sections(reader)?;
+        let transcript_scheme = match sections.remove(&SCHEME_SECTION) {
+            Some(scheme) => SchemeId::read(&scheme[..])?.scheme()?,
+            None => return Err(invalid("not a zkey written by write_zkey")),
+        };
+
+        let mut section = |id| sections.remove(&id).ok_or(invalid("missing section"));
+
+        if section(1)? != 1u32.to_le_bytes() {
+            return Err(invalid("not a Groth16 zkey"));
+        }
+
+        let header = section(2)?;
+        let mut h = &header[..];
+        let mut fields_match = h.read_u32::<LittleEndian>()? == N8Q as u32;
+        for limb in &Q {
+            fields_match &= h.read_u64::<LittleEndian>()? == *limb;
+        }
+        fields_match &= h.read_u32::<LittleEndian>()? == N8R as u32;
+        for limb in &R {
+            fields_match &= h.read_u64::<LittleEndian>()? == *limb;
+        }
+        if !fields_match {
+            return Err(invalid("not a BLS12-381 zkey"));
+        }
+
+        let _num_vars = h.read_u32::<LittleEndian>()?;
+        let _num_public = h.read_u32::<LittleEndian>()?;
+        let domain_size = h.read_u32::<LittleEndian>()? as usize;
+        if h.len() != G1_SIZE * 3 + G2_SIZE * 3 {
+            return Err(invalid("truncated header"));
+        }
+        let alpha_g1 = read_g1(&h[0..G1_SIZE])?;
+        let beta_g1 = read_g1(&h[G1_SIZE..2 * G1_SIZE])?;
+        let h = &h[2 * G1_SIZE..];
+        let beta_g2 = read_g2(&h[0..G2_SIZE])?;
+        let gamma_g2 = read_g2(&h[G2_SIZE..2 * G2_SIZE])?;
+        let h = &h[2 * G2_SIZE..];
+        let delta_g1 = read_g1(&h[0..G1_SIZE])?;
+        let delta_g2 = read_g2(&h[G1_SIZE..])?;
+
+        let ic = read_points(&section(3)?, G1_SIZE, read_g1)?;
+
+        let a = nonzero(read_points(&section(5)?, G1_SIZE, read_g1)?);
+        let b_g1 = nonzero(read_points(&section(6)?, G1_SIZE, read_g1)?);
+        let b_g2 = nonzero(read_points(&section(7)?, G2_SIZE, read_g2)?);
+        let l = read_points(&section(8)?, G1_SIZE, read_g1)?;
+
+        let h = read_points(&section(9)?, G1_SIZE, read_g1)?;
+        if !domain_size.is_power_of_two() || h.len() != domain_size {
+            return Err(invalid("H query doesn't match the domain"));
+        }
+        let h = Domain::new(domain_size.trailing_zeros()).h_from_snarkjs(&h)?;
+
+        let contributions = section(10)?;
+        let mut c = &contributions[..];
+        let mut cs_hash = [0u8; 64];
+        c.read_exact(&mut cs_hash)?;
+
+        let count = c.read_u32::<LittleEndian>()?;
+        let mut pubkeys = vec![];
+        for _ in 0..count {
+            let mut points = [0u8; G1_SIZE * 3 + G2_SIZE];
+            c.read_exact(&mut points)?;
+            let mut transcript = [0u8; 64];
+            c.read_exact(&mut transcript)?;
+
+            let pubkey: PublicKey<Bls12> = PublicKey {
+                delta_after: read_g1(&points[0..G1_SIZE])?,
+                s: read_g1(&points[G1_SIZE..2 * G1_SIZE])?,
+                s_delta: read_g1(&points[2 * G1_SIZE..3 * G1_SIZE])?,
+                r_delta: read_g2(&points[3 * G1_SIZE..])?,
+                transcript,
+            };
+            if bool::from(pubkey.delta_after.is_identity())
+                || bool::from(pubkey.s.is_identity())
+                || bool::from(pubkey.s_delta.is_identity())
+                || bool::from(pubkey.r_delta.is_identity())
+            {
+                return Err(invalid("point at infinity"));
+            }
+            pubkeys.push(pubkey);
+
+            // The type of contribution, and its parameters
+            c.read_u32::<LittleEndian>()?;
+            let params_len = c.read_u32::<LittleEndian>()? as usize;
+            if c.len() < params_len {
+                return Err(invalid("truncated contribution"));
+            }
+            c = &c[params_len..];
+        }
+
+        Ok(MPCParameters {
+            params: Parameters {
+                vk: VerifyingKey {
+                    alpha_g1,
+                    beta_g1,
+                    beta_g2,
+                    gamma_g2,
+                    delta_g1,
+                    delta_g2,
+                    ic,
+                },
+                h: Arc::new(h),
+                l: Arc::new(l),
+                a: Arc::new(a),
+                b_g1: Arc::new(b_g1),
+                b_g2: Arc::new(b_g2),
+            },
+            cs_hash,
+            contributions: pubkeys,
+            circuit_fingerprint: None,
+            transcript_scheme,
+            #[cfg(feature = "attest")]
+            verifier_attestations: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase1::RadixSource;
+    use crate::testing::{insecure_radix, RandomCircuit};
+    use bellman::groth16::{prepare_verifying_key, verify_proof, Proof};
+    use bellman::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+    use bls12_381::{G2Affine, G2Projective};
+
+    /// Records the values a circuit assigns, in the order of the
+    /// witness snarkjs proves with.
+    struct Witness {
+        inputs: Vec<Scalar>,
+        aux: Vec<Scalar>,
+    }
+
+    impl ConstraintSystem<Scalar> for Witness {
+        type Root = Self;
+
+        fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+        where
+            F: FnOnce() -> Result<Scalar, SynthesisError>,
+            A: FnOnce() -> AR,
+            AR: Into<String>,
+        {
+            self.aux.push(f()?);
+            Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+        }
+
+        fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+        where
+            F: FnOnce() -> Result<Scalar, SynthesisError>,
+            A: FnOnce() -> AR,
+            AR: Into<String>,
+        {
+            self.inputs.push(f()?);
+            Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+        }
+
+        fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+        where
+            A: FnOnce() -> AR,
+            AR: Into<String>,
+            LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+            LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+            LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        {
+        }
+
+        fn push_namespace<NR, N>(&mut self, _: N)
+        where
+            NR: Into<String>,
+            N: FnOnce() -> NR,
+        {
+        }
+
+        fn pop_namespace(&mut self) {}
+
+        fn get_root(&mut self) -> &mut Self::Root {
+            self
+        }
+    }
+
+    /// Proves as snarkjs' `groth16 prove` does, from nothing but the
+    /// zkey and the witness `w`: the constraints are put at snarkjs'
+    /// roots of unity, and the quotient is evaluated on its odd
+    /// coset. The FFTs are done the slow way, so that they don't
+    /// share anything with `Domain`.
+    fn snarkjs_prove(zkey: &[u8], w: &[Scalar], r: Scalar, s: Scalar) -> Proof<Bls12> {
+        let sections = read_sections(zkey).unwrap();
+        let g1s = |id| read_points(&sections[&id], G1_SIZE, read_g1).unwrap();
+
+        let mut header = &sections[&2][4 + N8Q + 4 + N8R..];
+        header.read_u32::<LittleEndian>().unwrap();
+        let num_public = header.read_u32::<LittleEndian>().unwrap() as usize;
+        let n = header.read_u32::<LittleEndian>().unwrap() as usize;
+        let alpha_g1 = read_g1(&header[..G1_SIZE]).unwrap();
+        let beta_g1 = read_g1(&header[G1_SIZE..]).unwrap();
+        let beta_g2 = read_g2(&header[2 * G1_SIZE..]).unwrap();
+        let delta_g1 = read_g1(&header[2 * G1_SIZE + 2 * G2_SIZE..]).unwrap();
+        let delta_g2 = read_g2(&header[3 * G1_SIZE + 2 * G2_SIZE..]).unwrap();
+
+        // A and B at each constraint, and C = A * B
+        let r2_inv = Scalar::from(2)
+            .pow_vartime(&[512, 0, 0, 0])
+            .invert()
+            .unwrap();
+        let (mut a_t, mut b_t) = (vec![Scalar::ZERO; n], vec![Scalar::ZERO; n]);
+        let mut coeffs = &sections[&4][4..];
+        while !coeffs.is_empty() {
+            let matrix = coeffs.read_u32::<LittleEndian>().unwrap();
+            let constraint = coeffs.read_u32::<LittleEndian>().unwrap() as usize;
+            let signal = coeffs.read_u32::<LittleEndian>().unwrap() as usize;
+            let mut repr = [0u8; N8R];
+            coeffs.read_exact(&mut repr).unwrap();
+            let coeff = Scalar::from_repr(repr).unwrap() * r2_inv;
+
+            let t = if matrix == 0 { &mut a_t } else { &mut b_t };
+            t[constraint] += coeff * w[signal];
+        }
+        let c_t: Vec<Scalar> = a_t.iter().zip(&b_t).map(|(a, b)| a * b).collect();
+
+        // Interpolated over snarkjs' domain, and evaluated on its odd
+        // coset
+        let exp = n.trailing_zeros();
+        let omega = root_of_unity(snarkjs_root_of_unity(), exp);
+        let shift = root_of_unity(snarkjs_root_of_unity(), exp + 1);
+        let pow = |x: Scalar, e: usize| x.pow_vartime(&[e as u64, 0, 0, 0]);
+        let odd = |t: &[Scalar]| -> Vec<Scalar> {
+            let n_inv = Scalar::from(n as u64).invert().unwrap();
+            let poly: Vec<Scalar> = (0..n)
+                .map(|k| {
+                    let sum: Scalar = (0..n).map(|j| t[j] * pow(omega, n - (j * k) % n)).sum();
+                    sum * n_inv
+                })
+                .collect();
+
+            (0..n)
+                .map(|i| {
+                    let x = shift * pow(omega, i);
+                    poly.iter().rev().fold(Scalar::ZERO, |acc, c| acc * x + c)
+                })
+                .collect()
+        };
+        let (a_odd, b_odd, c_odd) = (odd(&a_t), odd(&b_t), odd(&c_t));
+        let h: Vec<Scalar> = (0..n).map(|i| a_odd[i] * b_odd[i] - c_odd[i]).collect();
+
+        let msm = |points: &[G1Affine], scalars: &[Scalar]| -> G1Projective {
+            points.iter().zip(scalars).map(|(p, s)| p * s).sum()
+        };
+        let b_g2 = read_points(&sections[&7], G2_SIZE, read_g2).unwrap();
+
+        let pi_a = G1Projective::from(alpha_g1) + msm(&g1s(5), w) + delta_g1 * r;
+        let pi_b1 = G1Projective::from(beta_g1) + msm(&g1s(6), w) + delta_g1 * s;
+        let pi_b = G2Projective::from(beta_g2)
+            + b_g2.iter().zip(w).map(|(p, s)| p * s).sum::<G2Projective>()
+            + delta_g2 * s;
+        let pi_c = msm(&g1s(8), &w[num_public + 1..]) + msm(&g1s(9), &h) + pi_a * s + pi_b1 * r
+            - delta_g1 * (r * s);
+
+        Proof {
+            a: G1Affine::from(pi_a),
+            b: G2Affine::from(pi_b),
+            c: G1Affine::from(pi_c),
+        }
+    }
+
+    #[test]
+    fn snarkjs_proves_with_exported_parameters() {
+        let circuit = RandomCircuit {
+            constraints: 10,
+            inputs: 2,
+            aux: 6,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(4, [4; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+
+        let mut zkey = vec![];
+        params.write_zkey(circuit, &mut zkey).unwrap();
+
+        let mut witness = Witness {
+            inputs: vec![Scalar::ONE],
+            aux: vec![],
+        };
+        circuit.synthesize(&mut witness).unwrap();
+        let w = [&witness.inputs[..], &witness.aux[..]].concat();
+
+        let proof = snarkjs_prove(&zkey, &w, Scalar::from(3), Scalar::from(4));
+        let pvk = prepare_verifying_key(&params.get_params().vk);
+        verify_proof(&pvk, &proof, &witness.inputs[1..]).unwrap();
+
+        // The domains differ, so this isn't true by accident
+        assert_ne!(Domain::new(4).from_bellman, 1);
+    }
+
+    #[test]
+    fn zkeys_round_trip() {
+        let circuit = RandomCircuit {
+            constraints: 10,
+            inputs: 2,
+            aux: 6,
+        };
+        let mut radix = vec![];
+        insecure_radix::<Bls12>(4, [4; 32])
+            .write(&mut radix)
+            .unwrap();
+        let mut params =
+            MPCParameters::new_from_source(circuit, RadixSource::Bytes(&radix)).unwrap();
+        params.contribute_with_seed([1; 32]);
+
+        let mut zkey = vec![];
+        params.write_zkey(circuit, &mut zkey).unwrap();
+        let read = MPCParameters::read_zkey(&zkey[..]).unwrap();
+        assert!(read == params);
+
+        // Without the scheme section, the file may be from snarkjs
+        let mut sections = read_sections(&zkey[..]).unwrap();
+        sections.remove(&SCHEME_SECTION);
+        let mut stripped = b"zkey".to_vec();
+        stripped.write_u32::<LittleEndian>(1).unwrap();
+        stripped
+            .write_u32::<LittleEndian>(sections.len() as u32)
+            .unwrap();
+        for (id, section) in sections {
+            write_section_header(&mut stripped, id, section.len()).unwrap();
+            stripped.extend(section);
+        }
+        assert!(MPCParameters::read_zkey(&stripped[..]).is_err());
+    }
+}